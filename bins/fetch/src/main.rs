@@ -9,7 +9,7 @@ use std::{
 use alloy_consensus::constants::KECCAK_EMPTY;
 use alloy_primitives::{Address, B256};
 use alloy_provider::{Provider, ProviderBuilder, RootProvider, network::Ethereum};
-use alloy_rpc_types_eth::BlockId;
+use alloy_rpc_types_eth::{Block, BlockId};
 use clap::Parser;
 use color_eyre::eyre::{Result, WrapErr, eyre};
 use flate2::{Compression, bufread::GzDecoder, write::GzEncoder};
@@ -49,7 +49,7 @@ where
     C::Transaction: Serialize,
 {
     // Retrieve block from provider.
-    let block = provider
+    let block: Block<C::Transaction> = provider
         .get_block(block_id)
         .full()
         .await
@@ -57,6 +57,18 @@ where
         .ok_or_else(|| eyre!("No block found for ID: {block_id:?}"))?
         .into();
 
+    // Retrieve ommer headers to reward their beneficiaries.
+    let mut ommers = Vec::with_capacity(block.uncles.len());
+    for index in 0..block.uncles.len() {
+        let ommer: Block<C::Transaction> = provider
+            .get_uncle(block_id, index as u64)
+            .await
+            .context("Failed to fetch ommer from provider")?
+            .ok_or_else(|| eyre!("No ommer found at index {index}"))?
+            .into();
+        ommers.push(ommer.header);
+    }
+
     let spec_id = chain
         .get_block_spec(&block.header)
         .map_err(|e| {
@@ -71,7 +83,7 @@ where
 
     // Execute the block and track the pre-state in the RPC storage.
     Pevm::default()
-        .execute(&chain, &storage, &block, NonZeroUsize::MIN, true)
+        .execute_with_ommers(&chain, &storage, &block, &ommers, NonZeroUsize::MIN, true)
         .map_err(|e| eyre!("Failed to execute block: {e:?}"))?;
 
     let block_dir = format!("{data_dir}/blocks/{}", block.header.number);
@@ -84,6 +96,13 @@ where
         File::create(format!("{block_dir}/block.json")).context("Failed to create block file")?;
     serde_json::to_writer(block_file, &block).context("Failed to write block to file")?;
 
+    // Write ommer headers to disk.
+    if !ommers.is_empty() {
+        let ommers_file = File::create(format!("{block_dir}/ommers.json"))
+            .context("Failed to create ommers file")?;
+        serde_json::to_writer(ommers_file, &ommers).context("Failed to write ommers to file")?;
+    }
+
     // Populate bytecodes and state from RPC storage.
     // TODO: Deduplicate logic with [for_each_block_from_disk] when there is more usage
     let bytecodes_path = format!("{data_dir}/bytecodes.bincode.gz");
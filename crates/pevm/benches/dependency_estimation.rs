@@ -0,0 +1,126 @@
+//! Report the re-executions that estimating the dependencies between
+//! transactions saves on the snapshotted blocks, and benchmark executing them
+//! in parallel with and without the estimates.
+
+use std::{hint::black_box, num::NonZeroUsize};
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use pevm::{
+    BlockFeatures, ExecutionPolicy, ExecutionStrategy, Pevm,
+    chain::{PevmChain, PevmEthereum, PevmRise},
+};
+
+// Better project structure
+
+/// common module
+#[path = "../tests/common/mod.rs"]
+pub mod common;
+
+/// The number of parallel executions to average the statistics over.
+const RUNS: usize = 20;
+
+/// The number of workers, fixed as the re-executions depend on it.
+const CONCURRENCY_LEVEL: usize = 8;
+
+/// Execute every block in parallel, as the statistics are only collected
+/// for blocks executed in parallel.
+#[derive(Debug)]
+struct ParallelPolicy;
+
+impl ExecutionPolicy for ParallelPolicy {
+    fn decide(&self, _: &BlockFeatures, concurrency_level: NonZeroUsize) -> ExecutionStrategy {
+        ExecutionStrategy::Parallel(concurrency_level)
+    }
+}
+
+/// Print the re-executions and validation aborts per run of each block of
+/// [`chain_name`] without and with the estimates, then benchmark both.
+pub fn bench_chain<C>(c: &mut Criterion, chain: &C, chain_name: &str)
+where
+    C: PevmChain + Send + Sync,
+    C::Transaction: serde::de::DeserializeOwned,
+{
+    let concurrency_level = NonZeroUsize::new(CONCURRENCY_LEVEL).unwrap();
+    let mut pevms = [
+        Pevm::default()
+            .with_policy(ParallelPolicy)
+            .with_stats()
+            .without_dependency_estimation(),
+        Pevm::default().with_policy(ParallelPolicy).with_stats(),
+    ];
+
+    common::for_each_block_from_disk(chain_name, |block, ommers, storage, _| {
+        // Blocks whose ommer headers are not snapshotted are executed without
+        // the ommer rewards.
+        let execute = |pevm: &mut Pevm| {
+            if ommers.len() == block.uncles.len() {
+                pevm.execute_with_ommers(
+                    black_box(chain),
+                    black_box(&storage),
+                    black_box(&block),
+                    black_box(&ommers),
+                    black_box(concurrency_level),
+                    false,
+                )
+            } else {
+                pevm.execute(
+                    black_box(chain),
+                    black_box(&storage),
+                    black_box(&block),
+                    black_box(concurrency_level),
+                    false,
+                )
+            }
+        };
+
+        let [without, with] = pevms.each_mut().map(|pevm| {
+            let (mut re_executions, mut validation_aborts) = (0, 0);
+            for _ in 0..RUNS {
+                assert!(execute(pevm).is_ok());
+                let stats = pevm.take_stats().unwrap();
+                re_executions += stats.re_executions();
+                validation_aborts += stats.validation_aborts;
+            }
+            (
+                re_executions as f64 / RUNS as f64,
+                validation_aborts as f64 / RUNS as f64,
+            )
+        });
+        println!(
+            "{chain_name} block {} ({} txs, {concurrency_level} workers): {:.1} -> {:.1} re-executions, {:.1} -> {:.1} validation aborts per run without -> with estimates",
+            block.header.number,
+            block.transactions.len(),
+            without.0,
+            with.0,
+            without.1,
+            with.1,
+        );
+
+        let mut group = c.benchmark_group(format!(
+            "Estimation {chain_name} {}({} txs)",
+            block.header.number,
+            block.transactions.len(),
+        ));
+        let [without_estimates, with_estimates] = &mut pevms;
+        group.bench_function("Parallel (no estimates)", |b| {
+            b.iter(|| execute(without_estimates))
+        });
+        group.bench_function("Parallel", |b| b.iter(|| execute(with_estimates)));
+        group.finish();
+    });
+}
+
+/// Benchmark the Rise and Ethereum snapshots.
+pub fn criterion_benchmark(c: &mut Criterion) {
+    bench_chain(c, &PevmRise, "rise");
+    bench_chain(c, &PevmEthereum::mainnet(), "ethereum");
+}
+
+// HACK: we can't document public items inside of the macro
+#[allow(missing_docs)]
+mod benches {
+    use super::*;
+    criterion_group!(benches, criterion_benchmark);
+}
+
+criterion_main!(benches::benches);
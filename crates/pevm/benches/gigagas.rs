@@ -36,8 +36,15 @@ const GIGA_GAS: u64 = 1_000_000_000;
 #[global_allocator]
 static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
-/// Runs a benchmark for executing a set of transactions on a given blockchain state.
-pub fn bench(c: &mut Criterion, name: &str, storage: InMemoryStorage, txs: Vec<TxEnv>) {
+/// Runs a benchmark for executing a set of transactions on a given blockchain state,
+/// also in parallel with commutative writes if `commutative_writes`.
+pub fn bench(
+    c: &mut Criterion,
+    name: &str,
+    storage: InMemoryStorage,
+    txs: Vec<TxEnv>,
+    commutative_writes: bool,
+) {
     let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
     let chain = PevmEthereum::mainnet();
     let spec_id = SpecId::OSAKA;
@@ -69,6 +76,22 @@ pub fn bench(c: &mut Criterion, name: &str, storage: InMemoryStorage, txs: Vec<T
             .expect("must benchmark successful runs")
         })
     });
+    if commutative_writes {
+        let mut pevm = Pevm::default().with_commutative_writes();
+        group.bench_function("Parallel (commutative writes)", |b| {
+            b.iter(|| {
+                pevm.execute_revm_parallel(
+                    black_box(&chain),
+                    black_box(&storage),
+                    black_box(spec_id),
+                    black_box(block_env.clone()),
+                    black_box(txs.clone()),
+                    black_box(concurrency_level),
+                )
+                .expect("must benchmark successful runs")
+            })
+        });
+    }
     group.finish();
 }
 
@@ -104,6 +127,7 @@ pub fn bench_raw_transfers(c: &mut Criterion) {
                 }
             })
             .collect::<Vec<_>>(),
+        false,
     );
 }
 
@@ -117,6 +141,24 @@ pub fn bench_erc20(c: &mut Criterion) {
         "Independent ERC20",
         InMemoryStorage::new(state, Arc::new(bytecodes), Default::default()),
         txs,
+        true,
+    );
+}
+
+/// Benchmarks the execution time of ERC-20 token transfers from many holders
+/// to a few hot recipients, like an airdrop.
+pub fn bench_erc20_airdrop(c: &mut Criterion) {
+    const NUM_SENDERS: usize = 1_000;
+    let block_size = (GIGA_GAS as f64 / erc20::ESTIMATED_GAS_USED as f64).ceil() as usize;
+    let (mut state, bytecodes, txs) =
+        erc20::generate_hot_recipients(NUM_SENDERS, 10, block_size.div_ceil(NUM_SENDERS));
+    state.insert(Address::ZERO, EvmAccount::default()); // Beneficiary
+    bench(
+        c,
+        "Airdrop ERC20",
+        InMemoryStorage::new(state, Arc::new(bytecodes), Default::default()),
+        txs,
+        true,
     );
 }
 
@@ -137,6 +179,7 @@ pub fn bench_uniswap(c: &mut Criterion) {
         "Independent Uniswap",
         InMemoryStorage::new(final_state, Arc::new(final_bytecodes), Default::default()),
         final_txs,
+        false,
     );
 }
 
@@ -144,6 +187,7 @@ pub fn bench_uniswap(c: &mut Criterion) {
 pub fn benchmark_gigagas(c: &mut Criterion) {
     bench_raw_transfers(c);
     bench_erc20(c);
+    bench_erc20_airdrop(c);
     bench_uniswap(c);
 }
 
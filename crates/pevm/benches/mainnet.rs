@@ -27,10 +27,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     let chain = PevmEthereum::mainnet();
     let concurrency_level = thread::available_parallelism()
         .unwrap_or(NonZeroUsize::MIN)
-        // This max should be tuned to the running machine,
-        // ideally also per block depending on the number of
-        // transactions, gas usage, etc. ARM machines seem to
-        // go higher thanks to their low thread overheads.
+        // This max should be tuned to the running machine, while
+        // the execution policy picks the workers for each block
+        // within it. ARM machines seem to go higher thanks to
+        // their low thread overheads.
         .min(
             NonZeroUsize::new(
                 #[cfg(target_arch = "aarch64")]
@@ -42,34 +42,40 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         );
     let mut pevm = Pevm::default();
 
-    common::for_each_block_from_disk("ethereum", |block, storage| {
+    common::for_each_block_from_disk("ethereum", |block, ommers, storage, _| {
         let mut group = c.benchmark_group(format!(
             "Block {}({} txs, {} gas)",
             block.header.number,
             block.transactions.len(),
             block.header.gas_used
         ));
-        group.bench_function("Sequential", |b| {
-            b.iter(|| {
-                pevm.execute(
+        // Blocks whose ommer headers are not snapshotted are executed without
+        // the ommer rewards.
+        let execute = |pevm: &mut Pevm, force_sequential| {
+            if ommers.len() == block.uncles.len() {
+                pevm.execute_with_ommers(
                     black_box(&chain),
                     black_box(&storage),
                     black_box(&block),
+                    black_box(&ommers),
                     black_box(concurrency_level),
-                    black_box(true),
+                    force_sequential,
                 )
-            })
-        });
-        group.bench_function("Parallel", |b| {
-            b.iter(|| {
+            } else {
                 pevm.execute(
                     black_box(&chain),
                     black_box(&storage),
                     black_box(&block),
                     black_box(concurrency_level),
-                    black_box(false),
+                    force_sequential,
                 )
-            })
+            }
+        };
+        group.bench_function("Sequential", |b| {
+            b.iter(|| execute(&mut pevm, black_box(true)))
+        });
+        group.bench_function("Parallel", |b| {
+            b.iter(|| execute(&mut pevm, black_box(false)))
         });
         group.finish();
     });
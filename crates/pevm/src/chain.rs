@@ -4,20 +4,21 @@ use std::fmt::Debug;
 use std::{error::Error as StdError, fmt::Display};
 
 use alloy_consensus::{Signed, TxLegacy, transaction::Recovered};
-use alloy_primitives::{Address, B256, U256};
+use alloy_primitives::{Address, B256, Bytes, U256};
 use alloy_rpc_types_eth::{BlockTransactions, Header, Transaction};
 use revm::context::result::HaltReason;
 use revm::context::{ContextSetters, JournalTr, TxEnv};
 use revm::context_interface::LocalContextTr;
 use revm::handler::instructions::InstructionProvider;
 use revm::handler::{EvmTr, FrameResult, FrameTr, PrecompileProvider};
+use revm::inspector::InspectorEvmTr;
 use revm::interpreter::InterpreterResult;
 use revm::interpreter::interpreter::EthInterpreter;
 use revm::interpreter::interpreter_action::FrameInit;
 use revm::primitives::hardfork::SpecId;
 use revm::state::EvmState;
 use revm::{
-    Database, ExecuteEvm,
+    Database, ExecuteEvm, SystemCallEvm,
     context::{
         BlockEnv, ContextTr,
         result::{EVMError, ExecutionResult},
@@ -25,7 +26,7 @@ use revm::{
 };
 use smallvec::SmallVec;
 
-use crate::{MemoryLocationHash, PevmTxExecutionResult, mv_memory::MvMemory};
+use crate::{ExecutionError, PevmInspector, PevmTxExecutionResult, mv_memory::MvMemory};
 
 /// The error type of [`PevmChain::calculate_receipt_root`]
 #[derive(Debug, Clone)]
@@ -40,6 +41,19 @@ pub enum CalculateReceiptRootError {
     OpDepositMissingSender,
 }
 
+/// A system contract call that is executed before or after a block's transactions,
+/// like EIP-4788 beacon roots and EIP-7002 withdrawal requests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemCall {
+    /// The system contract to call.
+    pub address: Address,
+    /// The call data.
+    pub data: Bytes,
+    /// The EIP-7685 request type to collect the call output as.
+    /// The block is invalid if such a call fails.
+    pub request_type: Option<u8>,
+}
+
 /// Custom behaviours for different chains & networks
 pub trait PevmChain: Debug {
     /// The network type
@@ -52,7 +66,9 @@ pub trait PevmChain: Debug {
     // TODO: Support more tx conversions
     type Envelope: Debug + From<Signed<TxLegacy>>;
 
-    /// The EVM type
+    /// The EVM type. Its inspector is `()`, or a [`crate::CommutativeInspector`]
+    /// for pevm to record commutative storage writes, which only runs with
+    /// [`crate::Pevm::with_commutative_writes`].
     type Evm<DB: Database>: EvmTr<
             Context: ContextTr<
                 Db = DB,
@@ -74,7 +90,8 @@ pub trait PevmChain: Debug {
             ExecutionResult = ExecutionResult<Self::EvmHaltReason>,
             State = EvmState,
             Error = EVMError<DB::Error, Self::EvmErrorType>,
-        >;
+        > + InspectorEvmTr<Inspector: PevmInspector>
+        + SystemCallEvm;
 
     /// The EVM Spec type
     type EvmSpecId: Into<SpecId> + Copy + Send + Sync + Default;
@@ -132,6 +149,11 @@ pub trait PevmChain: Debug {
     /// Get a reference to the base [`TxEnv`] from a chain-specific transaction
     fn tx_env<'a>(&self, tx: &'a Self::EvmTx) -> &'a TxEnv;
 
+    /// Convert a transaction validation error of [`Self::Evm`] to the
+    /// [`ExecutionError`] that parallel execution reports, for sequential
+    /// execution to report the same errors.
+    fn transaction_error(&self, err: Self::EvmErrorType) -> ExecutionError;
+
     /// Whether this transaction has a nonce. Return false for types that have no nonce
     /// (e.g. OP deposits) so pevm's sender-nonce ordering check is skipped. Implementations
     /// may also adjust EVM cfg as a side effect (e.g. setting `disable_nonce_check`).
@@ -139,20 +161,64 @@ pub trait PevmChain: Debug {
         true
     }
 
-    /// Build [`MvMemory`]
-    fn build_mv_memory(&self, _block_env: &BlockEnv, txs: &[Self::EvmTx]) -> MvMemory {
-        MvMemory::new(txs.len(), [], [])
+    /// Reset [`MvMemory`], possibly recycled from a previous block, for the
+    /// transactions of a new block.
+    fn prepare_mv_memory(
+        &self,
+        mv_memory: &mut MvMemory,
+        _block_env: &BlockEnv,
+        txs: &[Self::EvmTx],
+    ) {
+        mv_memory.reset(txs.len(), [], []);
+    }
+
+    /// Get the system calls to execute before the block's transactions.
+    fn pre_block_system_calls(
+        &self,
+        _spec_id: Self::EvmSpecId,
+        _header: &Header,
+    ) -> Vec<SystemCall> {
+        Vec::new()
+    }
+
+    /// Get the system calls to execute after the block's transactions.
+    fn post_block_system_calls(
+        &self,
+        _spec_id: Self::EvmSpecId,
+        _header: &Header,
+    ) -> Vec<SystemCall> {
+        Vec::new()
+    }
+
+    /// Get the contract whose logs are collected as EIP-6110 deposit requests
+    /// after the block's transactions.
+    fn deposit_contract(&self, _spec_id: Self::EvmSpecId) -> Option<Address> {
+        None
+    }
+
+    /// Get the end-of-block rewards (balance increments) like pre-merge
+    /// block and ommer rewards, for a block with `num_ommers` ommers. Blocks
+    /// only have the hashes of their ommers, so their beneficiaries are only
+    /// rewarded when the `ommers` headers are given.
+    fn get_block_rewards(
+        &self,
+        _spec_id: Self::EvmSpecId,
+        _header: &Header,
+        _num_ommers: usize,
+        _ommers: &[Header],
+    ) -> Vec<(Address, U256)> {
+        Vec::new()
     }
 
     /// Get rewards (balance increments) to beneficiary accounts, etc.
     fn get_rewards(
         &self,
-        beneficiary_location_hash: u64,
+        beneficiary: Address,
         gas_used: U256,
         gas_price: U256,
         basefee: u64,
         tx: &Self::EvmTx,
-    ) -> SmallVec<[(MemoryLocationHash, U256); 1]>;
+    ) -> SmallVec<[(Address, U256); 1]>;
 
     /// Calculate receipt root
     fn calculate_receipt_root(
@@ -1,7 +1,14 @@
 //! Ethereum
 
 use alloy_consensus::{ReceiptEnvelope, Transaction, TxEnvelope, TxType};
-use alloy_primitives::{Address, B256, U256};
+use alloy_eips::{
+    eip2935::HISTORY_STORAGE_ADDRESS,
+    eip4788::BEACON_ROOTS_ADDRESS,
+    eip6110::MAINNET_DEPOSIT_CONTRACT_ADDRESS,
+    eip7002::{WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS, WITHDRAWAL_REQUEST_TYPE},
+    eip7251::{CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS, CONSOLIDATION_REQUEST_TYPE},
+};
+use alloy_primitives::{Address, B256, Bytes, U256};
 use alloy_provider::network::eip2718::Encodable2718;
 use alloy_rpc_types_eth::{BlockTransactions, Header};
 use hashbrown::HashMap;
@@ -9,7 +16,7 @@ use revm::{
     Context, Database, MainBuilder, MainContext, MainnetEvm,
     context::{
         BlockEnv, CfgEnv, TxEnv,
-        result::{HaltReason, InvalidTransaction},
+        result::{EVMError, HaltReason, InvalidTransaction},
     },
     context_interface::either::Either,
     handler::MainnetContext,
@@ -20,10 +27,10 @@ use revm::{
 };
 use smallvec::SmallVec;
 
-use super::{CalculateReceiptRootError, PevmChain};
+use super::{CalculateReceiptRootError, PevmChain, SystemCall};
 use crate::{
-    BuildIdentityHasher, MemoryLocation, MemoryLocationHash, PevmTxExecutionResult, TxIdx,
-    hash_deterministic, mv_memory::MvMemory,
+    BuildIdentityHasher, CommutativeInspector, ExecutionError, MemoryLocation,
+    PevmTxExecutionResult, TxIdx, hash_deterministic, mv_memory::MvMemory,
 };
 
 /// Implementation of [`PevmChain`] for Ethereum
@@ -62,7 +69,7 @@ impl PevmChain for PevmEthereum {
     type Network = alloy_provider::network::Ethereum;
     type Transaction = alloy_rpc_types_eth::Transaction;
     type Envelope = TxEnvelope;
-    type Evm<DB: Database> = MainnetEvm<MainnetContext<DB>>;
+    type Evm<DB: Database> = MainnetEvm<MainnetContext<DB>, CommutativeInspector>;
     type EvmSpecId = SpecId;
     type EvmTx = TxEnv;
     type EvmHaltReason = HaltReason;
@@ -85,7 +92,11 @@ impl PevmChain for PevmEthereum {
     // TODO: Better error handling & properly test this.
     // TODO: Only Ethereum Mainnet is supported at the moment.
     fn get_block_spec(&self, header: &Header) -> Result<SpecId, Self::BlockSpecError> {
-        Ok(if header.timestamp >= 1710338135 {
+        Ok(if header.timestamp >= 1764798551 {
+            SpecId::OSAKA
+        } else if header.timestamp >= 1746612311 {
+            SpecId::PRAGUE
+        } else if header.timestamp >= 1710338135 {
             SpecId::CANCUN
         } else if header.timestamp >= 1681338455 {
             SpecId::SHANGHAI
@@ -130,7 +141,7 @@ impl PevmChain for PevmEthereum {
             .with_cfg(cfg)
             .with_block(block_env)
             .with_db(db)
-            .build_mainnet()
+            .build_mainnet_with_inspector(CommutativeInspector::default())
     }
 
     /// Get the REVM tx envs of an Alloy block.
@@ -163,33 +174,125 @@ impl PevmChain for PevmEthereum {
         tx
     }
 
-    fn build_mv_memory(&self, block_env: &BlockEnv, txs: &[TxEnv]) -> MvMemory {
+    fn transaction_error(&self, err: InvalidTransaction) -> ExecutionError {
+        EVMError::Transaction(err)
+    }
+
+    fn prepare_mv_memory(&self, mv_memory: &mut MvMemory, block_env: &BlockEnv, txs: &[TxEnv]) {
         let block_size = txs.len();
         let beneficiary_location_hash =
-            hash_deterministic(MemoryLocation::Basic(block_env.beneficiary));
+            hash_deterministic(MemoryLocation::Balance(block_env.beneficiary));
 
-        // TODO: Estimate more locations based on sender, to, etc.
         let mut estimated_locations = HashMap::with_hasher(BuildIdentityHasher::default());
         estimated_locations.insert(
             beneficiary_location_hash,
             (0..block_size).collect::<Vec<TxIdx>>(),
         );
+        mv_memory.estimate_dependencies(txs.iter().enumerate(), &mut estimated_locations);
+
+        mv_memory.reset(block_size, estimated_locations, [block_env.beneficiary]);
+        mv_memory.index_senders(txs.iter().enumerate());
+        mv_memory.index_commutative_calls(txs.iter().enumerate());
+    }
+
+    // https://eips.ethereum.org/EIPS/eip-4788
+    // https://eips.ethereum.org/EIPS/eip-2935
+    fn pre_block_system_calls(&self, spec_id: SpecId, header: &Header) -> Vec<SystemCall> {
+        let mut calls = Vec::new();
+        // There is no parent to record for the genesis block.
+        if header.number == 0 {
+            return calls;
+        }
+        if spec_id >= SpecId::CANCUN
+            && let Some(parent_beacon_block_root) = header.parent_beacon_block_root
+        {
+            calls.push(SystemCall {
+                address: BEACON_ROOTS_ADDRESS,
+                data: Bytes::copy_from_slice(parent_beacon_block_root.as_slice()),
+                request_type: None,
+            });
+        }
+        if spec_id >= SpecId::PRAGUE {
+            calls.push(SystemCall {
+                address: HISTORY_STORAGE_ADDRESS,
+                data: Bytes::copy_from_slice(header.parent_hash.as_slice()),
+                request_type: None,
+            });
+        }
+        calls
+    }
 
-        MvMemory::new(block_size, estimated_locations, [block_env.beneficiary])
+    // https://eips.ethereum.org/EIPS/eip-7002
+    // https://eips.ethereum.org/EIPS/eip-7251
+    fn post_block_system_calls(&self, spec_id: SpecId, _header: &Header) -> Vec<SystemCall> {
+        if spec_id < SpecId::PRAGUE {
+            return Vec::new();
+        }
+        vec![
+            SystemCall {
+                address: WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS,
+                data: Bytes::new(),
+                request_type: Some(WITHDRAWAL_REQUEST_TYPE),
+            },
+            SystemCall {
+                address: CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS,
+                data: Bytes::new(),
+                request_type: Some(CONSOLIDATION_REQUEST_TYPE),
+            },
+        ]
+    }
+
+    // https://eips.ethereum.org/EIPS/eip-6110
+    fn deposit_contract(&self, spec_id: SpecId) -> Option<Address> {
+        (spec_id >= SpecId::PRAGUE).then_some(MAINNET_DEPOSIT_CONTRACT_ADDRESS)
+    }
+
+    // https://github.com/paradigmxyz/reth/blob/b4a1b733c93f7e262f1b774722670e08cdcb6276/crates/consensus/common/src/calc.rs
+    fn get_block_rewards(
+        &self,
+        spec_id: SpecId,
+        header: &Header,
+        num_ommers: usize,
+        ommers: &[Header],
+    ) -> Vec<(Address, U256)> {
+        const ETH_TO_WEI: u128 = 1_000_000_000_000_000_000;
+        // There are no block rewards after the merge.
+        let base_reward = if spec_id >= SpecId::MERGE {
+            return Vec::new();
+        } else if spec_id >= SpecId::CONSTANTINOPLE {
+            2 * ETH_TO_WEI
+        } else if spec_id >= SpecId::BYZANTIUM {
+            3 * ETH_TO_WEI
+        } else {
+            5 * ETH_TO_WEI
+        };
+
+        let mut rewards = Vec::with_capacity(ommers.len() + 1);
+        // The block beneficiary gets an extra 1/32 of the base reward per
+        // included ommer.
+        rewards.push((
+            header.beneficiary,
+            U256::from(base_reward + (base_reward >> 5) * num_ommers as u128),
+        ));
+        for ommer in ommers {
+            let distance = (8 + ommer.number).saturating_sub(header.number);
+            rewards.push((
+                ommer.beneficiary,
+                U256::from((distance as u128 * base_reward) >> 3),
+            ));
+        }
+        rewards
     }
 
     fn get_rewards(
         &self,
-        beneficiary_location_hash: u64,
+        beneficiary: Address,
         gas_used: U256,
         gas_price: U256,
         _: u64,
         _: &Self::EvmTx,
-    ) -> SmallVec<[(MemoryLocationHash, U256); 1]> {
-        smallvec::smallvec![(
-            beneficiary_location_hash,
-            gas_price.saturating_mul(gas_used)
-        )]
+    ) -> SmallVec<[(Address, U256); 1]> {
+        smallvec::smallvec![(beneficiary, gas_price.saturating_mul(gas_used))]
     }
 
     // Refer to section 4.3.2. Holistic Validity in the Ethereum Yellow Paper.
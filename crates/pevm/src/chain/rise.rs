@@ -15,15 +15,15 @@ use op_revm::{
 };
 use revm::{
     Context, Database, MainContext,
-    context::{BlockEnv, CfgEnv, TxEnv},
+    context::{BlockEnv, CfgEnv, TxEnv, result::EVMError},
     context_interface::either::Either,
     handler::EvmTr,
 };
 use smallvec::SmallVec;
 
 use crate::{
-    BuildIdentityHasher, MemoryLocation, MemoryLocationHash, PevmTxExecutionResult,
-    hash_deterministic, mv_memory::MvMemory,
+    BuildIdentityHasher, CommutativeInspector, ExecutionError, MemoryLocation, MemoryLocationHash,
+    PevmTxExecutionResult, hash_deterministic, mv_memory::MvMemory,
 };
 
 use super::{CalculateReceiptRootError, PevmChain};
@@ -31,13 +31,13 @@ use super::{CalculateReceiptRootError, PevmChain};
 const RISE_CHAIN_ID: ChainId = 4153; // Mainnet
 
 static BASE_FEE_RECIPIENT_LOCATION_HASH: LazyLock<MemoryLocationHash> =
-    LazyLock::new(|| hash_deterministic(MemoryLocation::Basic(BASE_FEE_RECIPIENT)));
+    LazyLock::new(|| hash_deterministic(MemoryLocation::Balance(BASE_FEE_RECIPIENT)));
 
 static L1_FEE_RECIPIENT_LOCATION_HASH: LazyLock<MemoryLocationHash> =
-    LazyLock::new(|| hash_deterministic(MemoryLocation::Basic(L1_FEE_RECIPIENT)));
+    LazyLock::new(|| hash_deterministic(MemoryLocation::Balance(L1_FEE_RECIPIENT)));
 
 static OPERATOR_FEE_RECIPIENT_LOCATION_HASH: LazyLock<MemoryLocationHash> =
-    LazyLock::new(|| hash_deterministic(MemoryLocation::Basic(OPERATOR_FEE_RECIPIENT)));
+    LazyLock::new(|| hash_deterministic(MemoryLocation::Balance(OPERATOR_FEE_RECIPIENT)));
 
 /// Implementation of [`PevmChain`] for RISE
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -65,7 +65,7 @@ impl PevmChain for PevmRise {
     type Network = op_alloy_network::Optimism;
     type Transaction = op_alloy_rpc_types::Transaction;
     type Envelope = OpTxEnvelope;
-    type Evm<DB: Database> = OpEvm<OpContext<DB>, ()>;
+    type Evm<DB: Database> = OpEvm<OpContext<DB>, CommutativeInspector>;
     type EvmSpecId = OpSpecId;
     type EvmTx = OpTransaction<TxEnv>;
     type EvmHaltReason = OpHaltReason;
@@ -102,14 +102,18 @@ impl PevmChain for PevmRise {
             .with_db(db)
             .with_tx(OpTransaction::default())
             .with_chain(L1BlockInfo::default())
-            .build_op()
+            .build_op_with_inspector(CommutativeInspector::default())
     }
 
-    fn build_mv_memory(&self, block_env: &BlockEnv, txs: &[OpTransaction<TxEnv>]) -> MvMemory {
+    fn prepare_mv_memory(
+        &self,
+        mv_memory: &mut MvMemory,
+        block_env: &BlockEnv,
+        txs: &[OpTransaction<TxEnv>],
+    ) {
         let beneficiary_location_hash =
-            hash_deterministic(MemoryLocation::Basic(block_env.beneficiary));
+            hash_deterministic(MemoryLocation::Balance(block_env.beneficiary));
 
-        // TODO: Estimate more locations based on sender, to, etc.
         // TODO: Benchmark to check whether adding these estimated
         // locations helps or harms the performance.
         let mut estimated_locations = HashMap::with_hasher(BuildIdentityHasher::default());
@@ -136,7 +140,15 @@ impl PevmChain for PevmRise {
             }
         }
 
-        MvMemory::new(
+        mv_memory.estimate_dependencies(
+            txs.iter()
+                .enumerate()
+                .filter(|(_, tx)| !tx.is_deposit())
+                .map(|(index, tx)| (index, &tx.base)),
+            &mut estimated_locations,
+        );
+
+        mv_memory.reset(
             txs.len(),
             estimated_locations,
             [
@@ -145,34 +157,34 @@ impl PevmChain for PevmRise {
                 L1_FEE_RECIPIENT,
                 OPERATOR_FEE_RECIPIENT,
             ],
-        )
+        );
+        // Deposits also bump the nonces of their senders.
+        mv_memory.index_senders(txs.iter().map(|tx| &tx.base).enumerate());
+        mv_memory.index_commutative_calls(txs.iter().map(|tx| &tx.base).enumerate());
     }
 
     fn get_rewards(
         &self,
-        beneficiary_location_hash: u64,
+        beneficiary: Address,
         gas_used: U256,
         gas_price: U256,
         basefee: u64,
         tx: &Self::EvmTx,
-    ) -> SmallVec<[(MemoryLocationHash, U256); 1]> {
+    ) -> SmallVec<[(Address, U256); 1]> {
         if tx.is_deposit() {
             SmallVec::new()
         } else {
             smallvec::smallvec![
+                (beneficiary, gas_price.saturating_mul(gas_used)),
                 (
-                    beneficiary_location_hash,
-                    gas_price.saturating_mul(gas_used)
-                ),
-                (
-                    *BASE_FEE_RECIPIENT_LOCATION_HASH,
+                    BASE_FEE_RECIPIENT,
                     U256::from(basefee).saturating_mul(gas_used),
                 ),
                 // RISE disables DA footprint and operator fees. Annoyingly, we still
                 // need to touch these to match revm's sequential execution for now.
                 // Will remove once we rewrite our own EVM implementation.
-                (*L1_FEE_RECIPIENT_LOCATION_HASH, U256::ZERO),
-                (*OPERATOR_FEE_RECIPIENT_LOCATION_HASH, U256::ZERO),
+                (L1_FEE_RECIPIENT, U256::ZERO),
+                (OPERATOR_FEE_RECIPIENT, U256::ZERO),
             ]
         }
     }
@@ -274,6 +286,14 @@ impl PevmChain for PevmRise {
         &tx.base
     }
 
+    fn transaction_error(&self, err: OpTransactionError) -> ExecutionError {
+        match err {
+            OpTransactionError::Base(err) => EVMError::Transaction(err),
+            // Deposit errors have no equivalent in parallel execution.
+            err => EVMError::Custom(err.to_string()),
+        }
+    }
+
     fn has_nonce<DB: Database>(&self, evm: &mut Self::Evm<DB>, tx: &Self::EvmTx) -> bool {
         let is_deposit = tx.is_deposit();
         evm.ctx()
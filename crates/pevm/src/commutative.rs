@@ -0,0 +1,529 @@
+use alloy_primitives::{Address, I256, U256};
+use revm::{
+    bytecode::opcode::{self, OPCODE_INFO},
+    context_interface::{ContextTr, JournalTr},
+    inspector::Inspector,
+    interpreter::{
+        CallInputs, CallOutcome, CreateInputs, CreateOutcome, Interpreter,
+        interpreter::EthInterpreter,
+        interpreter_types::{InputsTr, Jumps, LoopControl},
+    },
+};
+
+// The selectors of the ERC20 functions that credit a holder.
+const ERC20_TRANSFER: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+const ERC20_TRANSFER_FROM: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+
+// The holder whose balance an ERC20 call likely credits.
+pub(crate) fn erc20_credited_holder(data: &[u8]) -> Option<Address> {
+    // The address argument at [index] of the call data.
+    let arg = |index: usize| {
+        data.get(4 + 32 * index..4 + 32 * (index + 1))
+            .map(|word| Address::from_slice(&word[12..]))
+    };
+    match data.get(..4)?.try_into().ok()? {
+        ERC20_TRANSFER => arg(0),
+        ERC20_TRANSFER_FROM => arg(1),
+        _ => None,
+    }
+}
+
+// A stack item that derives from the value of a tracked storage slot before
+// the transaction, plus a (wrapping) delta.
+#[derive(Debug, Clone, Copy)]
+struct Taint {
+    slot: usize,
+    delta: U256,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Operand {
+    // The value of the slot before the transaction plus a (wrapping) delta.
+    Slot(U256),
+    Const(U256),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Comparison {
+    Lt,
+    Gt,
+    Slt,
+    Sgt,
+    Eq,
+}
+
+// A comparison that a transaction made on the value of a storage slot, which
+// must have the same outcome for the actual value of the slot for the
+// execution to be valid. This covers overflow and sufficient balance checks,
+// and the zero checks that SSTORE gas costs depend on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Constraint {
+    comparison: Comparison,
+    lhs: Operand,
+    rhs: Operand,
+    outcome: bool,
+}
+
+impl Constraint {
+    pub(crate) fn new(comparison: Comparison, lhs: Operand, rhs: Operand, base: U256) -> Self {
+        let mut constraint = Self {
+            comparison,
+            lhs,
+            rhs,
+            outcome: false,
+        };
+        constraint.outcome = constraint.evaluate(base);
+        constraint
+    }
+
+    fn evaluate(&self, base: U256) -> bool {
+        let value = |operand| match operand {
+            Operand::Slot(delta) => base.wrapping_add(delta),
+            Operand::Const(value) => value,
+        };
+        let (lhs, rhs) = (value(self.lhs), value(self.rhs));
+        match self.comparison {
+            Comparison::Lt => lhs < rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Slt => I256::from_raw(lhs) < I256::from_raw(rhs),
+            Comparison::Sgt => I256::from_raw(lhs) > I256::from_raw(rhs),
+            Comparison::Eq => lhs == rhs,
+        }
+    }
+
+    // Whether the comparison has the same outcome for [base] as the value of
+    // the slot before the transaction.
+    pub(crate) fn holds(&self, base: U256) -> bool {
+        self.evaluate(base) == self.outcome
+    }
+}
+
+// A storage slot that a transaction accessed, from its first access.
+#[derive(Debug)]
+pub(crate) struct TrackedSlot {
+    pub(crate) address: Address,
+    pub(crate) index: U256,
+    // The value of the slot before the transaction that the execution read.
+    base: U256,
+    // The net (wrapping) addition of the transaction to the slot.
+    pub(crate) delta: U256,
+    pub(crate) constraints: Vec<Constraint>,
+    // Whether the execution depends on the value of the slot beyond its
+    // constraints, like using it in other operations, or overwrote it with
+    // an unrelated value. The slot is then a normal read and write.
+    pub(crate) observed: bool,
+    // The deepest call depth that stored to the slot, to stop tracking it
+    // when a call that may have stored to it reverts.
+    store_depth: usize,
+}
+
+impl TrackedSlot {
+    fn constrain(&mut self, comparison: Comparison, lhs: Operand, rhs: Operand) {
+        let constraint = Constraint::new(comparison, lhs, rhs, self.base);
+        if !self.constraints.contains(&constraint) {
+            self.constraints.push(constraint);
+        }
+    }
+}
+
+// The change that the current instruction makes to the taints of its stack.
+#[derive(Debug)]
+struct Effect {
+    inputs: usize,
+    output: Output,
+}
+
+#[derive(Debug)]
+enum Output {
+    None,
+    Untainted,
+    Taint(Taint),
+    // The value loaded from [address] at [index].
+    Sload(Address, U256),
+    // [value] stored to a tracked slot, with its new delta.
+    Sstore(usize, U256),
+}
+
+/// The inspector of a chain's EVM, which pevm uses to record commutative
+/// storage writes. EVMs built with the `()` inspector do not record them.
+pub trait PevmInspector {
+    /// The inspector tracking commutative storage writes, if any.
+    fn commutative(&mut self) -> Option<&mut CommutativeInspector>;
+}
+
+impl PevmInspector for () {
+    fn commutative(&mut self) -> Option<&mut CommutativeInspector> {
+        None
+    }
+}
+
+impl PevmInspector for CommutativeInspector {
+    fn commutative(&mut self) -> Option<&mut CommutativeInspector> {
+        Some(self)
+    }
+}
+
+/// An inspector that tracks the storage slots that a transaction only adds
+/// constants to, like a token recipient's balance, for pevm to record their
+/// writes as deltas that commute with those of other transactions. It
+/// follows each loaded slot value through the stack, and stops tracking a
+/// slot once the execution depends on its value other than by comparisons.
+#[derive(Debug, Default)]
+pub struct CommutativeInspector {
+    slots: Vec<TrackedSlot>,
+    // The number of slots that are not observed yet.
+    live_slots: usize,
+    // The taint of each item on the stack of each call depth.
+    stacks: Vec<Vec<Option<Taint>>>,
+    effect: Option<Effect>,
+}
+
+impl CommutativeInspector {
+    // Clear the tracked slots before executing a transaction.
+    pub(crate) fn reset(&mut self) {
+        self.slots.clear();
+        self.live_slots = 0;
+        self.stacks.clear();
+        self.effect = None;
+    }
+
+    // The slots of the last transaction that it only added constants to.
+    pub(crate) fn commutative_slots(&self) -> impl Iterator<Item = &TrackedSlot> {
+        self.slots.iter().filter(|slot| !slot.observed)
+    }
+
+    fn find_slot(&self, address: Address, index: U256) -> Option<usize> {
+        self.slots
+            .iter()
+            .position(|slot| slot.address == address && slot.index == index)
+    }
+
+    fn observe(&mut self, slot: usize) {
+        let slot = &mut self.slots[slot];
+        if !slot.observed {
+            slot.observed = true;
+            self.live_slots -= 1;
+        }
+    }
+
+    // Align the taints of the stack at [depth] with its [len] items. New items
+    // were pushed outside of instructions, like call results, and are not
+    // tainted. Dropped tainted items are conservatively observed.
+    fn sync_stack(&mut self, depth: usize, len: usize) {
+        if self.stacks.len() <= depth {
+            self.stacks.resize_with(depth + 1, Vec::new);
+        }
+        let taints = &mut self.stacks[depth];
+        if taints.len() > len {
+            let dropped: Vec<usize> = taints
+                .drain(len..)
+                .flatten()
+                .map(|taint| taint.slot)
+                .collect();
+            for slot in dropped {
+                self.observe(slot);
+            }
+        } else {
+            taints.resize(len, None);
+        }
+    }
+
+    fn compare(
+        &mut self,
+        comparison: Comparison,
+        (lhs, lhs_value): (Option<Taint>, U256),
+        (rhs, rhs_value): (Option<Taint>, U256),
+    ) -> Output {
+        let slot = match (lhs, rhs) {
+            (Some(lhs), Some(rhs)) if lhs.slot != rhs.slot => {
+                self.observe(lhs.slot);
+                self.observe(rhs.slot);
+                return Output::Untainted;
+            }
+            (Some(taint), _) | (None, Some(taint)) => taint.slot,
+            (None, None) => return Output::Untainted,
+        };
+        let operand = |taint: Option<Taint>, value| match taint {
+            Some(taint) => Operand::Slot(taint.delta),
+            None => Operand::Const(value),
+        };
+        self.slots[slot].constrain(comparison, operand(lhs, lhs_value), operand(rhs, rhs_value));
+        Output::Untainted
+    }
+}
+
+impl<CTX: ContextTr> Inspector<CTX, EthInterpreter> for CommutativeInspector {
+    fn initialize_interp(&mut self, _: &mut Interpreter, context: &mut CTX) {
+        // The items left on the stack of the last frame at this depth were
+        // never used.
+        let depth = context.journal_ref().depth();
+        if self.stacks.len() <= depth {
+            self.stacks.resize_with(depth + 1, Vec::new);
+        }
+        self.stacks[depth].clear();
+    }
+
+    fn step(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+        let op = interp.bytecode.opcode();
+        if self.live_slots == 0 && op != opcode::SLOAD && op != opcode::SSTORE {
+            return;
+        }
+        let depth = context.journal_ref().depth();
+        let stack = interp.stack.data();
+        self.sync_stack(depth, stack.len());
+
+        let len = stack.len();
+        let taint = |stacks: &[Vec<Option<Taint>>], i: usize| {
+            (i < len).then(|| stacks[depth][len - 1 - i]).flatten()
+        };
+        let value = |i: usize| {
+            if i < len {
+                stack[len - 1 - i]
+            } else {
+                U256::ZERO
+            }
+        };
+        let (a, b) = (taint(&self.stacks, 0), taint(&self.stacks, 1));
+
+        let effect = match op {
+            opcode::SLOAD => {
+                if let Some(key) = a {
+                    self.observe(key.slot);
+                }
+                Effect {
+                    inputs: 1,
+                    output: Output::Sload(interp.input.target_address(), value(0)),
+                }
+            }
+            opcode::SSTORE => {
+                if let Some(key) = a {
+                    self.observe(key.slot);
+                }
+                let address = interp.input.target_address();
+                let output = match (self.find_slot(address, value(0)), b) {
+                    (Some(slot), Some(stored)) if stored.slot == slot => {
+                        Output::Sstore(slot, stored.delta)
+                    }
+                    (slot, stored) => {
+                        if let Some(stored) = stored {
+                            self.observe(stored.slot);
+                        }
+                        match slot {
+                            Some(slot) => self.observe(slot),
+                            // An absolute write before any read is not tracked.
+                            None => self.slots.push(TrackedSlot {
+                                address,
+                                index: value(0),
+                                base: U256::ZERO,
+                                delta: U256::ZERO,
+                                constraints: Vec::new(),
+                                observed: true,
+                                store_depth: depth,
+                            }),
+                        }
+                        Output::None
+                    }
+                };
+                Effect { inputs: 2, output }
+            }
+            opcode::ADD => Effect {
+                inputs: 2,
+                output: match (a, b) {
+                    (Some(a), Some(b)) => {
+                        self.observe(a.slot);
+                        self.observe(b.slot);
+                        Output::Untainted
+                    }
+                    (Some(taint), None) | (None, Some(taint)) => Output::Taint(Taint {
+                        slot: taint.slot,
+                        delta: taint.delta.wrapping_add(if a.is_some() {
+                            value(1)
+                        } else {
+                            value(0)
+                        }),
+                    }),
+                    (None, None) => Output::Untainted,
+                },
+            },
+            opcode::SUB => Effect {
+                inputs: 2,
+                output: match (a, b) {
+                    (Some(a), None) => Output::Taint(Taint {
+                        slot: a.slot,
+                        delta: a.delta.wrapping_sub(value(1)),
+                    }),
+                    // The difference of two values of the same slot does not
+                    // depend on the value of the slot.
+                    (Some(a), Some(b)) if a.slot == b.slot => Output::Untainted,
+                    (a, b) => {
+                        for taint in [a, b].into_iter().flatten() {
+                            self.observe(taint.slot);
+                        }
+                        Output::Untainted
+                    }
+                },
+            },
+            opcode::LT | opcode::GT | opcode::SLT | opcode::SGT | opcode::EQ => {
+                let comparison = match op {
+                    opcode::LT => Comparison::Lt,
+                    opcode::GT => Comparison::Gt,
+                    opcode::SLT => Comparison::Slt,
+                    opcode::SGT => Comparison::Sgt,
+                    _ => Comparison::Eq,
+                };
+                Effect {
+                    inputs: 2,
+                    output: self.compare(comparison, (a, value(0)), (b, value(1))),
+                }
+            }
+            opcode::ISZERO => Effect {
+                inputs: 1,
+                output: self.compare(Comparison::Eq, (a, value(0)), (None, U256::ZERO)),
+            },
+            opcode::POP => Effect {
+                inputs: 1,
+                output: Output::None,
+            },
+            opcode::DUP1..=opcode::DUP16 => Effect {
+                inputs: 0,
+                output: match taint(&self.stacks, (op - opcode::DUP1) as usize) {
+                    Some(taint) => Output::Taint(taint),
+                    None => Output::Untainted,
+                },
+            },
+            opcode::SWAP1..=opcode::SWAP16 => {
+                let n = (op - opcode::SWAP1) as usize + 1;
+                if n < len {
+                    self.stacks[depth].swap(len - 1, len - 1 - n);
+                }
+                Effect {
+                    inputs: 0,
+                    output: Output::None,
+                }
+            }
+            _ => {
+                let (inputs, outputs) = OPCODE_INFO[op as usize]
+                    .map_or((0, 0), |info| (info.inputs() as usize, info.outputs()));
+                for i in 0..inputs {
+                    if let Some(taint) = taint(&self.stacks, i) {
+                        self.observe(taint.slot);
+                    }
+                }
+                Effect {
+                    inputs,
+                    output: if outputs > 0 {
+                        Output::Untainted
+                    } else {
+                        Output::None
+                    },
+                }
+            }
+        };
+        self.effect = Some(effect);
+    }
+
+    fn step_end(&mut self, interp: &mut Interpreter, context: &mut CTX) {
+        let Some(Effect { inputs, output }) = self.effect.take() else {
+            return;
+        };
+        let depth = context.journal_ref().depth();
+        let failed = interp
+            .bytecode
+            .instruction_result()
+            .is_some_and(|result| !result.is_ok());
+
+        let output = match output {
+            Output::None => None,
+            Output::Untainted => Some(None),
+            Output::Taint(taint) => Some(Some(taint)),
+            Output::Sload(address, index) => Some(if failed {
+                None
+            } else {
+                let delta = match self.find_slot(address, index) {
+                    Some(slot) if self.slots[slot].observed => None,
+                    Some(slot) => Some((slot, self.slots[slot].delta)),
+                    None => {
+                        self.slots.push(TrackedSlot {
+                            address,
+                            index,
+                            base: interp.stack.data().last().copied().unwrap_or_default(),
+                            delta: U256::ZERO,
+                            constraints: Vec::new(),
+                            observed: false,
+                            store_depth: 0,
+                        });
+                        self.live_slots += 1;
+                        Some((self.slots.len() - 1, U256::ZERO))
+                    }
+                };
+                delta.map(|(slot, delta)| Taint { slot, delta })
+            }),
+            Output::Sstore(slot, delta) => {
+                if !failed {
+                    // SSTORE gas costs and refunds depend on whether the
+                    // original, current and new values are zero.
+                    let slot = &mut self.slots[slot];
+                    for delta in [U256::ZERO, slot.delta, delta] {
+                        slot.constrain(
+                            Comparison::Eq,
+                            Operand::Slot(delta),
+                            Operand::Const(U256::ZERO),
+                        );
+                    }
+                    slot.delta = delta;
+                    slot.store_depth = slot.store_depth.max(depth);
+                }
+                None
+            }
+        };
+
+        let taints = &mut self.stacks[depth];
+        taints.truncate(taints.len().saturating_sub(inputs));
+        if let Some(output) = output {
+            taints.push(output);
+        }
+        self.sync_stack(depth, interp.stack.len());
+    }
+
+    fn call_end(&mut self, context: &mut CTX, _: &CallInputs, outcome: &mut CallOutcome) {
+        if !outcome.result.result.is_ok() {
+            self.revert_stores(context.journal_ref().depth());
+        }
+    }
+
+    fn create_end(&mut self, context: &mut CTX, _: &CreateInputs, outcome: &mut CreateOutcome) {
+        if !outcome.result.result.is_ok() {
+            self.revert_stores(context.journal_ref().depth());
+        }
+    }
+}
+
+impl CommutativeInspector {
+    // A call returned to [depth] with its storage changes reverted, which
+    // the deltas of the slots it may have stored to do not account for.
+    fn revert_stores(&mut self, depth: usize) {
+        for slot in 0..self.slots.len() {
+            if self.slots[slot].store_depth > depth {
+                self.observe(slot);
+            }
+        }
+    }
+}
+
+// A read of a storage slot whose value the transaction only added constants
+// to or compared, validated by the outcomes of its comparisons instead of by
+// the origin of the value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct CommutativeRead {
+    // The value of the slot in storage, before the block.
+    pub(crate) storage_value: U256,
+    pub(crate) constraints: Vec<Constraint>,
+}
+
+impl CommutativeRead {
+    pub(crate) fn holds(&self, value: U256) -> bool {
+        self.constraints
+            .iter()
+            .all(|constraint| constraint.holds(value))
+    }
+}
@@ -0,0 +1,128 @@
+use std::fmt::{self, Display, Write};
+
+use alloy_primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{MemoryLocation, mv_memory::MvMemory};
+
+/// A memory location that a transaction read from a lower transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DependencyLocation {
+    /// The balance of an account.
+    Balance(Address),
+    /// The nonce of an account.
+    Nonce(Address),
+    /// The code hash of an account.
+    CodeHash(Address),
+    /// A storage slot of an account.
+    Storage(Address, U256),
+    /// The self-destruct of an account, which cleared its storage.
+    SelfDestruct(Address),
+}
+
+impl From<MemoryLocation> for DependencyLocation {
+    fn from(location: MemoryLocation) -> Self {
+        match location {
+            MemoryLocation::Balance(address) => Self::Balance(address),
+            MemoryLocation::Nonce(address) => Self::Nonce(address),
+            MemoryLocation::CodeHash(address) => Self::CodeHash(address),
+            MemoryLocation::Storage(address, index) => Self::Storage(address, index),
+            MemoryLocation::SelfDestruct(address) => Self::SelfDestruct(address),
+        }
+    }
+}
+
+impl Display for DependencyLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Balance(address) => write!(f, "balance {address}"),
+            Self::Nonce(address) => write!(f, "nonce {address}"),
+            Self::CodeHash(address) => write!(f, "code {address}"),
+            Self::Storage(address, index) => write!(f, "storage {address}[{index:#x}]"),
+            Self::SelfDestruct(address) => write!(f, "self-destruct {address}"),
+        }
+    }
+}
+
+/// A transaction that read a memory location written by a lower transaction.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyEdge {
+    /// The index of the writing transaction.
+    pub from: usize,
+    /// The index of the reading transaction.
+    pub to: usize,
+    /// The memory location read.
+    pub location: DependencyLocation,
+}
+
+/// The dependencies between the transactions of a block executed in parallel,
+/// discovered from the reads of their final incarnations. Collected when
+/// [`crate::Pevm::with_dependency_graph`] is set and taken with
+/// [`crate::Pevm::take_dependency_graph`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DependencyGraph {
+    /// The gas used by each transaction of the block, zero for the excluded ones.
+    pub tx_gas: Vec<u64>,
+    /// The dependencies, sorted by the reading then the writing transaction.
+    pub edges: Vec<DependencyEdge>,
+}
+
+impl DependencyGraph {
+    pub(crate) fn new(mv_memory: &MvMemory, tx_gas: Vec<u64>) -> Self {
+        let mut edges: Vec<DependencyEdge> = mv_memory
+            .read_dependencies()
+            .into_iter()
+            .map(|(from, to, location)| DependencyEdge {
+                from,
+                to,
+                location: location.into(),
+            })
+            .collect();
+        edges.sort_unstable_by_key(|edge| (edge.to, edge.from));
+        Self { tx_gas, edges }
+    }
+
+    /// The total gas used by the block's transactions.
+    pub fn total_gas(&self) -> u64 {
+        self.tx_gas.iter().sum()
+    }
+
+    /// The gas of the heaviest chain of dependent transactions, which must
+    /// execute one after another however many workers there are.
+    pub fn critical_path_gas(&self) -> u64 {
+        // The gas used until each transaction finishes on the critical path,
+        // as transactions only depend on lower ones.
+        let mut finish_gas = vec![0; self.tx_gas.len()];
+        let mut edges = self.edges.iter().peekable();
+        for (tx_idx, gas) in self.tx_gas.iter().enumerate() {
+            let mut start_gas = 0;
+            while let Some(edge) = edges.next_if(|edge| edge.to == tx_idx) {
+                start_gas = start_gas.max(finish_gas[edge.from]);
+            }
+            finish_gas[tx_idx] = start_gas + gas;
+        }
+        finish_gas.into_iter().max().unwrap_or_default()
+    }
+
+    /// The speedup of executing the block with unlimited workers over
+    /// executing it sequentially, ignoring all overheads.
+    pub fn max_speedup(&self) -> f64 {
+        match self.critical_path_gas() {
+            0 => 1.0,
+            critical_path_gas => self.total_gas() as f64 / critical_path_gas as f64,
+        }
+    }
+
+    /// Serialize the graph to the Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph dependencies {\n");
+        for (tx_idx, gas) in self.tx_gas.iter().enumerate() {
+            let _ = writeln!(dot, "  {tx_idx} [label=\"#{tx_idx}\\n{gas} gas\"];");
+        }
+        for DependencyEdge { from, to, location } in &self.edges {
+            let _ = writeln!(dot, "  {from} -> {to} [label=\"{location}\"];");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
@@ -29,12 +29,17 @@ impl Hasher for SuffixHasher {
 /// Build a suffix hasher
 pub type BuildSuffixHasher = BuildHasherDefault<SuffixHasher>;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 enum MemoryLocation {
-    // TODO: Separate an account's balance and nonce?
-    Basic(Address),
+    // An account's balance and nonce are separate locations, for transactions
+    // that only credit an account to not conflict with those that bump its nonce.
+    Balance(Address),
+    Nonce(Address),
     CodeHash(Address),
     Storage(Address, U256),
+    // The last self-destruct of an account, which clears its storage for the
+    // later transactions.
+    SelfDestruct(Address),
 }
 
 // We only need the full memory location to read from storage.
@@ -65,6 +70,8 @@ impl Hasher for IdentityHasher {
 /// Build an identity hasher
 pub type BuildIdentityHasher = BuildHasherDefault<IdentityHasher>;
 
+// Different locations with the same hash are detected by [MvMemory], which
+// falls back to sequential execution instead of mixing up their values.
 // TODO: Ensure it's not easy to hand-craft transactions and storage slots
 // that can cause a lot of collisions that destroys pevm's performance.
 #[inline(always)]
@@ -77,7 +84,8 @@ fn hash_deterministic<T: Hash>(x: T) -> u64 {
 // matches & potentially dangerous mismatch mistakes.
 #[derive(Debug, Clone)]
 enum MemoryValue {
-    Basic(AccountBasic),
+    Balance(U256),
+    Nonce(u64),
     CodeHash(B256),
     Storage(U256),
     // We lazily update the beneficiary balance to avoid continuous
@@ -88,9 +96,15 @@ enum MemoryValue {
     // when there is an explicit read.
     // Explicit balance addition.
     LazyRecipient(U256),
-    // Explicit balance subtraction & implicit nonce increment.
+    // Explicit balance subtraction. Lazy senders still write their new
+    // nonce to the nonce location.
     LazySender(U256),
-    // The account was self-destructed.
+    // Explicit (wrapping) addition to a storage slot that the transaction
+    // only added a constant to, like a token recipient's balance. Fully
+    // evaluated like the lazy balances.
+    LazyStorage(U256),
+    // The account was self-destructed, clearing its balance, nonce, code,
+    // and storage. Written to all of its account locations.
     SelfDestructed,
 }
 
@@ -162,6 +176,12 @@ struct TxVersion {
 enum ReadOrigin {
     MvMemory(TxVersion),
     Storage,
+    // A storage slot cleared by the self-destruct of its account in a
+    // transaction, and not written to after it.
+    SelfDestruct(TxIdx),
+    // A storage slot whose value only matters to the transaction through
+    // comparisons, which are re-evaluated instead.
+    Commutative(Box<CommutativeRead>),
 }
 
 // Most memory locations only have one read origin. Lazy updated ones like
@@ -174,17 +194,38 @@ type ReadOrigins = SmallVec<[ReadOrigin; 1]>;
 type ReadSet = HashMap<MemoryLocationHash, ReadOrigins, BuildIdentityHasher>;
 
 // The updates made by this transaction incarnation, which is applied
-// to the multi-version data structure at the end of execution.
-type WriteSet = Vec<(MemoryLocationHash, MemoryValue)>;
+// to the multi-version data structure at the end of execution. The full
+// locations are kept to detect hash collisions.
+type WriteSet = Vec<(MemoryLocationHash, MemoryLocation, MemoryValue)>;
 
 // A scheduled worker task
-// TODO: Add more useful work when there are idle workers like near
-// the end of block execution, while waiting for a huge blocking
-// transaction to resolve, etc.
 #[derive(Debug)]
 enum Task {
     Execution(TxVersion),
     Validation(TxVersion),
+    // Useful work for workers that would otherwise spin while the last
+    // transactions execute, like near the end of block execution or while
+    // waiting for a huge blocking transaction to resolve.
+    Idle(IdleTask),
+}
+
+#[derive(Debug)]
+enum IdleTask {
+    // Fetch the state that a transaction waiting to re-execute likely reads.
+    PrefetchTx(TxIdx),
+    // Fetch the state before the block of the lazily updated accounts and
+    // storage slots, which are read again to evaluate them.
+    PrefetchLazy,
+    // Validate an executed transaction ahead of the validation tasks, to
+    // abort it early if it read values that changed.
+    Revalidation(TxVersion),
+    // Wait for the storage to fetch the reads of the parked transactions,
+    // then resume them.
+    ResumeParked,
+    // Lazy addresses are only evaluated after the block, as they need the
+    // final writes of every transaction, so idle workers only prefetch their
+    // state. Building the block's results is a move of each result, which
+    // is cheaper than handing it to an idle worker.
 }
 
 bitflags! {
@@ -195,6 +236,10 @@ bitflags! {
         // scheduler, meaning a [false] here will still be validated if
         // there was a lower transaction that has broken the preprocessed
         // dependency chain and returned [true]
+        // TODO: As the empty flag, every set of flags contains it, so every
+        // execution is still validated. Skipping the validation of the
+        // first and lazy transactions needs scheduler tests on contended
+        // raw transfer blocks first.
         const NeedValidation = 0;
         // We need to validate from the next transaction if this execution
         // wrote to a new location.
@@ -215,18 +260,48 @@ macro_rules! index_mutex {
 }
 
 pub mod chain;
+mod commutative;
+use commutative::CommutativeRead;
+pub use commutative::{CommutativeInspector, PevmInspector};
 mod compat;
+mod dependency_graph;
+pub use dependency_graph::{DependencyEdge, DependencyGraph, DependencyLocation};
 mod mv_memory;
 mod pevm;
-pub use pevm::{Pevm, PevmError, PevmResult, execute_revm_sequential};
+pub use pevm::{
+    Pevm, PevmBlockExecutionResult, PevmBuiltBlock, PevmError, PevmMode, PevmResult,
+    execute_revm_sequential,
+};
+mod pipeline;
+mod policy;
+pub use policy::{
+    AdaptiveExecutionPolicy, BlockFeatures, DefaultExecutionPolicy, ExecutionPolicy,
+    ExecutionStrategy,
+};
+mod prefetcher;
+mod replay;
+pub use replay::{ScheduleEvent, ScheduleLog};
 mod scheduler;
+mod session;
+pub use session::{PevmSession, PevmSessionReceipt};
+mod state_diff;
+pub use state_diff::{AccountDiff, BlockStateDiff, StorageSlotDiff};
+mod state_root;
+pub use state_root::{StateRootProvider, calculate_state_root};
+mod stats;
+pub use stats::{FallbackReason, PevmStats};
 mod storage;
 pub use storage::{
-    AccountBasic, BlockHashes, Bytecodes, ChainState, EvmAccount, EvmCode, InMemoryStorage,
-    Storage, StorageWrapper,
+    AccountBasic, AsyncStorage, BlockHashes, Bytecodes, ChainState, EvmAccount, EvmCode,
+    InMemoryStorage, Storage, StorageWrapper,
 };
+mod verify;
+pub use verify::{Divergence, DivergenceLocation, VerifyMode};
 mod vm;
-pub use vm::{ExecutionError, PevmTxExecutionResult};
+pub use vm::{EvmStateTransitions, ExecutionError, PevmTxExecutionResult};
+mod worker_pool;
 
+#[cfg(feature = "async-storage")]
+pub use storage::PrefetchStorage;
 #[cfg(feature = "rpc-storage")]
 pub use storage::{RpcStorage, RpcStorageError};
@@ -1,17 +1,66 @@
 use std::{
     collections::{BTreeMap, HashSet},
-    sync::Mutex,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+    },
 };
 
-use alloy_primitives::{Address, B256};
-use dashmap::DashMap;
-use revm::state::Bytecode;
+use alloy_primitives::{Address, B256, U256};
+use dashmap::{DashMap, mapref::one::Ref};
+use hashbrown::HashMap;
+use revm::{context::TxEnv, state::Bytecode};
+use rustc_hash::FxBuildHasher;
 
 use crate::{
-    BuildIdentityHasher, BuildSuffixHasher, MemoryEntry, MemoryLocationHash, ReadOrigin, ReadSet,
-    TxIdx, TxVersion, WriteSet,
+    BuildIdentityHasher, BuildSuffixHasher, MemoryEntry, MemoryLocation, MemoryLocationHash,
+    MemoryValue, ReadOrigin, ReadSet, ScheduleEvent, TxIdx, TxVersion, WriteSet,
+    commutative::{CommutativeRead, erc20_credited_holder},
+    hash_deterministic,
+    replay::{ScheduleRecorder, read_set_digest},
+    vm::ReadError,
 };
 
+// The writes of transactions to a memory location.
+#[derive(Default, Debug)]
+pub(crate) struct LocationWrites {
+    // The full location of the writes, to detect other locations with the
+    // same hash. Unset while there are only estimated writes.
+    location: Option<MemoryLocation>,
+    pub(crate) entries: BTreeMap<TxIdx, MemoryEntry>,
+}
+
+impl LocationWrites {
+    // Check that the writes are to [location] and not to a different one with
+    // the same hash, whose values would otherwise be mixed up.
+    fn verify(
+        &self,
+        location_hash: MemoryLocationHash,
+        location: &MemoryLocation,
+    ) -> Result<(), ReadError> {
+        match &self.location {
+            Some(written_location) if written_location != location => {
+                Err(ReadError::LocationCollision(location_hash))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// The closest earlier transactions that a transaction failing its sender
+// checks likely waits for, indexed while preprocessing the block.
+#[derive(Default, Debug)]
+struct SenderDependencies {
+    // The closest earlier transaction from the same sender, which writes the
+    // nonce that the transaction checks.
+    sender_tx: Option<TxIdx>,
+    // The closest earlier transaction that transfers value to the sender.
+    funding_tx: Option<TxIdx>,
+    // One more than the last incarnation of the transaction that waited for
+    // the indexed transaction, or zero if none has.
+    waited_incarnation: AtomicUsize,
+}
+
 #[derive(Default, Debug)]
 struct LastLocations {
     read: ReadSet,
@@ -20,57 +69,212 @@ struct LastLocations {
 }
 
 type LazyAddresses = HashSet<Address, BuildSuffixHasher>;
+type LazyStorage = HashSet<(Address, U256), FxBuildHasher>;
 
 /// The `MvMemory` contains shared memory in a form of a multi-version data
 /// structure for values written and read by different transactions. It stores
 /// multiple writes for each memory location, along with a value and an associated
 /// version of a corresponding transaction.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct MvMemory {
     /// The list of transaction incarnations and written values for each memory location
     // No more hashing is required as we already identify memory locations by their hash
     // in the read & write sets. [dashmap] having a dedicated interface for this use case
     // (that skips hashing for [u64] keys) would make our code cleaner and "faster".
     // Nevertheless, the compiler should be good enough to optimize these cases anyway.
-    pub(crate) data: DashMap<MemoryLocationHash, BTreeMap<TxIdx, MemoryEntry>, BuildIdentityHasher>,
+    // TODO: Fine-tune the number of shards, like to the next number of two from the
+    // number of worker threads.
+    pub(crate) data: DashMap<MemoryLocationHash, LocationWrites, BuildIdentityHasher>,
     /// Last read & written locations of each transaction
     last_locations: Vec<Mutex<LastLocations>>,
     /// Lazy addresses that need full evaluation at the end of the block
     lazy_addresses: Mutex<LazyAddresses>,
+    /// Storage slots with commutative writes that need full evaluation at the end of the block
+    lazy_storage: Mutex<LazyStorage>,
+    /// The transactions that each transaction failing its sender checks waits for
+    sender_dependencies: Vec<SenderDependencies>,
+    /// Whether to pick the transactions to inspect for commutative storage writes
+    commutative_writes: bool,
+    /// Whether to skip estimating the dependencies between transactions
+    skip_dependency_estimation: bool,
+    /// Whether to inspect each transaction for commutative storage writes
+    inspected_txs: Vec<bool>,
     /// New bytecodes deployed in this block
     pub(crate) new_bytecodes: DashMap<B256, Bytecode, BuildSuffixHasher>,
+    /// The recorder of the finished executions, when recording the schedule
+    recorder: Option<Arc<ScheduleRecorder>>,
 }
 
 impl MvMemory {
+    #[cfg(test)]
     pub(crate) fn new(
         block_size: usize,
         estimated_locations: impl IntoIterator<Item = (MemoryLocationHash, Vec<TxIdx>)>,
         lazy_addresses: impl IntoIterator<Item = Address>,
     ) -> Self {
-        // TODO: Fine-tune the number of shards, like to the next number of two from the
-        // number of worker threads.
-        let data = DashMap::default();
+        let mut mv_memory = Self::default();
+        mv_memory.reset(block_size, estimated_locations, lazy_addresses);
+        mv_memory
+    }
+
+    // Clear the memory of an executed block in place, keeping the allocated
+    // capacity of its maps and read & write sets for the next blocks.
+    pub(crate) fn clear(&mut self) {
+        self.data.clear();
+        for last_locations in &mut self.last_locations {
+            let last_locations = last_locations.get_mut().unwrap();
+            last_locations.read.clear();
+            last_locations.write.clear();
+        }
+        self.lazy_addresses.get_mut().unwrap().clear();
+        self.lazy_storage.get_mut().unwrap().clear();
+        self.sender_dependencies.clear();
+        self.new_bytecodes.clear();
+        self.recorder = None;
+    }
+
+    // Reset the memory for a new block, which is cheap when it was already
+    // cleared, like when recycled from a previous block.
+    pub(crate) fn reset(
+        &mut self,
+        block_size: usize,
+        estimated_locations: impl IntoIterator<Item = (MemoryLocationHash, Vec<TxIdx>)>,
+        lazy_addresses: impl IntoIterator<Item = Address>,
+    ) {
+        self.last_locations.truncate(block_size);
+        self.clear();
+        self.last_locations.resize_with(block_size, Mutex::default);
+        self.sender_dependencies
+            .resize_with(block_size, SenderDependencies::default);
+        self.inspected_txs.clear();
+        self.inspected_txs.resize(block_size, false);
         // We preallocate estimated locations to avoid restructuring trees at runtime
         // while holding a write lock. Ideally [dashmap] would have a lock-free
         // construction API. This is acceptable for now as it's a non-congested one-time
         // cost.
-        for (location_hash, estimated_tx_idxs) in estimated_locations {
-            data.insert(
+        for (location_hash, mut estimated_tx_idxs) in estimated_locations {
+            estimated_tx_idxs.sort_unstable();
+            estimated_tx_idxs.dedup();
+            // Register the estimates as the last written locations so they are
+            // cleared if the transaction ends up not writing to them, like when
+            // it is excluded from the block.
+            for tx_idx in &estimated_tx_idxs {
+                self.last_locations[*tx_idx]
+                    .get_mut()
+                    .unwrap()
+                    .write
+                    .push(location_hash);
+            }
+            self.data.insert(
                 location_hash,
-                estimated_tx_idxs
-                    .into_iter()
-                    .map(|tx_idx| (tx_idx, MemoryEntry::Estimate))
-                    .collect(),
+                LocationWrites {
+                    location: None,
+                    entries: estimated_tx_idxs
+                        .into_iter()
+                        .map(|tx_idx| (tx_idx, MemoryEntry::Estimate))
+                        .collect(),
+                },
             );
         }
-        Self {
-            data,
-            last_locations: (0..block_size).map(|_| Mutex::default()).collect(),
-            lazy_addresses: Mutex::new(LazyAddresses::from_iter(lazy_addresses)),
-            // TODO: Fine-tune the number of shards, like to the next number of two from the
-            // number of worker threads.
-            new_bytecodes: DashMap::default(),
+        self.lazy_addresses
+            .get_mut()
+            .unwrap()
+            .extend(lazy_addresses);
+    }
+
+    // Whether to pick the transactions to inspect for commutative storage
+    // writes when preparing the block, which is off by default. This is kept
+    // across resets, to be set before the block is prepared.
+    pub(crate) const fn set_commutative_writes(&mut self, commutative_writes: bool) {
+        self.commutative_writes = commutative_writes;
+    }
+
+    // Whether to skip estimating the dependencies between transactions when
+    // preparing the block, to measure what the estimates save. This is kept
+    // across resets, to be set before the block is prepared.
+    pub(crate) const fn set_skip_dependency_estimation(&mut self, skip: bool) {
+        self.skip_dependency_estimation = skip;
+    }
+
+    // Estimate the locations that transactions write and later ones read, for
+    // the readers to block on the writers instead of reading stale values and
+    // being re-executed. This chains the transactions of the same sender, which
+    // write the nonce that the next one reads. Storage slots are left to
+    // execution: the hot slots of real blocks, like the shared state of pools
+    // and oracles, are not predictable from the transactions.
+    pub(crate) fn estimate_dependencies<'a>(
+        &self,
+        txs: impl IntoIterator<Item = (TxIdx, &'a TxEnv)>,
+        estimated_locations: &mut HashMap<MemoryLocationHash, Vec<TxIdx>, BuildIdentityHasher>,
+    ) {
+        if self.skip_dependency_estimation {
+            return;
         }
+        // The last transaction of each sender so far.
+        let mut last_txs: HashMap<Address, TxIdx, FxBuildHasher> = HashMap::default();
+        for (tx_idx, tx) in txs {
+            if let Some(prev_tx_idx) = last_txs.insert(tx.caller, tx_idx) {
+                estimated_locations
+                    .entry(hash_deterministic(MemoryLocation::Nonce(tx.caller)))
+                    .or_default()
+                    .push(prev_tx_idx);
+            }
+        }
+    }
+
+    // Log the executions as finished to [recorder] as their writes are recorded.
+    pub(crate) fn record_schedule(&mut self, recorder: Arc<ScheduleRecorder>) {
+        self.recorder = Some(recorder);
+    }
+
+    // Append [additional] transactions to the block, which have not written
+    // to any location yet.
+    pub(crate) fn extend(&mut self, additional: usize) {
+        self.last_locations
+            .extend((0..additional).map(|_| Mutex::default()));
+        self.sender_dependencies
+            .extend((0..additional).map(|_| SenderDependencies::default()));
+    }
+
+    // Append the transactions of another block after the current ones, with
+    // their estimated locations and lazy addresses.
+    pub(crate) fn append(&mut self, other: Self) {
+        let offset = self.last_locations.len();
+        // The other block has not been executed, so it only has estimated
+        // writes without full locations.
+        for (location_hash, written_transactions) in other.data {
+            let mut writes = self.data.entry(location_hash).or_default();
+            for (tx_idx, entry) in written_transactions.entries {
+                writes.entries.insert(tx_idx + offset, entry);
+            }
+        }
+        self.last_locations.extend(other.last_locations);
+        self.sender_dependencies
+            .extend(
+                other
+                    .sender_dependencies
+                    .into_iter()
+                    .map(|dependencies| SenderDependencies {
+                        sender_tx: dependencies.sender_tx.map(|tx_idx| tx_idx + offset),
+                        funding_tx: dependencies.funding_tx.map(|tx_idx| tx_idx + offset),
+                        waited_incarnation: dependencies.waited_incarnation,
+                    }),
+            );
+        self.inspected_txs.extend(other.inspected_txs);
+        self.lazy_addresses
+            .get_mut()
+            .unwrap()
+            .extend(other.lazy_addresses.into_inner().unwrap());
+        self.lazy_storage
+            .get_mut()
+            .unwrap()
+            .extend(other.lazy_storage.into_inner().unwrap());
+        self.new_bytecodes.extend(other.new_bytecodes);
+    }
+
+    // The number of transactions in the block.
+    pub(crate) const fn block_size(&self) -> usize {
+        self.last_locations.len()
     }
 
     pub(crate) fn add_lazy_addresses(&self, new_lazy_addresses: impl IntoIterator<Item = Address>) {
@@ -80,27 +284,130 @@ impl MvMemory {
         }
     }
 
+    pub(crate) fn add_lazy_storage(&self, address: Address, index: U256) {
+        self.lazy_storage.lock().unwrap().insert((address, index));
+    }
+
+    // Index the closest earlier transactions from the same sender and funding
+    // the sender of each transaction, to retry the transactions failing their
+    // sender checks after them.
+    pub(crate) fn index_senders<'a>(&mut self, txs: impl IntoIterator<Item = (TxIdx, &'a TxEnv)>) {
+        // Size the indices for the whole block upfront instead of growing them.
+        let block_size = self.sender_dependencies.len();
+        let mut last_sender_txs: HashMap<Address, TxIdx, BuildSuffixHasher> =
+            HashMap::with_capacity_and_hasher(block_size, BuildSuffixHasher::default());
+        let mut last_funding_txs: HashMap<Address, TxIdx, BuildSuffixHasher> =
+            HashMap::with_capacity_and_hasher(block_size, BuildSuffixHasher::default());
+        for (tx_idx, tx) in txs {
+            let dependencies = &mut self.sender_dependencies[tx_idx];
+            dependencies.sender_tx = last_sender_txs.insert(tx.caller, tx_idx);
+            dependencies.funding_tx = last_funding_txs.get(&tx.caller).copied();
+            if let Some(to) = tx.kind.to()
+                && tx.value > U256::ZERO
+            {
+                last_funding_txs.insert(*to, tx_idx);
+            }
+        }
+    }
+
+    // Pick the transactions to inspect for commutative storage writes. The
+    // inspection slows execution down, so it is limited to the calls likely
+    // to conflict on additions: ERC20 transfers crediting a holder that other
+    // transfers of the block also credit, and other calls to contracts that
+    // several transactions call.
+    pub(crate) fn index_commutative_calls<'a>(
+        &mut self,
+        txs: impl IntoIterator<Item = (TxIdx, &'a TxEnv)>,
+    ) {
+        if !self.commutative_writes {
+            return;
+        }
+        let mut calls = Vec::with_capacity(self.inspected_txs.len());
+        let mut call_counts: HashMap<(Address, Option<Address>), usize, FxBuildHasher> =
+            HashMap::default();
+        for (tx_idx, tx) in txs {
+            let Some(to) = tx.kind.to() else {
+                continue;
+            };
+            let credited = erc20_credited_holder(&tx.data);
+            *call_counts.entry((*to, credited)).or_default() += 1;
+            calls.push((tx_idx, (*to, credited)));
+        }
+        for (tx_idx, call) in calls {
+            self.inspected_txs[tx_idx] = call_counts[&call] > 1;
+        }
+    }
+
+    pub(crate) fn is_inspected(&self, tx_idx: TxIdx) -> bool {
+        self.inspected_txs
+            .get(tx_idx)
+            .is_some_and(|inspected| *inspected)
+    }
+
+    // Get the transaction to wait for before retrying [tx_version], which
+    // failed its nonce check, or its balance check if [lacks_funds]. The first
+    // failure of each incarnation waits for the indexed transaction that likely
+    // fixes the check. An immediate retry of the same incarnation, as the
+    // indexed transaction has already executed, waits for the previous
+    // transaction instead, in case the sender is funded by internal
+    // transactions. The target only depends on the incarnation and not on
+    // how many times earlier ones failed.
+    pub(crate) fn sender_dependency(&self, tx_version: &TxVersion, lacks_funds: bool) -> TxIdx {
+        let tx_idx = tx_version.tx_idx;
+        let Some(dependencies) = self.sender_dependencies.get(tx_idx) else {
+            return tx_idx - 1;
+        };
+        let waited_incarnation = tx_version.tx_incarnation + 1;
+        let indexed_tx = if lacks_funds {
+            dependencies.funding_tx
+        } else {
+            dependencies.sender_tx
+        };
+        match indexed_tx {
+            Some(indexed_tx)
+                if dependencies
+                    .waited_incarnation
+                    .swap(waited_incarnation, Ordering::Relaxed)
+                    != waited_incarnation =>
+            {
+                indexed_tx
+            }
+            _ => tx_idx - 1,
+        }
+    }
+
     // Apply a new pair of read & write sets to the multi-version data structure.
     // Return whether a write occurred to a memory location not written to by
     // the previous incarnation of the same transaction. This determines whether
-    // the executed higher transactions need re-validation.
+    // the executed higher transactions need re-validation. [read_set] is
+    // swapped with the previous one of the transaction, for the caller to
+    // reuse its allocation. Return an error if a written location has the
+    // same hash as a different one.
     pub(crate) fn record(
         &self,
         tx_version: &TxVersion,
-        read_set: ReadSet,
+        read_set: &mut ReadSet,
         write_set: WriteSet,
-    ) -> bool {
+    ) -> Result<bool, ReadError> {
+        if let Some(recorder) = &self.recorder {
+            recorder.record(ScheduleEvent::FinishedExecution {
+                tx_idx: tx_version.tx_idx,
+                incarnation: tx_version.tx_incarnation,
+                reads: read_set_digest(read_set),
+            });
+        }
+
         let mut last_locations = index_mutex!(self.last_locations, tx_version.tx_idx);
-        last_locations.read = read_set;
+        std::mem::swap(&mut last_locations.read, read_set);
 
         // TODO: Group updates by shard to avoid locking operations.
         // Remove old locations that aren't written to anymore.
         let mut last_location_idx = 0;
         while last_location_idx < last_locations.write.len() {
             let prev_location = unsafe { last_locations.write.get_unchecked(last_location_idx) };
-            if write_set.iter().all(|(l, _)| l != prev_location) {
+            if write_set.iter().all(|(l, ..)| l != prev_location) {
                 if let Some(mut written_transactions) = self.data.get_mut(prev_location) {
-                    written_transactions.remove(&tx_version.tx_idx);
+                    written_transactions.entries.remove(&tx_version.tx_idx);
                 }
                 last_locations.write.swap_remove(last_location_idx);
             } else {
@@ -111,18 +418,40 @@ impl MvMemory {
         // Register new writes.
         let mut wrote_new_location = false;
 
-        for (location, value) in write_set {
-            self.data.entry(location).or_default().insert(
+        for (location_hash, location, value) in write_set {
+            let mut writes = self.data.entry(location_hash).or_default();
+            writes.verify(location_hash, &location)?;
+            writes.location = Some(location);
+            writes.entries.insert(
                 tx_version.tx_idx,
                 MemoryEntry::Data(tx_version.tx_incarnation, value),
             );
-            if !last_locations.write.contains(&location) {
-                last_locations.write.push(location);
+            if !last_locations.write.contains(&location_hash) {
+                last_locations.write.push(location_hash);
                 wrote_new_location = true;
             }
         }
 
-        wrote_new_location
+        Ok(wrote_new_location)
+    }
+
+    // Get the writes to [location], checking that they are not to a different
+    // location with the same hash.
+    pub(crate) fn location_writes(
+        &self,
+        location_hash: MemoryLocationHash,
+        location: &MemoryLocation,
+    ) -> Result<Option<Ref<'_, MemoryLocationHash, LocationWrites>>, ReadError> {
+        let Some(writes) = self.data.get(&location_hash) else {
+            return Ok(None);
+        };
+        writes.verify(location_hash, location)?;
+        Ok(Some(writes))
+    }
+
+    // The digest of the last read set recorded by an execution of [tx_idx].
+    pub(crate) fn read_set_digest(&self, tx_idx: TxIdx) -> u64 {
+        read_set_digest(&index_mutex!(self.last_locations, tx_idx).read)
     }
 
     // Obtain the last read set recorded by an execution of [tx_idx] and check
@@ -139,8 +468,12 @@ impl MvMemory {
     // can be aborted at most once).
     pub(crate) fn validate_read_locations(&self, tx_idx: TxIdx) -> bool {
         for (location, prior_origins) in &index_mutex!(self.last_locations, tx_idx).read {
-            if let Some(written_transactions) = self.data.get(location) {
-                let mut iter = written_transactions.range(..tx_idx);
+            if let [ReadOrigin::Commutative(read)] = prior_origins.as_slice() {
+                if !self.validate_commutative_read(*location, tx_idx, read) {
+                    return false;
+                }
+            } else if let Some(written_transactions) = self.data.get(location) {
+                let mut iter = written_transactions.entries.range(..tx_idx);
                 for prior_origin in prior_origins {
                     if let ReadOrigin::MvMemory(prior_version) = prior_origin {
                         // Found something: Must match version.
@@ -159,6 +492,16 @@ impl MvMemory {
                             return false;
                         }
                     }
+                    // Read as cleared by a self-destruct but there is now
+                    // something in between!
+                    else if let ReadOrigin::SelfDestruct(cleared_by) = prior_origin {
+                        if iter
+                            .next_back()
+                            .is_some_and(|(closest_idx, _)| closest_idx > cleared_by)
+                        {
+                            return false;
+                        }
+                    }
                     // Read from storage but there is now something
                     // in between!
                     else if iter.next_back().is_some() {
@@ -167,26 +510,368 @@ impl MvMemory {
                 }
             }
             // Read from multi-version data but now it's cleared.
-            else if prior_origins.len() != 1 || prior_origins.last() != Some(&ReadOrigin::Storage)
-            {
+            else if !matches!(
+                prior_origins.as_slice(),
+                [ReadOrigin::Storage | ReadOrigin::SelfDestruct(_)]
+            ) {
                 return false;
             }
         }
         true
     }
 
+    // Check that the comparisons of a commutative read of [location] by
+    // [tx_idx] have the same outcomes for the current value of the slot, which
+    // is the closest full value before the transaction plus the deltas after it.
+    fn validate_commutative_read(
+        &self,
+        location: MemoryLocationHash,
+        tx_idx: TxIdx,
+        read: &CommutativeRead,
+    ) -> bool {
+        let mut addition = U256::ZERO;
+        let mut value = None;
+        if let Some(written_transactions) = self.data.get(&location) {
+            for (_, entry) in written_transactions.entries.range(..tx_idx).rev() {
+                match entry {
+                    MemoryEntry::Data(_, MemoryValue::Storage(storage_value)) => {
+                        value = Some(*storage_value);
+                        break;
+                    }
+                    MemoryEntry::Data(_, MemoryValue::LazyStorage(delta)) => {
+                        addition = addition.wrapping_add(*delta);
+                    }
+                    _ => return false,
+                }
+            }
+        }
+        read.holds(value.unwrap_or(read.storage_value).wrapping_add(addition))
+    }
+
     // Replace the write set of the aborted version in the shared memory data
     // structure with special ESTIMATE markers to quickly abort higher transactions
     // that read them.
     pub(crate) fn convert_writes_to_estimates(&self, tx_idx: TxIdx) {
         for location in &index_mutex!(self.last_locations, tx_idx).write {
             if let Some(mut written_transactions) = self.data.get_mut(location) {
-                written_transactions.insert(tx_idx, MemoryEntry::Estimate);
+                written_transactions
+                    .entries
+                    .insert(tx_idx, MemoryEntry::Estimate);
+            }
+        }
+    }
+
+    // The writes that the last incarnation of each transaction read, as the
+    // writing transaction, the reading transaction, and the read location.
+    // Commutative reads only depend on comparisons that still hold after the
+    // lower writes, so they are not dependencies.
+    pub(crate) fn read_dependencies(&self) -> Vec<(TxIdx, TxIdx, MemoryLocation)> {
+        let mut dependencies = Vec::new();
+        for (tx_idx, last_locations) in self.last_locations.iter().enumerate() {
+            for (location_hash, origins) in &last_locations.lock().unwrap().read {
+                let location = self
+                    .data
+                    .get(location_hash)
+                    .and_then(|writes| writes.location);
+                for origin in origins {
+                    let (writer_idx, location) = match origin {
+                        ReadOrigin::MvMemory(version) => (version.tx_idx, location),
+                        // A cleared storage slot may have no writes with its full
+                        // location, so we fall back to the self-destructed account.
+                        ReadOrigin::SelfDestruct(writer_idx) => (
+                            *writer_idx,
+                            location.or_else(|| self.written_self_destruct(*writer_idx)),
+                        ),
+                        ReadOrigin::Storage | ReadOrigin::Commutative(_) => continue,
+                    };
+                    if let Some(location) = location {
+                        dependencies.push((writer_idx, tx_idx, location));
+                    }
+                }
             }
         }
+        dependencies
+    }
+
+    // The self-destruct location written by the last incarnation of [tx_idx].
+    fn written_self_destruct(&self, tx_idx: TxIdx) -> Option<MemoryLocation> {
+        index_mutex!(self.last_locations, tx_idx)
+            .write
+            .iter()
+            .filter_map(|location_hash| self.data.get(location_hash)?.location)
+            .find(|location| matches!(location, MemoryLocation::SelfDestruct(_)))
+    }
+
+    // The lazily updated accounts and storage slots so far, with [None]
+    // indices for accounts.
+    pub(crate) fn lazy_locations(&self) -> Vec<(Address, Option<U256>)> {
+        let lazy_addresses = self.lazy_addresses.lock().unwrap();
+        let lazy_storage = self.lazy_storage.lock().unwrap();
+        lazy_addresses
+            .iter()
+            .map(|address| (*address, None))
+            .chain(
+                lazy_storage
+                    .iter()
+                    .map(|(address, index)| (*address, Some(*index))),
+            )
+            .collect()
     }
 
     pub(crate) fn consume_lazy_addresses(&self) -> impl IntoIterator<Item = Address> {
         std::mem::take(&mut *self.lazy_addresses.lock().unwrap()).into_iter()
     }
+
+    pub(crate) fn consume_lazy_storage(&self) -> impl IntoIterator<Item = (Address, U256)> {
+        std::mem::take(&mut *self.lazy_storage.lock().unwrap()).into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::context::BlockEnv;
+
+    use super::*;
+    use crate::{
+        chain::{PevmChain, PevmEthereum},
+        commutative::{Comparison, Constraint, Operand},
+        vm::VmExecutionError,
+    };
+
+    // Two different storage slots forced to the same hash, which real hashes
+    // would only share by a rare or hand-crafted collision.
+    const LOCATION_HASH: MemoryLocationHash = 42;
+    const LOCATION_A: MemoryLocation =
+        MemoryLocation::Storage(Address::repeat_byte(0xaa), U256::ONE);
+    const LOCATION_B: MemoryLocation =
+        MemoryLocation::Storage(Address::repeat_byte(0xbb), U256::ONE);
+
+    fn write(
+        mv_memory: &MvMemory,
+        tx_idx: TxIdx,
+        location: MemoryLocation,
+    ) -> Result<bool, ReadError> {
+        mv_memory.record(
+            &TxVersion {
+                tx_idx,
+                tx_incarnation: 0,
+            },
+            &mut ReadSet::default(),
+            vec![(LOCATION_HASH, location, MemoryValue::Storage(U256::ONE))],
+        )
+    }
+
+    #[test]
+    fn colliding_locations() {
+        let mv_memory = MvMemory::new(3, [], []);
+        assert_eq!(write(&mv_memory, 0, LOCATION_A), Ok(true));
+        assert_eq!(write(&mv_memory, 1, LOCATION_A), Ok(true));
+        assert!(
+            mv_memory
+                .location_writes(LOCATION_HASH, &LOCATION_A)
+                .unwrap()
+                .is_some()
+        );
+        // Reading or writing the other location falls back to sequential execution
+        // instead of mixing up their values.
+        assert_eq!(
+            mv_memory.location_writes(LOCATION_HASH, &LOCATION_B).err(),
+            Some(ReadError::LocationCollision(LOCATION_HASH))
+        );
+        assert_eq!(
+            write(&mv_memory, 2, LOCATION_B),
+            Err(ReadError::LocationCollision(LOCATION_HASH))
+        );
+        assert!(matches!(
+            VmExecutionError::from(ReadError::LocationCollision(LOCATION_HASH)),
+            VmExecutionError::FallbackToSequential
+        ));
+    }
+
+    #[test]
+    fn colliding_estimated_locations() {
+        // Estimates have no full location until they are written.
+        let mv_memory = MvMemory::new(3, [(LOCATION_HASH, vec![0])], []);
+        assert!(
+            mv_memory
+                .location_writes(LOCATION_HASH, &LOCATION_B)
+                .unwrap()
+                .is_some()
+        );
+        assert_eq!(write(&mv_memory, 0, LOCATION_A), Ok(false));
+        assert_eq!(
+            mv_memory.location_writes(LOCATION_HASH, &LOCATION_B).err(),
+            Some(ReadError::LocationCollision(LOCATION_HASH))
+        );
+        assert_eq!(
+            write(&mv_memory, 1, LOCATION_B),
+            Err(ReadError::LocationCollision(LOCATION_HASH))
+        );
+    }
+
+    #[test]
+    fn commutative_reads() {
+        let version = |tx_idx| TxVersion {
+            tx_idx,
+            tx_incarnation: 0,
+        };
+        let write_delta = |mv_memory: &MvMemory, tx_idx, delta: U256| {
+            mv_memory
+                .record(
+                    &version(tx_idx),
+                    &mut ReadSet::default(),
+                    vec![(LOCATION_HASH, LOCATION_A, MemoryValue::LazyStorage(delta))],
+                )
+                .unwrap();
+        };
+        let mv_memory = MvMemory::new(3, [], []);
+        write_delta(&mv_memory, 0, U256::from(100));
+        write_delta(&mv_memory, 1, U256::from(30).wrapping_neg());
+        // The last transaction checked that the slot has at least 50, after
+        // reading 150 from storage plus the deltas.
+        let mut read_set = ReadSet::default();
+        read_set.insert(
+            LOCATION_HASH,
+            [ReadOrigin::Commutative(Box::new(CommutativeRead {
+                storage_value: U256::from(80),
+                constraints: vec![Constraint::new(
+                    Comparison::Lt,
+                    Operand::Slot(U256::ZERO),
+                    Operand::Const(U256::from(50)),
+                    U256::from(150),
+                )],
+            }))]
+            .into_iter()
+            .collect(),
+        );
+        mv_memory
+            .record(&version(2), &mut read_set, Vec::new())
+            .unwrap();
+        assert!(mv_memory.validate_read_locations(2));
+        // Other deltas do not invalidate the read while the check holds.
+        write_delta(&mv_memory, 1, U256::from(100).wrapping_neg());
+        assert!(mv_memory.validate_read_locations(2));
+        write_delta(&mv_memory, 1, U256::from(140).wrapping_neg());
+        assert!(!mv_memory.validate_read_locations(2));
+        // A full value replaces the storage value and earlier deltas, while an
+        // estimate may be anything.
+        mv_memory
+            .record(
+                &version(1),
+                &mut ReadSet::default(),
+                vec![(
+                    LOCATION_HASH,
+                    LOCATION_A,
+                    MemoryValue::Storage(U256::from(60)),
+                )],
+            )
+            .unwrap();
+        assert!(mv_memory.validate_read_locations(2));
+        mv_memory.convert_writes_to_estimates(1);
+        assert!(!mv_memory.validate_read_locations(2));
+    }
+
+    #[test]
+    fn inspected_calls() {
+        let token = Address::repeat_byte(0x70);
+        let transfer = |to: u8| TxEnv {
+            kind: revm::context::TransactTo::Call(token),
+            data: [
+                &[0xa9, 0x05, 0x9c, 0xbb][..],
+                B256::left_padding_from(Address::repeat_byte(to).as_slice()).as_slice(),
+                B256::ZERO.as_slice(),
+            ]
+            .concat()
+            .into(),
+            ..TxEnv::default()
+        };
+        let call = |to: u8| TxEnv {
+            kind: revm::context::TransactTo::Call(Address::repeat_byte(to)),
+            data: vec![0x12, 0x34, 0x56, 0x78].into(),
+            ..TxEnv::default()
+        };
+        // Only transfers crediting the same holder, and other calls to the
+        // same contract, are inspected.
+        let txs = [
+            transfer(1),
+            transfer(2),
+            transfer(1),
+            call(3),
+            call(4),
+            call(3),
+        ];
+        let mut mv_memory = MvMemory::default();
+        mv_memory.set_commutative_writes(true);
+        PevmEthereum::mainnet().prepare_mv_memory(&mut mv_memory, &BlockEnv::default(), &txs);
+        assert_eq!(
+            (0..txs.len())
+                .map(|tx_idx| mv_memory.is_inspected(tx_idx))
+                .collect::<Vec<_>>(),
+            [true, false, true, true, false, true]
+        );
+    }
+
+    #[test]
+    fn estimated_sender_chains() {
+        let tx = |sender: u8| TxEnv {
+            caller: Address::repeat_byte(sender),
+            ..TxEnv::default()
+        };
+        let txs = [tx(1), tx(2), tx(1), tx(3), tx(1), tx(2)];
+        let mut mv_memory = MvMemory::default();
+        let mut estimated_locations = HashMap::default();
+        mv_memory.estimate_dependencies(txs.iter().enumerate(), &mut estimated_locations);
+        // Each transaction but the last of its sender writes the nonce that
+        // the next one reads.
+        assert_eq!(
+            estimated_locations,
+            HashMap::from_iter([
+                (
+                    hash_deterministic(MemoryLocation::Nonce(Address::repeat_byte(1))),
+                    vec![0, 2]
+                ),
+                (
+                    hash_deterministic(MemoryLocation::Nonce(Address::repeat_byte(2))),
+                    vec![1]
+                ),
+            ])
+        );
+
+        let mut estimated_locations = HashMap::default();
+        mv_memory.set_skip_dependency_estimation(true);
+        mv_memory.estimate_dependencies(txs.iter().enumerate(), &mut estimated_locations);
+        assert!(estimated_locations.is_empty());
+    }
+
+    #[test]
+    fn sender_dependencies() {
+        let sender = TxEnv {
+            caller: Address::repeat_byte(0x51),
+            ..TxEnv::default()
+        };
+        let other = TxEnv {
+            caller: Address::repeat_byte(0x52),
+            ..TxEnv::default()
+        };
+        let txs = [sender.clone(), other.clone(), other, sender];
+        let mut mv_memory = MvMemory::new(txs.len(), [], []);
+        mv_memory.index_senders(txs.iter().enumerate());
+        let tx_version = |tx_incarnation| TxVersion {
+            tx_idx: 3,
+            tx_incarnation,
+        };
+        // Each incarnation first waits for the earlier transaction of the same
+        // sender, then for the previous one when retried immediately.
+        assert_eq!(mv_memory.sender_dependency(&tx_version(0), false), 0);
+        assert_eq!(mv_memory.sender_dependency(&tx_version(0), false), 2);
+        assert_eq!(mv_memory.sender_dependency(&tx_version(1), false), 0);
+        assert_eq!(mv_memory.sender_dependency(&tx_version(1), false), 2);
+
+        // The next block starts over.
+        mv_memory.clear();
+        assert_eq!(mv_memory.sender_dependency(&tx_version(0), false), 2);
+        mv_memory.reset(txs.len(), [], []);
+        mv_memory.index_senders(txs.iter().enumerate());
+        assert_eq!(mv_memory.sender_dependency(&tx_version(0), false), 0);
+    }
 }
@@ -1,29 +1,53 @@
 use std::{
+    collections::{BTreeMap, BTreeSet},
     fmt::Debug,
+    iter,
     num::NonZeroUsize,
-    sync::{Mutex, OnceLock, mpsc},
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
     thread,
+    time::{Duration, Instant},
 };
 
-use alloy_primitives::{TxNonce, U256};
-use alloy_rpc_types_eth::{Block, BlockTransactions};
+use alloy_eips::{eip6110::DEPOSIT_REQUEST_TYPE, eip7685::Requests};
+use alloy_primitives::{Address, B256, TxNonce, U256, b256};
+use alloy_rpc_types_eth::{Block, BlockTransactions, Header};
 use hashbrown::HashMap;
 use revm::{
-    DatabaseCommit, ExecuteEvm,
-    context::{BlockEnv, ContextTr, Transaction, result::InvalidTransaction},
+    DatabaseCommit, ExecuteEvm, SystemCallEvm,
+    context::{
+        BlockEnv, ContextTr, Transaction, TxEnv,
+        result::{EVMError, ExecutionResult, InvalidTransaction, ResultAndState},
+    },
     database::CacheDB,
-    handler::EvmTr,
+    handler::{EvmTr, SYSTEM_ADDRESS},
+    primitives::KECCAK_EMPTY,
 };
 
 use crate::{
-    EvmAccount, MemoryEntry, MemoryLocation, MemoryValue, Storage, Task, TxIdx, TxVersion,
-    chain::PevmChain,
+    BlockStateDiff, DependencyGraph, Divergence, DivergenceLocation, EvmAccount,
+    EvmStateTransitions, FallbackReason, IdleTask, MemoryEntry, MemoryLocation, MemoryValue,
+    PevmStats, ScheduleLog, Storage, Task, TxIdx, TxVersion, VerifyMode,
+    chain::{PevmChain, SystemCall},
     compat::get_block_env,
     hash_deterministic,
     mv_memory::MvMemory,
+    pipeline,
+    policy::{BlockFeatures, DefaultExecutionPolicy, ExecutionPolicy, ExecutionStrategy},
+    prefetcher::Prefetcher,
+    replay::{ScheduleRecorder, replay_tasks},
     scheduler::Scheduler,
-    storage::StorageWrapper,
-    vm::{ExecutionError, PevmTxExecutionResult, Vm, VmExecutionError, VmExecutionResult},
+    session::{self, PevmSession, SessionBlock},
+    storage::{CachedStorage, StateOverlay, StorageWrapper},
+    verify::find_block_divergence,
+    vm::{
+        ExecutionError, PevmTxExecutionResult, TxExecutor, Vm, VmExecutionError, VmExecutionResult,
+        execution_error,
+    },
+    worker_pool::{Helper, WorkerPool},
 };
 
 /// Errors when executing a block with pevm.
@@ -36,9 +60,19 @@ pub enum PevmError<C: PevmChain> {
     /// Transactions lack information for execution.
     #[error("Transactions lack information for execution")]
     MissingTransactionData,
+    /// The block has ommers but their headers were not provided.
+    #[error("Missing headers of the block's ommers")]
+    MissingOmmerHeaders,
     /// Invalid input transaction.
     #[error("Invalid input transaction")]
     InvalidTransaction(#[source] C::TransactionParsingError),
+    /// A replayed task cannot run as the replay has diverged from the recorded
+    /// schedule, like when a validation that aborted before no longer aborts.
+    #[error("Replay diverged from the recorded schedule at event #{position}")]
+    ReplayDiverged {
+        /// The position of the task in the log
+        position: usize,
+    },
     /// Nonce too low or too high
     #[error("Nonce mismatch for tx #{tx_idx}. Expected {executed_nonce}, got {tx_nonce}")]
     NonceMismatch {
@@ -53,6 +87,17 @@ pub enum PevmError<C: PevmChain> {
     // TODO: More concrete types than just an arbitrary string.
     #[error("Storage error: {0}")]
     StorageError(String),
+    /// A mandatory system contract call failed, invalidating the block.
+    #[error("System call to {0} failed")]
+    SystemCallFailed(Address),
+    /// A system contract that collects requests has no code, invalidating
+    /// the block.
+    #[error("System contract {0} has no code")]
+    MissingSystemContract(Address),
+    /// A transaction emitted a malformed EIP-6110 deposit log, invalidating
+    /// the block.
+    #[error("Invalid deposit log")]
+    InvalidDepositLog,
     /// EVM execution error.
     #[error("Execution error")]
     ExecutionError(
@@ -71,48 +116,455 @@ pub enum PevmError<C: PevmChain> {
 /// Execution result of a block
 pub type PevmResult<C> = Result<Vec<PevmTxExecutionResult>, PevmError<C>>;
 
+// Execution results of the included transactions, and the indices of the
+// excluded ones with their errors.
+pub(crate) type ExclusiveResult<C> =
+    Result<(Vec<PevmTxExecutionResult>, Vec<(TxIdx, ExecutionError)>), PevmError<C>>;
+
+// Execution results of a block's transactions like [ExclusiveResult], with the
+// state of its balance increments if they were lazily evaluated with them.
+type ParallelResult<C> = Result<
+    (
+        Vec<PevmTxExecutionResult>,
+        Vec<(TxIdx, ExecutionError)>,
+        Option<EvmStateTransitions>,
+    ),
+    PevmError<C>,
+>;
+
+/// How pevm handles transactions that fail to execute.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum PevmMode {
+    /// Sync canonical blocks, where all transactions must be valid. Invalid
+    /// transactions are retried after lower transactions, as they may fund
+    /// the sender or fill a nonce gap. Other errors abort the block.
+    #[default]
+    Sync,
+    /// Build new blocks, excluding invalid transactions like those with
+    /// nonce gaps or lacking funds. Other errors abort the block.
+    Build,
+    /// Test blocks, excluding and collecting all transaction errors.
+    Test,
+}
+
+impl PevmMode {
+    // Whether a transaction with this error is excluded from the block
+    // instead of aborting it.
+    pub(crate) const fn excludes<DBError, TxError>(
+        &self,
+        err: &EVMError<DBError, TxError>,
+    ) -> bool {
+        match self {
+            Self::Sync => false,
+            Self::Build => matches!(err, EVMError::Transaction(_)),
+            Self::Test => !matches!(err, EVMError::Database(_)),
+        }
+    }
+}
+
+/// Execution result of a full block, including its system calls.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PevmBlockExecutionResult {
+    /// State transitions of the system calls before the block's transactions.
+    pub pre_block_state: EvmStateTransitions,
+    /// Execution results of the block's transactions.
+    pub tx_results: Vec<PevmTxExecutionResult>,
+    /// State transitions of the system calls after the block's transactions.
+    pub post_block_state: EvmStateTransitions,
+    /// State transitions of the end-of-block balance increments, like
+    /// withdrawals and pre-merge block & ommer rewards.
+    pub balance_increment_state: EvmStateTransitions,
+    /// EIP-7685 requests collected from the post-block system calls.
+    pub requests: Requests,
+    /// The merged state changes of all the above.
+    pub state_diff: BlockStateDiff,
+    /// Indices of the transactions excluded from the block in [`PevmMode::Build`]
+    /// and [`PevmMode::Test`], with their errors.
+    pub excluded_txs: Vec<(usize, ExecutionError)>,
+}
+
+/// A block built from candidate transactions by [`Pevm::build_block`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PevmBuiltBlock<T> {
+    /// The transactions included in the block, in execution order.
+    pub txs: Vec<T>,
+    /// Execution results of the included transactions.
+    pub tx_results: Vec<PevmTxExecutionResult>,
+    /// Indices of the invalid candidates skipped before the block was full,
+    /// with their errors.
+    pub excluded_txs: Vec<(usize, ExecutionError)>,
+    /// Indices of the candidates skipped as their gas or blob gas limit did
+    /// not fit the remaining room of the block.
+    pub skipped_txs: Vec<usize>,
+    /// The total gas used by the included transactions.
+    pub gas_used: u64,
+    /// The total blob gas used by the included transactions.
+    pub blob_gas_used: u64,
+    /// The merged state changes of the included transactions.
+    pub state_diff: BlockStateDiff,
+}
+
+// The shared inputs of the workers executing a block in parallel.
+pub(crate) struct WorkerContext<'a, S, C: PevmChain> {
+    pub(crate) chain: &'a C,
+    pub(crate) storage: &'a S,
+    pub(crate) spec_id: C::EvmSpecId,
+    pub(crate) block_env: &'a BlockEnv,
+    pub(crate) txs: &'a [C::EvmTx],
+    pub(crate) mv_memory: &'a MvMemory,
+    pub(crate) mode: PevmMode,
+}
+
+impl<'a, S: Storage, C: PevmChain> WorkerContext<'a, S, C> {
+    // A new executor of [Self::txs], and of [balance_increments] after them if any.
+    fn new_executor(&self, balance_increments: &'a [(Address, U256)]) -> BlockExecutor<'a, S, C> {
+        BlockExecutor {
+            vm: self.new_vm(),
+            num_txs: self.txs.len(),
+            balance_increments,
+        }
+    }
+
+    pub(crate) fn new_vm(&self) -> Vm<'a, S, C> {
+        Vm::new(
+            self.chain,
+            self.spec_id,
+            self.block_env,
+            self.txs,
+            self.storage,
+            self.mv_memory,
+            self.mode,
+        )
+    }
+
+    // Fully evaluate the lazy addresses into the results of [Self::txs], and of
+    // the balance increments after them if any.
+    pub(crate) fn evaluate_lazy_addresses(
+        &self,
+        fully_evaluated_results: &mut [PevmTxExecutionResult],
+    ) -> Result<(), PevmError<C>> {
+        evaluate_lazy_addresses(
+            self.chain,
+            self.storage,
+            self.mv_memory,
+            // Indices past the transactions are the block's balance increments.
+            |tx_idx| {
+                (
+                    self.spec_id,
+                    self.txs.get(tx_idx).map(|tx| self.chain.tx_env(tx)),
+                )
+            },
+            fully_evaluated_results,
+        )
+    }
+}
+
+// Executes the transactions of a block, and its end-of-block balance increments
+// as an extra transaction after them. The incremented accounts are then lazy
+// addresses like the beneficiary account, so the transactions that credit
+// them never conflict with the increments.
+struct BlockExecutor<'a, S: Storage, C: PevmChain> {
+    vm: Vm<'a, S, C>,
+    num_txs: usize,
+    balance_increments: &'a [(Address, U256)],
+}
+
+impl<S: Storage, C: PevmChain> TxExecutor for BlockExecutor<'_, S, C> {
+    fn execute(&mut self, tx_version: &TxVersion) -> Result<VmExecutionResult, VmExecutionError> {
+        if tx_version.tx_idx == self.num_txs {
+            self.vm
+                .execute_balance_increments(tx_version, self.balance_increments)
+        } else {
+            self.vm.execute(tx_version)
+        }
+    }
+
+    fn prefetch_tx(&mut self, tx_idx: TxIdx) {
+        if tx_idx < self.num_txs {
+            self.vm.prefetch_tx(tx_idx);
+        }
+    }
+
+    fn prefetch(&mut self, address: &Address, index: Option<&U256>) {
+        self.vm.prefetch(address, index);
+    }
+
+    fn wait_for_fetches(&mut self) {
+        self.vm.wait_for_fetches();
+    }
+}
+
+// The number of times a worker immediately retries a transaction that blocks
+// on already re-executed transactions, before falling back to sequential.
+const MAX_IMMEDIATE_RETRIES: usize = 256;
+
 #[derive(Debug)]
-enum AbortReason {
-    FallbackToSequential,
+pub(crate) enum AbortReason {
+    FallbackToSequential(FallbackReason),
     ExecutionError(ExecutionError),
 }
 
-// TODO: Better implementation
+// The number of cleared blocks kept for reuse. Blocks executed back to back
+// alternate between two, as the next block may start before the last one is
+// cleared.
+const MAX_RECYCLED_BLOCKS: usize = 2;
+
+// The intrinsic gas of the cheapest transaction, a plain transfer, below which
+// a block being built has no room left.
+const MIN_TRANSACTION_GAS: u64 = 21_000;
+
+// Clear the multi-version memory and schedulers of executed blocks on a
+// background thread, into a pool for the next blocks to reuse their
+// allocations without waiting for their contents to be dropped.
 #[derive(Debug)]
-struct AsyncDropper<T> {
-    sender: mpsc::Sender<T>,
+struct Recycler {
+    sender: mpsc::Sender<(MvMemory, Scheduler)>,
+    pool: Arc<Mutex<Vec<(MvMemory, Scheduler)>>>,
     _handle: thread::JoinHandle<()>,
 }
 
-impl<T: Send + 'static> Default for AsyncDropper<T> {
+impl Default for Recycler {
     fn default() -> Self {
         let (sender, receiver) = mpsc::channel();
+        let pool = Arc::new(Mutex::new(Vec::with_capacity(MAX_RECYCLED_BLOCKS)));
+        let recycled = pool.clone();
         Self {
             sender,
-            _handle: std::thread::spawn(move || receiver.into_iter().for_each(drop)),
+            pool,
+            _handle: std::thread::spawn(move || {
+                for (mut mv_memory, scheduler) in receiver {
+                    mv_memory.clear();
+                    let mut pool = recycled.lock().unwrap();
+                    if pool.len() < MAX_RECYCLED_BLOCKS {
+                        pool.push((mv_memory, scheduler));
+                    }
+                }
+            }),
         }
     }
 }
 
-impl<T> AsyncDropper<T> {
-    fn drop(&self, t: T) {
-        let _ = self.sender.send(t);
-    }
-}
-
-// TODO: Port more recyclable resources into here.
 #[derive(Debug, Default)]
 /// The main pevm struct that executes blocks.
 pub struct Pevm {
-    execution_results: Vec<Mutex<Option<PevmTxExecutionResult>>>,
+    mode: PevmMode,
+    // Whether to prefetch the state that transactions are likely to read.
+    prefetching: bool,
+    // Whether to record the commutative storage writes of likely-conflicting calls.
+    commutative_writes: bool,
+    // Whether to skip estimating the dependencies between transactions.
+    skip_dependency_estimation: bool,
+    // The policy deciding how to execute each block, [DefaultExecutionPolicy] if unset.
+    policy: Option<Box<dyn ExecutionPolicy>>,
+    // Whether to collect the statistics of blocks executed in parallel.
+    collect_stats: bool,
+    // The statistics of the last block executed in parallel, until taken.
+    stats: Option<PevmStats>,
+    // Whether to collect the dependency graphs of blocks executed in parallel.
+    collect_dependency_graph: bool,
+    // The dependency graph of the last block executed in parallel, until taken.
+    dependency_graph: Option<DependencyGraph>,
+    // Whether to record the schedules of blocks executed in parallel.
+    record_schedule: bool,
+    // The schedule of the last block executed in parallel, until taken.
+    schedule_log: Option<ScheduleLog>,
+    // How often to verify blocks executed in parallel against sequential
+    // execution.
+    verify_mode: VerifyMode,
+    // The number of blocks executed in parallel since the last verified one.
+    unverified_blocks: usize,
+    // The divergences of the verified blocks, until taken.
+    divergences: Vec<Divergence>,
+    execution_results: Vec<Mutex<Option<Result<PevmTxExecutionResult, ExecutionError>>>>,
     abort_reason: OnceLock<AbortReason>,
-    dropper: AsyncDropper<(MvMemory, Scheduler)>,
+    // Whether to pin the worker threads to CPU cores.
+    pinned_workers: bool,
+    // The worker threads kept across blocks, spawned on the first parallel
+    // execution and respawned when a block needs more.
+    workers: Option<WorkerPool>,
+    recycler: Recycler,
 }
 
 impl Pevm {
+    /// Construct a pevm instance that executes blocks in [mode].
+    pub fn with_mode(mode: PevmMode) -> Self {
+        Self {
+            mode,
+            ..Self::default()
+        }
+    }
+
+    /// Prefetch the state that transactions are likely to read, like their
+    /// senders, recipients, access lists, and EIP-7702 authorities, into a cache in
+    /// front of the storage while they are executed, in parallel or sequentially.
+    /// The state is fetched on the worker threads that are not executing the
+    /// block. This helps storages bound by I/O, like `RpcStorage`, to stall
+    /// executions less on cold reads.
+    pub const fn with_prefetching(mut self) -> Self {
+        self.prefetching = true;
+        self
+    }
+
+    /// Record the storage writes that only add constants, like to a token
+    /// recipient's balance, as deltas that commute with the writes of other
+    /// transactions. Calls likely to conflict this way are inspected, which
+    /// slows them down, so this only pays off on blocks with hot slots on
+    /// machines with enough cores. Chains whose EVMs have the `()`
+    /// inspector ignore this.
+    pub const fn with_commutative_writes(mut self) -> Self {
+        self.commutative_writes = true;
+        self
+    }
+
+    pub(crate) const fn commutative_writes(&self) -> bool {
+        self.commutative_writes
+    }
+
+    /// Skip estimating the dependencies between the transactions of a block
+    /// before executing it, like the nonce chains of the same sender, to
+    /// measure how many re-executions the estimates save.
+    pub const fn without_dependency_estimation(mut self) -> Self {
+        self.skip_dependency_estimation = true;
+        self
+    }
+
+    pub(crate) const fn skip_dependency_estimation(&self) -> bool {
+        self.skip_dependency_estimation
+    }
+
+    /// Pin the worker threads to the CPU cores available to the process, which
+    /// avoids migrating them between cores mid-block. This is only supported
+    /// on Linux and ignored on other platforms.
+    pub const fn with_pinned_workers(mut self) -> Self {
+        self.pinned_workers = true;
+        self
+    }
+
+    /// Collect the [`PevmStats`] of each block executed in parallel, like the
+    /// incarnations of its transactions and the time spent in each phase.
+    pub const fn with_stats(mut self) -> Self {
+        self.collect_stats = true;
+        self
+    }
+
+    /// Take the statistics of the last block executed in parallel, if they
+    /// are collected with [`Self::with_stats`] and not taken yet.
+    pub const fn take_stats(&mut self) -> Option<PevmStats> {
+        self.stats.take()
+    }
+
+    /// Collect the [`DependencyGraph`] of each block executed in parallel, to
+    /// study how parallel blocks are.
+    pub const fn with_dependency_graph(mut self) -> Self {
+        self.collect_dependency_graph = true;
+        self
+    }
+
+    /// Take the dependency graph of the last block executed in parallel, if
+    /// it is collected with [`Self::with_dependency_graph`] and not taken yet.
+    pub const fn take_dependency_graph(&mut self) -> Option<DependencyGraph> {
+        self.dependency_graph.take()
+    }
+
+    /// Record the interleaving of the tasks of each block executed in parallel,
+    /// to replay a failing execution with [`Self::replay_revm_parallel`].
+    pub const fn with_schedule_recording(mut self) -> Self {
+        self.record_schedule = true;
+        self
+    }
+
+    /// Take the schedule of the last block executed in parallel, if it is
+    /// recorded with [`Self::with_schedule_recording`] and not taken yet.
+    pub const fn take_schedule_log(&mut self) -> Option<ScheduleLog> {
+        self.schedule_log.take()
+    }
+
+    /// Verify blocks executed in parallel against a sequential execution of
+    /// the same block, as often as `verify_mode` samples them. A verified
+    /// block whose results diverge returns the sequential results, and its
+    /// first divergence is kept for [`Self::take_divergences`]. Blocks that
+    /// fail to execute in parallel are not verified.
+    pub const fn with_verify_mode(mut self, verify_mode: VerifyMode) -> Self {
+        self.verify_mode = verify_mode;
+        self
+    }
+
+    /// Take the first divergence of each verified block whose parallel and
+    /// sequential results diverged since the last call, in execution order.
+    pub fn take_divergences(&mut self) -> Vec<Divergence> {
+        std::mem::take(&mut self.divergences)
+    }
+
+    // Whether to verify the next block executed in parallel.
+    const fn sample_verification(&mut self) -> bool {
+        match self.verify_mode {
+            VerifyMode::Never => false,
+            VerifyMode::Always => true,
+            VerifyMode::Sampled(n) => {
+                let verify = self.unverified_blocks == 0;
+                self.unverified_blocks = (self.unverified_blocks + 1) % n.get();
+                verify
+            }
+        }
+    }
+
+    /// Decide how to execute each block with `policy` instead of the
+    /// [`DefaultExecutionPolicy`].
+    pub fn with_policy(mut self, policy: impl ExecutionPolicy + 'static) -> Self {
+        self.policy = Some(Box::new(policy));
+        self
+    }
+
+    // Decide how to execute [txs], which use [gas_used] if known, with at most
+    // [concurrency_level] workers.
+    pub(crate) fn decide_strategy<C: PevmChain>(
+        &self,
+        chain: &C,
+        txs: &[C::EvmTx],
+        gas_used: Option<u64>,
+        concurrency_level: NonZeroUsize,
+    ) -> ExecutionStrategy {
+        let mut features = BlockFeatures::new(txs.iter().map(|tx| chain.tx_env(tx)));
+        features.gas_used = gas_used;
+        let policy = self.policy.as_deref().unwrap_or(&DefaultExecutionPolicy);
+        match policy.decide(&features, concurrency_level) {
+            ExecutionStrategy::Parallel(workers) => {
+                ExecutionStrategy::Parallel(workers.min(concurrency_level))
+            }
+            strategy => strategy,
+        }
+    }
+
     /// Execute an Alloy block, which is becoming the "standard" format in Rust.
-    /// TODO: Better error handling.
+    /// Blocks only have the hashes of their ommers, so the beneficiaries of the
+    /// ommers of pre-merge blocks are not rewarded. Execute these blocks with
+    /// [`Self::execute_with_ommers`] for their full state transitions.
     pub fn execute<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        block: &Block<C::Transaction>,
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> Result<PevmBlockExecutionResult, PevmError<C>>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        self.execute_block(
+            chain,
+            storage,
+            block,
+            &[],
+            concurrency_level,
+            force_sequential,
+        )
+    }
+
+    /// Execute an Alloy block with the headers of its ommers, which are needed
+    /// to reward the ommer beneficiaries of pre-merge blocks.
+    /// TODO: Better error handling.
+    pub fn execute_with_ommers<S, C>(
         &mut self,
         chain: &C,
         storage: &S,
@@ -123,9 +575,37 @@ impl Pevm {
         // the new Alloy [`Transaction`] interface that is mostly `&self`. We'd need
         // to do some dirty destruction to get the owned fields.
         block: &Block<C::Transaction>,
+        ommers: &[Header],
         concurrency_level: NonZeroUsize,
         force_sequential: bool,
-    ) -> PevmResult<C>
+    ) -> Result<PevmBlockExecutionResult, PevmError<C>>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        if block.uncles.len() != ommers.len() {
+            return Err(PevmError::MissingOmmerHeaders);
+        }
+        self.execute_block(
+            chain,
+            storage,
+            block,
+            ommers,
+            concurrency_level,
+            force_sequential,
+        )
+    }
+
+    // Execute an Alloy block, rewarding the beneficiaries of the given [ommers].
+    fn execute_block<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        block: &Block<C::Transaction>,
+        ommers: &[Header],
+        concurrency_level: NonZeroUsize,
+        force_sequential: bool,
+    ) -> Result<PevmBlockExecutionResult, PevmError<C>>
     where
         C: PevmChain + Send + Sync,
         S: Storage + Send + Sync + Debug,
@@ -134,33 +614,302 @@ impl Pevm {
             .get_block_spec(&block.header)
             .map_err(PevmError::BlockSpecError)?;
         let block_env = get_block_env(&block.header, spec_id);
-        let tx_envs = match &block.transactions {
-            BlockTransactions::Full(txs) => txs
-                .iter()
-                .map(|tx| chain.get_tx_env(tx))
-                .collect::<Result<Vec<_>, _>>()
-                .map_err(PevmError::InvalidTransaction)?,
-            _ => return Err(PevmError::MissingTransactionData),
-        };
-        // TODO: Continue to fine tune this condition.
-        if force_sequential
-            || tx_envs.len() < concurrency_level.into()
-            || block.header.gas_used < 4_000_000
-        {
-            execute_revm_sequential(chain, storage, spec_id, block_env, tx_envs)
+        let tx_envs = block_tx_envs(chain, block)?;
+
+        // The block's transactions must see the changes of the pre-block system
+        // calls, which we layer on top of the input storage.
+        let mut requests = Requests::default();
+        let mut overlay = StateOverlay::new(storage);
+        let pre_block_state = execute_system_calls(
+            chain,
+            &overlay,
+            spec_id,
+            &block_env,
+            chain.pre_block_system_calls(spec_id, &block.header),
+            &mut requests,
+        )?;
+        overlay.apply(&pre_block_state);
+
+        let balance_increments = block_balance_increments(chain, spec_id, block, ommers);
+        let strategy = if force_sequential {
+            ExecutionStrategy::Sequential
         } else {
-            self.execute_revm_parallel(
+            self.decide_strategy(
                 chain,
-                storage,
-                spec_id,
-                block_env,
-                tx_envs,
+                &tx_envs,
+                Some(block.header.gas_used),
                 concurrency_level,
             )
+        };
+        let (tx_results, excluded_txs, lazy_balance_increment_state) = match strategy {
+            ExecutionStrategy::Sequential => self
+                .execute_revm_sequential_prefetched(
+                    chain,
+                    &overlay,
+                    spec_id,
+                    block_env.clone(),
+                    tx_envs,
+                    concurrency_level,
+                )
+                .map(|(tx_results, excluded_txs)| (tx_results, excluded_txs, None)),
+            ExecutionStrategy::Parallel(workers) => self.execute_revm_parallel_excluding(
+                chain,
+                &overlay,
+                spec_id,
+                block_env.clone(),
+                tx_envs,
+                &balance_increments,
+                workers,
+                self.mode,
+            ),
+        }?;
+
+        if let Some(deposit_contract) = chain.deposit_contract(spec_id) {
+            collect_deposit_requests(deposit_contract, &tx_results, &mut requests)?;
+        }
+        let post_block_calls = chain.post_block_system_calls(spec_id, &block.header);
+
+        // The end-of-block phases run on top of the post-transaction state.
+        if !post_block_calls.is_empty() || !balance_increments.is_empty() {
+            for tx_result in &tx_results {
+                overlay.apply(&tx_result.state);
+            }
+        }
+
+        let post_block_state = execute_system_calls(
+            chain,
+            &overlay,
+            spec_id,
+            &block_env,
+            post_block_calls,
+            &mut requests,
+        )?;
+        overlay.apply(&post_block_state);
+
+        // The increments evaluated with the transactions are credited before
+        // the post-block system calls, so they are only valid if the calls did
+        // not touch the incremented accounts.
+        let balance_increment_state = match lazy_balance_increment_state {
+            Some(state)
+                if balance_increments
+                    .iter()
+                    .all(|(address, _)| !post_block_state.contains_key(address)) =>
+            {
+                state
+            }
+            _ => apply_balance_increments(&overlay, &balance_increments)?,
+        };
+
+        let state_diff = BlockStateDiff::from_transitions(
+            storage,
+            iter::once(&pre_block_state)
+                .chain(tx_results.iter().map(|tx_result| &tx_result.state))
+                .chain([&post_block_state, &balance_increment_state]),
+        )
+        .map_err(|err| PevmError::StorageError(err.to_string()))?;
+
+        Ok(PevmBlockExecutionResult {
+            pre_block_state,
+            tx_results,
+            post_block_state,
+            balance_increment_state,
+            requests,
+            state_diff,
+            excluded_txs,
+        })
+    }
+
+    /// Execute consecutive blocks, pipelining them so the transactions of a
+    /// block start speculatively on the unfinished writes of the previous ones.
+    /// This keeps workers busy during the serial tail of each block. Like
+    /// [`Self::execute`], the ommer beneficiaries of pre-merge blocks are not
+    /// rewarded, which needs [`Self::execute_blocks_with_ommers`].
+    pub fn execute_blocks<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        blocks: &[Block<C::Transaction>],
+        concurrency_level: NonZeroUsize,
+    ) -> Result<Vec<PevmBlockExecutionResult>, PevmError<C>>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        pipeline::execute_blocks(self, chain, storage, blocks, &[], concurrency_level)
+    }
+
+    /// Pipeline consecutive blocks like [`Self::execute_blocks`], with the
+    /// headers of the ommers of each block to reward their beneficiaries.
+    pub fn execute_blocks_with_ommers<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        blocks: &[Block<C::Transaction>],
+        ommers: &[Vec<Header>],
+        concurrency_level: NonZeroUsize,
+    ) -> Result<Vec<PevmBlockExecutionResult>, PevmError<C>>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        if blocks.len() != ommers.len()
+            || blocks
+                .iter()
+                .zip(ommers)
+                .any(|(block, ommers)| block.uncles.len() != ommers.len())
+        {
+            return Err(PevmError::MissingOmmerHeaders);
+        }
+        pipeline::execute_blocks(self, chain, storage, blocks, ommers, concurrency_level)
+    }
+
+    /// Run an in-flight block that [`producer`] continuously pushes transactions
+    /// to from a scoped thread, receiving receipts as they are finalized, while
+    /// the calling thread executes them on the workers. The block closes once [`producer`]
+    /// returns, returning its output with the execution results of the
+    /// included transactions and the excluded ones. See [`PevmSession`].
+    pub fn session<S, C, R>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        spec_id: C::EvmSpecId,
+        block_env: BlockEnv,
+        concurrency_level: NonZeroUsize,
+        producer: impl FnOnce(&PevmSession<C::EvmTx>) -> R + Send,
+    ) -> (R, ExclusiveResult<C>)
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+        R: Send,
+    {
+        let mode = if self.mode == PevmMode::Test {
+            PevmMode::Test
+        } else {
+            PevmMode::Build
+        };
+        session::run_session(
+            self,
+            chain,
+            storage,
+            spec_id,
+            block_env,
+            mode,
+            concurrency_level,
+            producer,
+        )
+    }
+
+    /// Build a block from an ordered stream of candidate transactions, like a
+    /// transaction pool sorted by tips. Candidates are speculatively executed in
+    /// windows sized to the remaining gas, with the workers decided by the
+    /// execution policy for each window, and included if they fit
+    /// [`BlockEnv::gas_limit`] and the optional blob gas limit. The windows
+    /// share a multi-version memory, so each one reads the writes of the
+    /// previous ones without re-executing them. Invalid candidates are excluded
+    /// like in [`PevmMode::Build`], candidates too large for the remaining room
+    /// are skipped, and building stops once there is no room for a transfer.
+    /// System calls and balance increments are left to the caller.
+    #[allow(clippy::too_many_arguments)]
+    pub fn build_block<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        spec_id: C::EvmSpecId,
+        block_env: BlockEnv,
+        candidates: impl IntoIterator<Item = C::EvmTx>,
+        blob_gas_limit: Option<u64>,
+        concurrency_level: NonZeroUsize,
+    ) -> Result<PevmBuiltBlock<C::EvmTx>, PevmError<C>>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        // Keep collecting all errors when testing.
+        let mode = match self.mode {
+            PevmMode::Test => PevmMode::Test,
+            _ => PevmMode::Build,
+        };
+        let gas_limit = block_env.gas_limit;
+        let blob_gas_limit = blob_gas_limit.unwrap_or(u64::MAX);
+        let mut candidates = candidates.into_iter().enumerate();
+        let mut block = SessionBlock::new(self, chain, storage, spec_id, block_env, mode);
+        // The executed transactions, with their candidate indices and blob gas.
+        let mut txs = Vec::new();
+        let mut executed = Vec::new();
+        let mut skipped_txs = Vec::new();
+        let (mut gas_used, mut blob_gas_used) = (0u64, 0u64);
+        // The next candidate when it did not fit the previous window.
+        let mut next_candidate = None;
+        while gas_limit.saturating_sub(gas_used) >= MIN_TRANSACTION_GAS {
+            // A window holds as many candidates as fit if they all use their
+            // full gas limits. Unused gas and excluded candidates leave room
+            // for the next window.
+            let mut window = Vec::new();
+            let (mut window_gas, mut window_blob_gas) = (gas_used, blob_gas_used);
+            while let Some((candidate_idx, tx)) =
+                next_candidate.take().or_else(|| candidates.next())
+            {
+                let tx_env = chain.tx_env(&tx);
+                let (tx_gas, tx_blob_gas) = (tx_env.gas_limit, tx_env.total_blob_gas());
+                if window_gas.saturating_add(tx_gas) <= gas_limit
+                    && window_blob_gas.saturating_add(tx_blob_gas) <= blob_gas_limit
+                {
+                    window_gas += tx_gas;
+                    window_blob_gas += tx_blob_gas;
+                    executed.push((candidate_idx, tx_blob_gas));
+                    window.push(tx);
+                } else if gas_used.saturating_add(tx_gas) <= gas_limit
+                    && blob_gas_used.saturating_add(tx_blob_gas) <= blob_gas_limit
+                {
+                    // It fits after the window if its candidates use less gas.
+                    next_candidate = Some((candidate_idx, tx));
+                    break;
+                } else {
+                    skipped_txs.push(candidate_idx);
+                }
+            }
+            if window.is_empty() {
+                break;
+            }
+
+            let workers = match block.decide_strategy(&window, concurrency_level) {
+                ExecutionStrategy::Sequential => NonZeroUsize::MIN,
+                ExecutionStrategy::Parallel(workers) => workers,
+            };
+            txs.extend_from_slice(&window);
+            for (tx_idx, receipt) in block.execute_batch(window, workers)? {
+                if let Ok(receipt) = receipt {
+                    gas_used = receipt.cumulative_gas_used;
+                    blob_gas_used += executed[tx_idx].1;
+                }
+            }
         }
+
+        let (tx_results, excluded_txs) = block.finish()?;
+        remove_excluded_results(&mut txs, &excluded_txs);
+        let state_diff = BlockStateDiff::from_transitions(
+            storage,
+            tx_results.iter().map(|tx_result| &tx_result.state),
+        )
+        .map_err(|err| PevmError::StorageError(err.to_string()))?;
+
+        Ok(PevmBuiltBlock {
+            txs,
+            tx_results,
+            excluded_txs: excluded_txs
+                .into_iter()
+                .map(|(tx_idx, err)| (executed[tx_idx].0, err))
+                .collect(),
+            skipped_txs,
+            gas_used,
+            blob_gas_used,
+            state_diff,
+        })
     }
 
     /// Execute an REVM block.
+    /// Only the included transactions are returned in [`PevmMode::Build`] and
+    /// [`PevmMode::Test`]. Use [`Self::execute`] to get the excluded ones.
     // Ideally everyone would go through the [Alloy] interface. This one is currently
     // useful for testing, and for users that are heavily tied to Revm like Reth.
     pub fn execute_revm_parallel<S, C>(
@@ -172,6 +921,42 @@ impl Pevm {
         txs: Vec<C::EvmTx>,
         concurrency_level: NonZeroUsize,
     ) -> PevmResult<C>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        self.execute_revm_parallel_excluding(
+            chain,
+            storage,
+            spec_id,
+            block_env,
+            txs,
+            &[],
+            concurrency_level,
+            self.mode,
+        )
+        .map(|(tx_results, ..)| tx_results)
+    }
+
+    /// Execute an REVM block on the calling thread, forcing the interleaving of
+    /// tasks recorded by a parallel execution of the same block with
+    /// [`Self::with_schedule_recording`]. This steps through a failing parallel
+    /// execution deterministically, as each task runs to completion in order.
+    /// Only the interleaving of tasks is forced, not the values they read: an
+    /// execution that read a location while another task was writing it may
+    /// read from a different transaction version when replayed, in which case
+    /// the replay returns [`PevmError::ReplayDiverged`] at the event it cannot
+    /// force. Replays must use the same [`PevmMode`] as the recording.
+    #[allow(clippy::too_many_arguments)]
+    pub fn replay_revm_parallel<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        spec_id: C::EvmSpecId,
+        block_env: BlockEnv,
+        txs: Vec<C::EvmTx>,
+        log: &ScheduleLog,
+    ) -> PevmResult<C>
     where
         C: PevmChain + Send + Sync,
         S: Storage + Send + Sync + Debug,
@@ -179,207 +964,495 @@ impl Pevm {
         if txs.is_empty() {
             return Ok(Vec::new());
         }
+        self.execute_revm_parallel_on(
+            chain,
+            storage,
+            spec_id,
+            block_env,
+            txs,
+            &[],
+            NonZeroUsize::MIN,
+            self.mode,
+            Some(log),
+            None,
+        )
+        .map(|(tx_results, ..)| tx_results)
+    }
 
-        let block_size = txs.len();
-        let scheduler = Scheduler::new(block_size);
-
-        let mv_memory = chain.build_mv_memory(&block_env, &txs);
+    #[allow(clippy::too_many_arguments)]
+    fn execute_revm_parallel_excluding<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        spec_id: C::EvmSpecId,
+        block_env: BlockEnv,
+        txs: Vec<C::EvmTx>,
+        balance_increments: &[(Address, U256)],
+        concurrency_level: NonZeroUsize,
+        mode: PevmMode,
+    ) -> ParallelResult<C>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        if txs.is_empty() {
+            return Ok((Vec::new(), Vec::new(), None));
+        }
+        if !self.sample_verification() {
+            return self.execute_revm_parallel_unverified(
+                chain,
+                storage,
+                spec_id,
+                block_env,
+                txs,
+                balance_increments,
+                concurrency_level,
+                mode,
+            );
+        }
 
-        let additional = block_size.saturating_sub(self.execution_results.len());
-        if additional > 0 {
-            self.execution_results.reserve(additional);
-            for _ in 0..additional {
-                self.execution_results.push(Mutex::new(None));
+        let (tx_results, excluded_txs, balance_increment_state) = self
+            .execute_revm_parallel_unverified(
+                chain,
+                storage,
+                spec_id,
+                block_env.clone(),
+                txs.clone(),
+                balance_increments,
+                concurrency_level,
+                mode,
+            )?;
+        // The sequential execution is the reference, so its results or error
+        // are returned when the parallel ones diverge.
+        let sequential_result =
+            execute_revm_sequential_excluding(chain, storage, spec_id, block_env, txs, mode);
+        let divergence = match &sequential_result {
+            Ok((sequential_results, sequential_excluded_txs)) => find_block_divergence(
+                (&tx_results, &excluded_txs),
+                (sequential_results, sequential_excluded_txs),
+            ),
+            Err(err) => Some(Divergence {
+                tx_idx: None,
+                location: DivergenceLocation::Outcome,
+                parallel: String::from("Ok"),
+                sequential: format!("{err:?}"),
+            }),
+        };
+        match divergence {
+            Some(divergence) => {
+                self.divergences.push(divergence);
+                sequential_result.map(|(tx_results, excluded_txs)| (tx_results, excluded_txs, None))
             }
+            None => Ok((tx_results, excluded_txs, balance_increment_state)),
         }
+    }
 
-        // TODO: Better thread handling
-        thread::scope(|scope| {
-            for _ in 0..concurrency_level.into() {
-                scope.spawn(|| {
-                    let mut vm = Vm::new(chain, spec_id, &block_env, &txs, storage, &mv_memory);
-                    let mut task = scheduler.next_task();
-                    while task.is_some() {
-                        task = match task.unwrap() {
-                            Task::Execution(tx_version) => {
-                                self.try_execute(&mut vm, &scheduler, tx_version)
-                            }
-                            Task::Validation(tx_version) => {
-                                try_validate(&mv_memory, &scheduler, &tx_version)
-                            }
-                        };
-
-                        // TODO: Have different functions or an enum for the caller to choose
-                        // the handling behaviour when a transaction's EVM execution fails.
-                        // Parallel block builders would like to exclude such transaction,
-                        // verifiers may want to exit early to save CPU cycles, while testers
-                        // may want to collect all execution results. We are exiting early as
-                        // the default behaviour for now.
-                        if self.abort_reason.get().is_some() {
-                            break;
-                        }
+    #[allow(clippy::too_many_arguments)]
+    fn execute_revm_parallel_unverified<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        spec_id: C::EvmSpecId,
+        block_env: BlockEnv,
+        txs: Vec<C::EvmTx>,
+        balance_increments: &[(Address, U256)],
+        concurrency_level: NonZeroUsize,
+        mode: PevmMode,
+    ) -> ParallelResult<C>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        if !self.prefetching {
+            return self.execute_revm_parallel_on(
+                chain,
+                storage,
+                spec_id,
+                block_env,
+                txs,
+                balance_increments,
+                concurrency_level,
+                mode,
+                None,
+                None,
+            );
+        }
+        // Prefetch on a spare worker thread until the block is executed.
+        let cached_storage = CachedStorage::new(storage);
+        let prefetcher = Prefetcher::new(&cached_storage, chain, &block_env, &txs);
+        self.execute_revm_parallel_on(
+            chain,
+            &cached_storage,
+            spec_id,
+            block_env,
+            txs,
+            balance_increments,
+            concurrency_level,
+            mode,
+            None,
+            Some(&prefetcher),
+        )
+    }
 
-                        if task.is_none() {
-                            task = scheduler.next_task();
-                        }
-                    }
-                });
-            }
+    // Execute [txs] sequentially on the calling thread, prefetching their state
+    // on the worker threads if prefetching.
+    fn execute_revm_sequential_prefetched<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        spec_id: C::EvmSpecId,
+        block_env: BlockEnv,
+        txs: Vec<C::EvmTx>,
+        concurrency_level: NonZeroUsize,
+    ) -> ExclusiveResult<C>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        let mode = self.mode;
+        if !self.prefetching {
+            return execute_revm_sequential_excluding(
+                chain, storage, spec_id, block_env, txs, mode,
+            );
+        }
+        let cached_storage = CachedStorage::new(storage);
+        let prefetcher = Prefetcher::new(&cached_storage, chain, &block_env, &txs);
+        let execute = || {
+            execute_revm_sequential_excluding(chain, &cached_storage, spec_id, block_env, txs, mode)
+        };
+        self.reserve_workers(concurrency_level);
+        match &self.workers {
+            Some(workers) => workers.run_beside(&prefetcher, execute),
+            // Prefetch on a scoped thread if we failed to spawn persistent workers.
+            None => thread::scope(|scope| {
+                scope.spawn(|| prefetcher.help());
+                let result = execute();
+                prefetcher.stop();
+                result
+            }),
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn execute_revm_parallel_on<S, C>(
+        &mut self,
+        chain: &C,
+        storage: &S,
+        spec_id: C::EvmSpecId,
+        block_env: BlockEnv,
+        txs: Vec<C::EvmTx>,
+        // The end-of-block balance increments to evaluate with the transactions.
+        balance_increments: &[(Address, U256)],
+        concurrency_level: NonZeroUsize,
+        mode: PevmMode,
+        // The schedule to replay on the calling thread instead of running workers.
+        replay_log: Option<&ScheduleLog>,
+        // The task to run on a spare thread while the workers execute the block.
+        helper: Option<&dyn Helper>,
+    ) -> ParallelResult<C>
+    where
+        C: PevmChain + Send + Sync,
+        S: Storage + Send + Sync + Debug,
+    {
+        let started_at = Instant::now();
+        let num_txs = txs.len();
+        // Recorded schedules only have the transactions' tasks, to be replayed
+        // with [Self::replay_revm_parallel].
+        let balance_increments = if self.record_schedule {
+            &[]
+        } else {
+            balance_increments
+        };
+        let block_size = num_txs + usize::from(!balance_increments.is_empty());
+        let (mut mv_memory, mut scheduler) = self.take_recycled();
+        scheduler.reset(block_size);
+        if self.collect_stats {
+            scheduler.record_dependencies();
+        }
+
+        mv_memory.set_commutative_writes(self.commutative_writes);
+        mv_memory.set_skip_dependency_estimation(self.skip_dependency_estimation);
+        chain.prepare_mv_memory(&mut mv_memory, &block_env, &txs);
+        mv_memory.extend(block_size - num_txs);
+        let recorder = (self.record_schedule && replay_log.is_none()).then(|| {
+            let recorder = Arc::new(ScheduleRecorder::default());
+            scheduler.record_schedule(recorder.clone());
+            mv_memory.record_schedule(recorder.clone());
+            recorder
         });
+        let prepared_at = Instant::now();
 
-        if let Some(abort_reason) = self.abort_reason.take() {
+        let ctx = WorkerContext {
+            chain,
+            storage,
+            spec_id,
+            block_env: &block_env,
+            txs: &txs,
+            mv_memory: &mv_memory,
+            mode,
+        };
+        let abort_reason = match replay_log {
+            Some(log) => {
+                self.reserve_execution_results(block_size);
+                if let Err(position) =
+                    replay_tasks(self, log, &mv_memory, &scheduler, ctx.new_executor(&[]))
+                {
+                    self.recycle((mv_memory, scheduler));
+                    return Err(PevmError::ReplayDiverged { position });
+                }
+                self.abort_reason.take()
+            }
+            None => self.run_workers(
+                block_size,
+                &mv_memory,
+                &scheduler,
+                concurrency_level,
+                || ctx.new_executor(balance_increments),
+                helper,
+            ),
+        };
+        if let Some(recorder) = recorder {
+            self.schedule_log = Some(recorder.take());
+        }
+        if self.collect_stats {
+            let mut incarnations = scheduler.incarnations();
+            incarnations.truncate(num_txs);
+            self.stats = Some(PevmStats {
+                block_size: num_txs,
+                concurrency_level: concurrency_level.get(),
+                incarnations,
+                validation_aborts: scheduler.validation_aborts(),
+                idle_tasks: scheduler.idle_tasks(),
+                dependencies: scheduler.take_dependencies(),
+                memory_locations: mv_memory.data.len(),
+                fallback: match &abort_reason {
+                    Some(AbortReason::FallbackToSequential(reason)) => Some(*reason),
+                    _ => None,
+                },
+                preparation_time: prepared_at - started_at,
+                parallel_time: prepared_at.elapsed(),
+                lazy_evaluation_time: Duration::ZERO,
+            });
+        }
+        if let Some(abort_reason) = abort_reason {
             match abort_reason {
-                AbortReason::FallbackToSequential => {
-                    self.dropper.drop((mv_memory, scheduler));
-                    return execute_revm_sequential(chain, storage, spec_id, block_env, txs);
+                AbortReason::FallbackToSequential(_) => {
+                    self.recycle((mv_memory, scheduler));
+                    return execute_revm_sequential_excluding(
+                        chain, storage, spec_id, block_env, txs, mode,
+                    )
+                    .map(|(tx_results, excluded_txs)| (tx_results, excluded_txs, None));
                 }
                 AbortReason::ExecutionError(err) => {
-                    self.dropper.drop((mv_memory, scheduler));
+                    self.recycle((mv_memory, scheduler));
                     return Err(PevmError::ExecutionError(err));
                 }
             }
         }
 
         let mut fully_evaluated_results = Vec::with_capacity(block_size);
+        let mut excluded_txs = Vec::new();
         let mut cumulative_gas_used: u64 = 0;
-        for i in 0..block_size {
-            let mut execution_result = index_mutex!(self.execution_results, i).take().unwrap();
-            cumulative_gas_used =
-                cumulative_gas_used.saturating_add(execution_result.receipt.cumulative_gas_used);
-            execution_result.receipt.cumulative_gas_used = cumulative_gas_used;
-            fully_evaluated_results.push(execution_result);
-        }
-
-        // We fully evaluate (the balance and nonce of) the beneficiary account
-        // and raw transfer recipients that may have been atomically updated.
-        for address in mv_memory.consume_lazy_addresses() {
-            let location_hash = hash_deterministic(MemoryLocation::Basic(address));
-            if let Some(write_history) = mv_memory.data.get(&location_hash) {
-                let mut balance = U256::ZERO;
-                let mut nonce = 0;
-                // Read from storage if the first multi-version entry is not an absolute value.
-                if !matches!(
-                    write_history.first_key_value(),
-                    Some((_, MemoryEntry::Data(_, MemoryValue::Basic(_))))
-                ) && let Ok(Some(account)) = storage.basic(&address)
-                {
-                    balance = account.balance;
-                    nonce = account.nonce;
+        let mut tx_gas = Vec::new();
+        for i in 0..num_txs {
+            match index_mutex!(self.execution_results, i).take().unwrap() {
+                Ok(mut execution_result) => {
+                    if self.collect_dependency_graph {
+                        tx_gas.push(execution_result.receipt.cumulative_gas_used);
+                    }
+                    cumulative_gas_used = cumulative_gas_used
+                        .saturating_add(execution_result.receipt.cumulative_gas_used);
+                    execution_result.receipt.cumulative_gas_used = cumulative_gas_used;
+                    fully_evaluated_results.push(execution_result);
                 }
-                // Accounts that take implicit writes like the beneficiary account can be contract!
-                let code_hash = match storage.code_hash(&address) {
-                    Ok(code_hash) => code_hash,
-                    Err(err) => return Err(PevmError::StorageError(err.to_string())),
-                };
-                let code = if let Some(code_hash) = &code_hash {
-                    match storage.code_by_hash(code_hash) {
-                        Ok(code) => code,
-                        Err(err) => return Err(PevmError::StorageError(err.to_string())),
+                Err(err) => {
+                    // Keep the block indices until the lazy evaluation below, which
+                    // never touches excluded transactions as they have no writes.
+                    if self.collect_dependency_graph {
+                        tx_gas.push(0);
                     }
-                } else {
-                    None
-                };
+                    excluded_txs.push((i, err));
+                    fully_evaluated_results.push(PevmTxExecutionResult {
+                        receipt: Default::default(),
+                        state: EvmStateTransitions::default(),
+                    });
+                }
+            }
+        }
 
-                for (tx_idx, memory_entry) in write_history.iter() {
-                    let tx = chain.tx_env(unsafe { txs.get_unchecked(*tx_idx) });
-                    match memory_entry {
-                        MemoryEntry::Data(_, MemoryValue::Basic(info)) => {
-                            // We fall back to sequential execution when reading a self-destructed account,
-                            // so an empty account here would be a bug
-                            debug_assert!(!(info.balance.is_zero() && info.nonce == 0));
-                            balance = info.balance;
-                            nonce = info.nonce;
-                        }
-                        MemoryEntry::Data(_, MemoryValue::LazyRecipient(addition)) => {
-                            balance = balance.saturating_add(*addition);
-                        }
-                        MemoryEntry::Data(_, MemoryValue::LazySender(subtraction)) => {
-                            // We must re-do extra sender balance checks as we mock
-                            // the max value in [Vm] during execution. Ideally we
-                            // can turn off these redundant checks in revm.
-                            // Ideally we would share these calculations with revm
-                            // (using their utility functions).
-                            let mut max_fee = U256::from(tx.gas_limit)
-                                .saturating_mul(U256::from(tx.gas_price))
-                                .saturating_add(tx.value);
-                            max_fee = max_fee.saturating_add(
-                                U256::from(tx.total_blob_gas())
-                                    .saturating_mul(U256::from(tx.max_fee_per_blob_gas)),
-                            );
-                            if balance < max_fee {
-                                Err(ExecutionError::Transaction(
-                                    InvalidTransaction::LackOfFundForMaxFee {
-                                        balance: Box::new(balance),
-                                        fee: Box::new(max_fee),
-                                    },
-                                ))?
-                            }
-                            balance = balance.saturating_sub(*subtraction);
-                            nonce += 1;
-                        }
-                        // TODO: Better error handling
-                        _ => unreachable!(),
-                    }
-                    // Assert that evaluated nonce is correct when address is caller.
-                    if tx.caller == address {
-                        let executed_nonce = if nonce == 0 {
-                            return Err(PevmError::UnreachableError);
-                        } else {
-                            nonce - 1
-                        };
-                        if tx.nonce != executed_nonce {
-                            // TODO: Consider falling back to sequential instead
-                            return Err(PevmError::NonceMismatch {
-                                tx_idx: *tx_idx,
-                                tx_nonce: tx.nonce,
-                                executed_nonce,
-                            });
-                        }
-                    }
-                    // SAFETY: The multi-version data structure should not leak an index over block size.
-                    let tx_result = unsafe { fully_evaluated_results.get_unchecked_mut(*tx_idx) };
-                    let account = tx_result.state.entry(address).or_default();
-                    // TODO: Deduplicate this logic with [PevmTxExecutionResult::from_revm]
-                    if chain.is_eip_161_enabled(spec_id)
-                        && code_hash.is_none()
-                        && nonce == 0
-                        && balance == U256::ZERO
-                    {
-                        *account = None;
-                    } else if let Some(account) = account {
-                        // Explicit write: only overwrite the account info in case there are storage changes
-                        // Code cannot change midblock here as we're falling back to sequential execution
-                        // on reading a self-destructed contract.
-                        account.balance = balance;
-                        account.nonce = nonce;
-                    } else {
-                        // Implicit write: e.g. gas payments to the beneficiary account,
-                        // which doesn't have explicit writes in [tx_result.state]
-                        *account = Some(EvmAccount {
-                            balance,
-                            nonce,
-                            code_hash,
-                            code: code.clone(),
-                            storage: HashMap::default(),
-                        });
-                    }
+        // The balance increments never fail, as they do not read.
+        if block_size > num_txs {
+            match index_mutex!(self.execution_results, num_txs).take() {
+                Some(Ok(execution_result)) => fully_evaluated_results.push(execution_result),
+                _ => {
+                    self.recycle((mv_memory, scheduler));
+                    return Err(PevmError::UnreachableError);
+                }
+            }
+        }
+
+        if self.collect_dependency_graph {
+            self.dependency_graph = Some(DependencyGraph::new(&mv_memory, tx_gas));
+        }
+
+        let evaluating_at = Instant::now();
+        let evaluated = ctx.evaluate_lazy_addresses(&mut fully_evaluated_results);
+        if let Some(stats) = &mut self.stats {
+            stats.lazy_evaluation_time = evaluating_at.elapsed();
+        }
+        self.recycle((mv_memory, scheduler));
+        evaluated?;
+
+        let balance_increment_state = (block_size > num_txs)
+            .then(|| fully_evaluated_results.pop())
+            .flatten()
+            .map(|execution_result| execution_result.state);
+        remove_excluded_results(&mut fully_evaluated_results, &excluded_txs);
+        Ok((
+            fully_evaluated_results,
+            excluded_txs,
+            balance_increment_state,
+        ))
+    }
+
+    pub(crate) const fn mode(&self) -> PevmMode {
+        self.mode
+    }
+
+    // Take the multi-version memory and scheduler of a cleared block to reset
+    // for a new one, or new ones if none has been cleared yet.
+    pub(crate) fn take_recycled(&self) -> (MvMemory, Scheduler) {
+        self.recycler.pool.lock().unwrap().pop().unwrap_or_default()
+    }
+
+    // Clear the multi-version memory and scheduler of an executed block off
+    // the hot path, to reuse their allocations for the next blocks.
+    pub(crate) fn recycle(&self, block: (MvMemory, Scheduler)) {
+        let _ = self.recycler.sender.send(block);
+    }
+
+    // Execute and validate the transactions of a block until the scheduler has
+    // no more tasks, returning the reason if the workers aborted. Each worker
+    // executes transactions with its own executor from [new_executor], while
+    // [helper] runs on a spare thread until the workers are done.
+    pub(crate) fn run_workers<E: TxExecutor>(
+        &mut self,
+        block_size: usize,
+        mv_memory: &MvMemory,
+        scheduler: &Scheduler,
+        concurrency_level: NonZeroUsize,
+        new_executor: impl Fn() -> E + Sync,
+        helper: Option<&dyn Helper>,
+    ) -> Option<AbortReason> {
+        self.reserve_execution_results(block_size);
+
+        self.reserve_workers(concurrency_level.saturating_add(usize::from(helper.is_some())));
+        let running_workers = AtomicUsize::new(concurrency_level.get());
+        let run_worker = || {
+            self.run_worker(mv_memory, scheduler, &new_executor);
+            if running_workers.fetch_sub(1, Ordering::Relaxed) == 1
+                && let Some(helper) = helper
+            {
+                helper.stop();
+            }
+        };
+        let run_helper = || {
+            if let Some(helper) = helper {
+                helper.help();
+            }
+        };
+        match &self.workers {
+            Some(workers) => workers.run(concurrency_level, run_worker, run_helper),
+            // Fall back to scoped threads if we failed to spawn persistent workers.
+            None => thread::scope(|scope| {
+                for _ in 0..concurrency_level.into() {
+                    scope.spawn(run_worker);
                 }
+                run_helper();
+            }),
+        }
+
+        self.abort_reason.take()
+    }
+
+    // Respawn the persistent worker threads if there are fewer than
+    // [num_threads], leaving none if the operating system fails to spawn them.
+    fn reserve_workers(&mut self, num_threads: NonZeroUsize) {
+        if self
+            .workers
+            .as_ref()
+            .is_none_or(|workers| workers.num_threads() < num_threads.get())
+        {
+            self.workers = WorkerPool::new(num_threads, self.pinned_workers);
+        }
+    }
+
+    // Make room for the execution results of a block of [block_size].
+    fn reserve_execution_results(&mut self, block_size: usize) {
+        let additional = block_size.saturating_sub(self.execution_results.len());
+        if additional > 0 {
+            self.execution_results.reserve(additional);
+            for _ in 0..additional {
+                self.execution_results.push(Mutex::new(None));
             }
         }
+    }
+
+    // Whether the block being executed has been aborted.
+    pub(crate) fn is_aborted(&self) -> bool {
+        self.abort_reason.get().is_some()
+    }
+
+    // Execute and validate the transactions of a block on the calling worker
+    // thread until the scheduler has no more tasks or the block is aborted.
+    fn run_worker<E: TxExecutor>(
+        &self,
+        mv_memory: &MvMemory,
+        scheduler: &Scheduler,
+        new_executor: &impl Fn() -> E,
+    ) {
+        let mut executor = new_executor();
+        let mut task = scheduler.next_task();
+        while task.is_some() {
+            task = match task.unwrap() {
+                Task::Execution(tx_version) => {
+                    self.try_execute(&mut executor, scheduler, tx_version)
+                }
+                Task::Validation(tx_version) => try_validate(mv_memory, scheduler, &tx_version),
+                Task::Idle(idle_task) => {
+                    run_idle_task(&mut executor, mv_memory, scheduler, idle_task)
+                }
+            };
+
+            // Transactions that fail to execute are either excluded from
+            // the block or abort it, depending on [PevmMode].
+            if self.abort_reason.get().is_some() {
+                break;
+            }
 
-        self.dropper.drop((mv_memory, scheduler));
+            if task.is_none() {
+                task = scheduler.next_task();
+            }
+        }
+    }
 
-        Ok(fully_evaluated_results)
+    // Take the execution result of a transaction after [Self::run_workers].
+    pub(crate) fn take_execution_result(
+        &self,
+        tx_idx: TxIdx,
+    ) -> Option<Result<PevmTxExecutionResult, ExecutionError>> {
+        index_mutex!(self.execution_results, tx_idx).take()
     }
 
-    fn try_execute<'a, S: Storage, C: PevmChain>(
+    pub(crate) fn try_execute(
         &self,
-        vm: &mut Vm<'a, S, C>,
+        executor: &mut impl TxExecutor,
         scheduler: &Scheduler,
         tx_version: TxVersion,
     ) -> Option<Task> {
+        let mut immediate_retries = 0;
         loop {
-            return match vm.execute(&tx_version) {
+            return match executor.execute(&tx_version) {
                 Err(VmExecutionError::Retry) => {
                     if self.abort_reason.get().is_none() {
                         continue;
@@ -388,8 +1461,9 @@ impl Pevm {
                 }
                 Err(VmExecutionError::FallbackToSequential) => {
                     scheduler.abort();
-                    self.abort_reason
-                        .get_or_init(|| AbortReason::FallbackToSequential);
+                    self.abort_reason.get_or_init(|| {
+                        AbortReason::FallbackToSequential(FallbackReason::LocationCollision)
+                    });
                     None
                 }
                 Err(VmExecutionError::Blocking(blocking_tx_idx)) => {
@@ -397,11 +1471,26 @@ impl Pevm {
                         && self.abort_reason.get().is_none()
                     {
                         // Retry the execution immediately if the blocking transaction was
-                        // re-executed by the time we can add it as a dependency.
+                        // re-executed by the time we can add it as a dependency. A
+                        // transaction that keeps blocking on executed transactions likely
+                        // fails a check that never passes, like a nonce gap in a faulty
+                        // block, so we execute the block sequentially to surface its error.
+                        immediate_retries += 1;
+                        if immediate_retries > MAX_IMMEDIATE_RETRIES {
+                            scheduler.abort();
+                            self.abort_reason.get_or_init(|| {
+                                AbortReason::FallbackToSequential(FallbackReason::BlockingRetries)
+                            });
+                            return None;
+                        }
                         continue;
                     }
                     None
                 }
+                Err(VmExecutionError::Pending) => {
+                    scheduler.park(tx_version.tx_idx);
+                    None
+                }
                 Err(VmExecutionError::ExecutionError(err)) => {
                     scheduler.abort();
                     self.abort_reason
@@ -421,7 +1510,229 @@ impl Pevm {
     }
 }
 
-fn try_validate(
+// Fully evaluate (the balance and nonce of) the lazy addresses in [mv_memory],
+// like the beneficiary account and raw transfer recipients that may have been
+// atomically updated, and the storage slots with commutative writes, into the
+// results of the block's transactions. [tx_at]
+// returns the spec and transaction of each index, if it is a transaction and
+// not a system operation like balance increments.
+pub(crate) fn evaluate_lazy_addresses<'a, S: Storage, C: PevmChain>(
+    chain: &C,
+    storage: &S,
+    mv_memory: &MvMemory,
+    tx_at: impl Fn(TxIdx) -> (C::EvmSpecId, Option<&'a TxEnv>),
+    fully_evaluated_results: &mut [PevmTxExecutionResult],
+) -> Result<(), PevmError<C>> {
+    for address in mv_memory.consume_lazy_addresses() {
+        let balance_writes = mv_memory
+            .data
+            .get(&hash_deterministic(MemoryLocation::Balance(address)));
+        // Nonces are always written as absolute values, or cleared by a
+        // self-destruct.
+        let nonce_writes: BTreeMap<TxIdx, u64> = mv_memory
+            .data
+            .get(&hash_deterministic(MemoryLocation::Nonce(address)))
+            .map(|write_history| {
+                write_history
+                    .entries
+                    .iter()
+                    .map(|(tx_idx, memory_entry)| match memory_entry {
+                        MemoryEntry::Data(_, MemoryValue::Nonce(nonce)) => (*tx_idx, *nonce),
+                        MemoryEntry::Data(_, MemoryValue::SelfDestructed) => (*tx_idx, 0),
+                        // TODO: Better error handling
+                        _ => unreachable!(),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let balance_entries = balance_writes
+            .as_ref()
+            .map(|write_history| &write_history.entries);
+        if balance_entries.is_none() && nonce_writes.is_empty() {
+            continue;
+        }
+        let first_balance_write = balance_entries.and_then(|entries| entries.first_key_value());
+
+        let mut balance = U256::ZERO;
+        let mut nonce = 0;
+        // Read from storage unless the same transaction first writes both the
+        // balance and the nonce as absolute values.
+        let first_balance_idx = match first_balance_write {
+            Some((
+                tx_idx,
+                MemoryEntry::Data(_, MemoryValue::Balance(_) | MemoryValue::SelfDestructed),
+            )) => Some(tx_idx),
+            _ => None,
+        };
+        if (first_balance_idx.is_none()
+            || first_balance_idx != nonce_writes.first_key_value().map(|(tx_idx, _)| tx_idx))
+            && let Ok(Some(account)) = storage.basic(&address)
+        {
+            balance = account.balance;
+            nonce = account.nonce;
+        }
+        // Accounts that take implicit writes like the beneficiary account can be contract!
+        let mut code_hash = match storage.code_hash(&address) {
+            Ok(code_hash) => code_hash,
+            Err(err) => return Err(PevmError::StorageError(err.to_string())),
+        };
+        let mut code = if let Some(code_hash) = &code_hash {
+            match storage.code_by_hash(code_hash) {
+                Ok(code) => code,
+                Err(err) => return Err(PevmError::StorageError(err.to_string())),
+            }
+        } else {
+            None
+        };
+
+        let tx_idxs: BTreeSet<TxIdx> = balance_entries
+            .into_iter()
+            .flat_map(BTreeMap::keys)
+            .chain(nonce_writes.keys())
+            .copied()
+            .collect();
+        for tx_idx in tx_idxs {
+            let (spec_id, tx) = tx_at(tx_idx);
+            let prev_nonce = nonce;
+            if let Some(nonce_write) = nonce_writes.get(&tx_idx) {
+                nonce = *nonce_write;
+            }
+            match balance_entries.and_then(|entries| entries.get(&tx_idx)) {
+                None => {}
+                Some(MemoryEntry::Data(_, MemoryValue::Balance(balance_write))) => {
+                    balance = *balance_write;
+                }
+                Some(MemoryEntry::Data(_, MemoryValue::LazyRecipient(addition))) => {
+                    balance = balance.saturating_add(*addition);
+                }
+                // The transaction already cleared the account in its state, so
+                // later implicit writes start from an empty account.
+                Some(MemoryEntry::Data(_, MemoryValue::SelfDestructed)) => {
+                    balance = U256::ZERO;
+                    code_hash = None;
+                    code = None;
+                    continue;
+                }
+                Some(MemoryEntry::Data(_, MemoryValue::LazySender(subtraction))) => {
+                    // We must re-do extra sender balance checks as we mock
+                    // the max value in [Vm] during execution. Ideally we
+                    // can turn off these redundant checks in revm.
+                    // Ideally we would share these calculations with revm
+                    // (using their utility functions).
+                    let Some(tx) = tx else {
+                        return Err(PevmError::UnreachableError);
+                    };
+                    // Lazy senders are executed with their transaction nonces,
+                    // which must continue the evaluated nonces of the account.
+                    if tx.nonce != prev_nonce {
+                        return Err(PevmError::NonceMismatch {
+                            tx_idx,
+                            tx_nonce: tx.nonce,
+                            executed_nonce: prev_nonce,
+                        });
+                    }
+                    let mut max_fee = U256::from(tx.gas_limit)
+                        .saturating_mul(U256::from(tx.gas_price))
+                        .saturating_add(tx.value);
+                    max_fee = max_fee.saturating_add(
+                        U256::from(tx.total_blob_gas())
+                            .saturating_mul(U256::from(tx.max_fee_per_blob_gas)),
+                    );
+                    if balance < max_fee {
+                        Err(ExecutionError::Transaction(
+                            InvalidTransaction::LackOfFundForMaxFee {
+                                balance: Box::new(balance),
+                                fee: Box::new(max_fee),
+                            },
+                        ))?
+                    }
+                    balance = balance.saturating_sub(*subtraction);
+                }
+                // TODO: Better error handling
+                _ => unreachable!(),
+            }
+            // Assert that evaluated nonce is correct when address is caller.
+            if let Some(tx) = tx
+                && tx.caller == address
+            {
+                let executed_nonce = if nonce == 0 {
+                    return Err(PevmError::UnreachableError);
+                } else {
+                    nonce - 1
+                };
+                if tx.nonce != executed_nonce {
+                    // TODO: Consider falling back to sequential instead
+                    return Err(PevmError::NonceMismatch {
+                        tx_idx,
+                        tx_nonce: tx.nonce,
+                        executed_nonce,
+                    });
+                }
+            }
+            // SAFETY: The multi-version data structure should not leak an index over block size.
+            let tx_result = unsafe { fully_evaluated_results.get_unchecked_mut(tx_idx) };
+            let account = tx_result.state.entry(address).or_default();
+            // TODO: Deduplicate this logic with [PevmTxExecutionResult::from_revm]
+            if chain.is_eip_161_enabled(spec_id)
+                && code_hash.is_none()
+                && nonce == 0
+                && balance == U256::ZERO
+            {
+                *account = None;
+            } else if let Some(account) = account {
+                // Explicit write: only overwrite the account info in case there are storage changes.
+                // Its code is kept for later implicit writes, as a self-destructed
+                // account may be re-created with new code midblock.
+                account.balance = balance;
+                account.nonce = nonce;
+                code_hash = account.code_hash;
+                code.clone_from(&account.code);
+            } else {
+                // Implicit write: e.g. gas payments to the beneficiary account,
+                // which doesn't have explicit writes in [tx_result.state]
+                *account = Some(EvmAccount {
+                    balance,
+                    nonce,
+                    code_hash,
+                    code: code.clone(),
+                    storage: HashMap::default(),
+                });
+            }
+        }
+    }
+    for (address, index) in mv_memory.consume_lazy_storage() {
+        let location = MemoryLocation::Storage(address, index);
+        let Some(write_history) = mv_memory.data.get(&hash_deterministic(location)) else {
+            continue;
+        };
+        let mut value = match write_history.entries.first_key_value() {
+            Some((_, MemoryEntry::Data(_, MemoryValue::Storage(_)))) => U256::ZERO,
+            _ => storage
+                .storage(&address, &index)
+                .map_err(|err| PevmError::StorageError(err.to_string()))?,
+        };
+        for (tx_idx, memory_entry) in &write_history.entries {
+            match memory_entry {
+                MemoryEntry::Data(_, MemoryValue::Storage(storage_write)) => {
+                    value = *storage_write;
+                }
+                MemoryEntry::Data(_, MemoryValue::LazyStorage(delta)) => {
+                    value = value.wrapping_add(*delta);
+                }
+                // TODO: Better error handling
+                _ => unreachable!(),
+            }
+            // SAFETY: The multi-version data structure should not leak an index over block size.
+            let tx_result = unsafe { fully_evaluated_results.get_unchecked_mut(*tx_idx) };
+            if let Some(Some(account)) = tx_result.state.get_mut(&address) {
+                account.storage.insert(index, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn try_validate(
     mv_memory: &MvMemory,
     scheduler: &Scheduler,
     tx_version: &TxVersion,
@@ -434,9 +1745,259 @@ fn try_validate(
     scheduler.finish_validation(tx_version, aborted)
 }
 
+// Do the useful work handed to an idle worker, returning the re-execution of
+// a transaction if its revalidation aborted it.
+fn run_idle_task(
+    executor: &mut impl TxExecutor,
+    mv_memory: &MvMemory,
+    scheduler: &Scheduler,
+    idle_task: IdleTask,
+) -> Option<Task> {
+    match idle_task {
+        IdleTask::PrefetchTx(tx_idx) => executor.prefetch_tx(tx_idx),
+        IdleTask::PrefetchLazy => {
+            for (address, index) in mv_memory.lazy_locations() {
+                executor.prefetch(&address, index.as_ref());
+            }
+        }
+        // Only failed revalidations have effects, the same as failed
+        // validation tasks. Passing ones leave the validation tasks to
+        // validate the transaction in order.
+        IdleTask::Revalidation(tx_version) => {
+            if !mv_memory.validate_read_locations(tx_version.tx_idx)
+                && scheduler.try_validation_abort(&tx_version)
+            {
+                mv_memory.convert_writes_to_estimates(tx_version.tx_idx);
+                return scheduler.finish_validation(&tx_version, true);
+            }
+        }
+        IdleTask::ResumeParked => {
+            executor.wait_for_fetches();
+            scheduler.resume_parked();
+        }
+    }
+    None
+}
+
+// Execute system calls sequentially on top of [storage], returning their merged
+// state transitions. The outputs of calls with a request type are collected into
+// [requests]. Calls to contracts without code are skipped, unless they collect
+// requests, which invalidates the block.
+fn execute_system_calls<S: Storage + Debug, C: PevmChain>(
+    chain: &C,
+    storage: &S,
+    spec_id: C::EvmSpecId,
+    block_env: &BlockEnv,
+    calls: Vec<SystemCall>,
+    requests: &mut Requests,
+) -> Result<EvmStateTransitions, PevmError<C>> {
+    let mut transitions = EvmStateTransitions::default();
+    if calls.is_empty() {
+        return Ok(transitions);
+    }
+
+    let db = CacheDB::new(StorageWrapper(storage));
+    let mut evm = chain.build_evm(spec_id, block_env.clone(), db);
+    for SystemCall {
+        address,
+        data,
+        request_type,
+    } in calls
+    {
+        let code_hash = storage
+            .code_hash(&address)
+            .map_err(|err| PevmError::StorageError(err.to_string()))?;
+        if code_hash.is_none_or(|code_hash| code_hash == KECCAK_EMPTY) {
+            if request_type.is_some() {
+                return Err(PevmError::MissingSystemContract(address));
+            }
+            continue;
+        }
+
+        let ResultAndState { result, mut state } = evm
+            .system_call(address, data)
+            .map_err(|err| PevmError::ExecutionError(execution_error(chain, err)))?;
+
+        // System calls neither charge the caller nor reward the beneficiary,
+        // but revm still loads and marks them as touched.
+        state.remove(&SYSTEM_ADDRESS);
+        state.remove(&block_env.beneficiary);
+
+        if let Some(request_type) = request_type {
+            match &result {
+                ExecutionResult::Success { output, .. } => {
+                    requests.push_request_with_type(request_type, output.data().clone());
+                }
+                _ => return Err(PevmError::SystemCallFailed(address)),
+            }
+        }
+
+        evm.ctx().db_mut().commit(state.clone());
+
+        let call_result =
+            PevmTxExecutionResult::from_revm(chain, spec_id, ResultAndState::new(result, state));
+        merge_transitions(&mut transitions, call_result.state);
+    }
+    Ok(transitions)
+}
+
+// The topic of the EIP-6110 `DepositEvent(bytes,bytes,bytes,bytes,bytes)` log.
+const DEPOSIT_EVENT_TOPIC: B256 =
+    b256!("0x649bbc62d0e31342afea4e5cd82d4049e7e1ee912fc0889aa790803be39038c5");
+
+// The ABI encoding of a deposit log: the offsets of its pubkey, withdrawal
+// credentials, amount, signature and index, then the length and padded bytes
+// of each.
+const DEPOSIT_LOG_LENGTH: usize = 576;
+const DEPOSIT_LOG_FIELDS: [(usize, usize); 5] =
+    [(160, 48), (256, 32), (320, 8), (384, 96), (512, 8)];
+
+// Collect the deposit logs of [deposit_contract] into a deposit request,
+// rejecting logs that do not have the exact layout of a deposit.
+pub(crate) fn collect_deposit_requests<C: PevmChain>(
+    deposit_contract: Address,
+    tx_results: &[PevmTxExecutionResult],
+    requests: &mut Requests,
+) -> Result<(), PevmError<C>> {
+    let mut deposits = Vec::new();
+    for log in tx_results
+        .iter()
+        .flat_map(|tx_result| &tx_result.receipt.logs)
+    {
+        if log.address != deposit_contract || log.topics().first() != Some(&DEPOSIT_EVENT_TOPIC) {
+            continue;
+        }
+        let data = &log.data.data;
+        if data.len() != DEPOSIT_LOG_LENGTH {
+            return Err(PevmError::InvalidDepositLog);
+        }
+        let word = |position: usize| U256::from_be_slice(&data[position..position + 32]);
+        for (field_idx, (offset, length)) in DEPOSIT_LOG_FIELDS.into_iter().enumerate() {
+            if word(32 * field_idx) != U256::from(offset) || word(offset) != U256::from(length) {
+                return Err(PevmError::InvalidDepositLog);
+            }
+            deposits.extend_from_slice(&data[offset + 32..offset + 32 + length]);
+        }
+    }
+    requests.push_request_with_type(DEPOSIT_REQUEST_TYPE, deposits);
+    Ok(())
+}
+
+// Parse the transactions of a block to execute.
+pub(crate) fn block_tx_envs<C: PevmChain>(
+    chain: &C,
+    block: &Block<C::Transaction>,
+) -> Result<Vec<C::EvmTx>, PevmError<C>> {
+    match &block.transactions {
+        BlockTransactions::Full(txs) => txs
+            .iter()
+            .map(|tx| chain.get_tx_env(tx))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(PevmError::InvalidTransaction),
+        _ => Err(PevmError::MissingTransactionData),
+    }
+}
+
+// Remove the results of the excluded transactions, sorted by index, in a
+// single pass over the results.
+pub(crate) fn remove_excluded_results<T>(
+    results: &mut Vec<T>,
+    excluded_txs: &[(TxIdx, ExecutionError)],
+) {
+    let mut excluded_tx_idxs = excluded_txs.iter().map(|(tx_idx, _)| *tx_idx).peekable();
+    let mut tx_idx = 0;
+    results.retain(|_| {
+        let is_excluded = excluded_tx_idxs.next_if_eq(&tx_idx).is_some();
+        tx_idx += 1;
+        !is_excluded
+    });
+}
+
+// Merge the next state transitions into the previous ones.
+pub(crate) fn merge_transitions(
+    transitions: &mut EvmStateTransitions,
+    next_transitions: EvmStateTransitions,
+) {
+    for (address, account) in next_transitions {
+        match (transitions.get_mut(&address), account) {
+            (Some(Some(prev)), Some(account)) => {
+                prev.balance = account.balance;
+                prev.nonce = account.nonce;
+                prev.code_hash = account.code_hash;
+                prev.code = account.code;
+                prev.storage.extend(account.storage);
+            }
+            (_, account) => {
+                transitions.insert(address, account);
+            }
+        }
+    }
+}
+
+// The end-of-block balance increments of block rewards and withdrawals.
+pub(crate) fn block_balance_increments<C: PevmChain>(
+    chain: &C,
+    spec_id: C::EvmSpecId,
+    block: &Block<C::Transaction>,
+    ommers: &[Header],
+) -> Vec<(Address, U256)> {
+    let mut balance_increments: HashMap<Address, U256> = HashMap::default();
+    for (address, reward) in
+        chain.get_block_rewards(spec_id, &block.header, block.uncles.len(), ommers)
+    {
+        let balance = balance_increments.entry(address).or_default();
+        *balance = balance.saturating_add(reward);
+    }
+    for withdrawal in block.withdrawals.iter().flatten() {
+        let balance = balance_increments.entry(withdrawal.address).or_default();
+        *balance = balance.saturating_add(withdrawal.amount_wei());
+    }
+    let mut balance_increments: Vec<_> = balance_increments
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .collect();
+    balance_increments.sort_unstable();
+    balance_increments
+}
+
+// Credit end-of-block balance increments on top of [storage], returning
+// the incremented accounts.
+fn apply_balance_increments<S: Storage, C: PevmChain>(
+    storage: &S,
+    balance_increments: &[(Address, U256)],
+) -> Result<EvmStateTransitions, PevmError<C>> {
+    let mut transitions = EvmStateTransitions::default();
+    for &(address, amount) in balance_increments {
+        let basic = storage
+            .basic(&address)
+            .map_err(|err| PevmError::StorageError(err.to_string()))?
+            .unwrap_or_default();
+        // Withdrawal and reward recipients can be contracts!
+        let code_hash = storage
+            .code_hash(&address)
+            .map_err(|err| PevmError::StorageError(err.to_string()))?;
+        let code = match &code_hash {
+            Some(code_hash) => storage
+                .code_by_hash(code_hash)
+                .map_err(|err| PevmError::StorageError(err.to_string()))?,
+            None => None,
+        };
+        transitions.insert(
+            address,
+            Some(EvmAccount {
+                balance: basic.balance.saturating_add(amount),
+                nonce: basic.nonce,
+                code_hash,
+                code,
+                storage: HashMap::default(),
+            }),
+        );
+    }
+    Ok(transitions)
+}
+
 /// Execute REVM transactions sequentially.
 // Useful for falling back for (small) blocks with many dependencies.
-// TODO: Use this for a long chain of sequential transactions even in parallel mode.
 pub fn execute_revm_sequential<S: Storage + Debug, C: PevmChain>(
     chain: &C,
     storage: &S,
@@ -444,16 +2005,37 @@ pub fn execute_revm_sequential<S: Storage + Debug, C: PevmChain>(
     block_env: BlockEnv,
     txs: Vec<C::EvmTx>,
 ) -> PevmResult<C> {
+    execute_revm_sequential_excluding(chain, storage, spec_id, block_env, txs, PevmMode::Sync)
+        .map(|(tx_results, _)| tx_results)
+}
+
+pub(crate) fn execute_revm_sequential_excluding<S: Storage + Debug, C: PevmChain>(
+    chain: &C,
+    storage: &S,
+    spec_id: C::EvmSpecId,
+    block_env: BlockEnv,
+    txs: Vec<C::EvmTx>,
+    mode: PevmMode,
+) -> ExclusiveResult<C> {
     let db = CacheDB::new(StorageWrapper(storage));
     let mut evm = chain.build_evm(spec_id, block_env, db);
 
     let mut results: Vec<PevmTxExecutionResult> = Vec::with_capacity(txs.len());
+    let mut excluded_txs = Vec::new();
     let mut cumulative_gas_used: u64 = 0;
-    for tx in txs {
-        // TODO: More concrete error type
-        let result_and_state = evm
-            .transact(tx)
-            .map_err(|err| ExecutionError::Custom(err.to_string()))?;
+    for (tx_idx, tx) in txs.into_iter().enumerate() {
+        let result_and_state = match evm.transact(tx) {
+            Ok(result_and_state) => result_and_state,
+            Err(err) => {
+                let excluded = mode.excludes(&err);
+                let err = execution_error(chain, err);
+                if !excluded {
+                    return Err(PevmError::ExecutionError(err));
+                }
+                excluded_txs.push((tx_idx, err));
+                continue;
+            }
+        };
 
         evm.ctx().db_mut().commit(result_and_state.state.clone());
 
@@ -466,5 +2048,5 @@ pub fn execute_revm_sequential<S: Storage + Debug, C: PevmChain>(
 
         results.push(execution_result);
     }
-    Ok(results)
+    Ok((results, excluded_txs))
 }
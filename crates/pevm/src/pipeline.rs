@@ -0,0 +1,372 @@
+use std::{fmt::Debug, iter, num::NonZeroUsize, sync::Mutex};
+
+use alloy_eips::eip7685::Requests;
+use alloy_primitives::{Address, U256};
+use alloy_rpc_types_eth::{Block, Header};
+use revm::context::BlockEnv;
+
+use crate::{
+    BlockStateDiff, EvmStateTransitions, Pevm, PevmBlockExecutionResult, PevmError,
+    PevmTxExecutionResult, Storage, TxIdx, TxVersion,
+    chain::{PevmChain, SystemCall},
+    compat::get_block_env,
+    mv_memory::MvMemory,
+    pevm::{
+        AbortReason, block_balance_increments, block_tx_envs, collect_deposit_requests,
+        evaluate_lazy_addresses, merge_transitions,
+    },
+    storage::StateOverlay,
+    vm::{SystemCallOutput, TxExecutor, Vm, VmExecutionError, VmExecutionResult},
+};
+
+// A block prepared for pipelined execution.
+struct PipelinedBlock<C: PevmChain> {
+    spec_id: C::EvmSpecId,
+    block_env: BlockEnv,
+    txs: Vec<C::EvmTx>,
+    pre_block_calls: Vec<SystemCall>,
+    post_block_calls: Vec<SystemCall>,
+    deposit_contract: Option<Address>,
+    balance_increments: Vec<(Address, U256)>,
+    // The index of the first transaction in the pipeline's [MvMemory].
+    first_tx_idx: TxIdx,
+}
+
+// What a pipeline index executes. Each block takes an index for each pre-block
+// system call, transaction, post-block system call, then one for its balance
+// increments.
+#[derive(Clone, Copy)]
+enum Slot {
+    SystemCall(usize, usize),
+    Tx(usize),
+    BalanceIncrements(usize),
+}
+
+// Executes the slots of all pipelined blocks, with a [Vm] for each block.
+struct PipelineExecutor<'a, S: Storage, C: PevmChain> {
+    vms: Vec<Vm<'a, S, C>>,
+    blocks: &'a [PipelinedBlock<C>],
+    slots: &'a [Slot],
+    // The outputs of the latest system call executions, for requests.
+    outputs: &'a [Mutex<Option<SystemCallOutput>>],
+}
+
+impl<S: Storage, C: PevmChain> TxExecutor for PipelineExecutor<'_, S, C> {
+    fn execute(&mut self, tx_version: &TxVersion) -> Result<VmExecutionResult, VmExecutionError> {
+        // SAFETY: A correct scheduler would guarantee this index to be inbound.
+        match unsafe { *self.slots.get_unchecked(tx_version.tx_idx) } {
+            Slot::SystemCall(block_idx, call_idx) => {
+                let block = &self.blocks[block_idx];
+                let call = if call_idx < block.pre_block_calls.len() {
+                    &block.pre_block_calls[call_idx]
+                } else {
+                    &block.post_block_calls[call_idx - block.pre_block_calls.len()]
+                };
+                let (result, output) = self.vms[block_idx].execute_system_call(tx_version, call)?;
+                *index_mutex!(self.outputs, tx_version.tx_idx) = Some(output);
+                Ok(result)
+            }
+            Slot::Tx(block_idx) => self.vms[block_idx].execute(tx_version),
+            Slot::BalanceIncrements(block_idx) => self.vms[block_idx]
+                .execute_balance_increments(tx_version, &self.blocks[block_idx].balance_increments),
+        }
+    }
+
+    fn prefetch_tx(&mut self, tx_idx: TxIdx) {
+        // SAFETY: A correct scheduler would guarantee this index to be inbound.
+        if let Slot::Tx(block_idx) = unsafe { *self.slots.get_unchecked(tx_idx) } {
+            self.vms[block_idx].prefetch_tx(tx_idx);
+        }
+    }
+
+    // The blocks are executed on top of the same storage.
+    fn prefetch(&mut self, address: &Address, index: Option<&U256>) {
+        if let Some(vm) = self.vms.first_mut() {
+            vm.prefetch(address, index);
+        }
+    }
+
+    fn wait_for_fetches(&mut self) {
+        if let Some(vm) = self.vms.first_mut() {
+            vm.wait_for_fetches();
+        }
+    }
+}
+
+// Execute consecutive blocks as a single multi-version block so the transactions
+// of a block can speculatively start on the unfinished writes of the previous
+// ones. Block boundaries like system calls and withdrawals are executed like
+// transactions in between, so cross-block reads are validated like intra-block
+// reads. The beneficiaries of the ommers of each block are rewarded when
+// their headers are given.
+pub(crate) fn execute_blocks<S, C>(
+    pevm: &mut Pevm,
+    chain: &C,
+    storage: &S,
+    blocks: &[Block<C::Transaction>],
+    ommers: &[Vec<Header>],
+    concurrency_level: NonZeroUsize,
+) -> Result<Vec<PevmBlockExecutionResult>, PevmError<C>>
+where
+    C: PevmChain + Send + Sync,
+    S: Storage + Send + Sync + Debug,
+{
+    // Later blocks can read the hashes of the earlier ones.
+    let mut overlay = StateOverlay::new(storage);
+    let mut pipelined_blocks = Vec::with_capacity(blocks.len());
+    let mut slots = Vec::new();
+    let (mut mv_memory, mut scheduler) = pevm.take_recycled();
+    mv_memory.reset(0, [], []);
+    for (block_idx, block) in blocks.iter().enumerate() {
+        overlay.insert_block_hash(block.header.number, block.header.hash);
+        let spec_id = chain
+            .get_block_spec(&block.header)
+            .map_err(PevmError::BlockSpecError)?;
+        let block_env = get_block_env(&block.header, spec_id);
+        let txs = block_tx_envs(chain, block)?;
+        let pre_block_calls = chain.pre_block_system_calls(spec_id, &block.header);
+        let post_block_calls = chain.post_block_system_calls(spec_id, &block.header);
+        let balance_increments = block_balance_increments(
+            chain,
+            spec_id,
+            block,
+            ommers.get(block_idx).map_or(&[], Vec::as_slice),
+        );
+
+        let num_calls = pre_block_calls.len() + post_block_calls.len();
+        slots.extend(
+            (0..pre_block_calls.len()).map(|call_idx| Slot::SystemCall(block_idx, call_idx)),
+        );
+        mv_memory.extend(pre_block_calls.len());
+        let first_tx_idx = slots.len();
+        slots.extend(iter::repeat_n(Slot::Tx(block_idx), txs.len()));
+        let mut block_mv_memory = MvMemory::default();
+        block_mv_memory.set_commutative_writes(pevm.commutative_writes());
+        block_mv_memory.set_skip_dependency_estimation(pevm.skip_dependency_estimation());
+        chain.prepare_mv_memory(&mut block_mv_memory, &block_env, &txs);
+        mv_memory.append(block_mv_memory);
+        slots.extend(
+            (pre_block_calls.len()..num_calls)
+                .map(|call_idx| Slot::SystemCall(block_idx, call_idx)),
+        );
+        slots.push(Slot::BalanceIncrements(block_idx));
+        mv_memory.extend(post_block_calls.len() + 1);
+
+        pipelined_blocks.push(PipelinedBlock::<C> {
+            spec_id,
+            block_env,
+            txs,
+            pre_block_calls,
+            post_block_calls,
+            deposit_contract: chain.deposit_contract(spec_id),
+            balance_increments,
+            first_tx_idx,
+        });
+    }
+    if slots.is_empty() {
+        pevm.recycle((mv_memory, scheduler));
+        return Ok(Vec::new());
+    }
+
+    let block_size = slots.len();
+    scheduler.reset(block_size);
+    let outputs = (0..block_size)
+        .map(|_| Mutex::new(None))
+        .collect::<Vec<_>>();
+    let mode = pevm.mode();
+    let abort_reason = pevm.run_workers(
+        block_size,
+        &mv_memory,
+        &scheduler,
+        concurrency_level,
+        || PipelineExecutor {
+            vms: pipelined_blocks
+                .iter()
+                .map(|block| {
+                    Vm::new(
+                        chain,
+                        block.spec_id,
+                        &block.block_env,
+                        &block.txs,
+                        &overlay,
+                        &mv_memory,
+                        mode,
+                    )
+                    .with_first_tx_idx(block.first_tx_idx)
+                })
+                .collect(),
+            blocks: &pipelined_blocks,
+            slots: &slots,
+            outputs: &outputs,
+        },
+        None,
+    );
+    match abort_reason {
+        // Execute the blocks one after another instead.
+        Some(AbortReason::FallbackToSequential(_)) => {
+            pevm.recycle((mv_memory, scheduler));
+            return execute_blocks_one_by_one(pevm, chain, storage, blocks, concurrency_level);
+        }
+        Some(AbortReason::ExecutionError(err)) => {
+            pevm.recycle((mv_memory, scheduler));
+            return Err(PevmError::ExecutionError(err));
+        }
+        None => {}
+    }
+
+    let mut results = Vec::with_capacity(block_size);
+    let mut excluded = Vec::new();
+    for tx_idx in 0..block_size {
+        match pevm.take_execution_result(tx_idx) {
+            Some(Ok(result)) => results.push(result),
+            Some(Err(err)) => {
+                // The lazy evaluation never touches excluded transactions as
+                // they have no writes.
+                excluded.push((tx_idx, err));
+                results.push(PevmTxExecutionResult {
+                    receipt: Default::default(),
+                    state: EvmStateTransitions::default(),
+                });
+            }
+            None => return Err(PevmError::UnreachableError),
+        }
+    }
+
+    let evaluated = evaluate_lazy_addresses(
+        chain,
+        &overlay,
+        &mv_memory,
+        |tx_idx| match slots[tx_idx] {
+            Slot::Tx(block_idx) => {
+                let block = &pipelined_blocks[block_idx];
+                let tx = &block.txs[tx_idx - block.first_tx_idx];
+                (block.spec_id, Some(chain.tx_env(tx)))
+            }
+            Slot::SystemCall(block_idx, _) | Slot::BalanceIncrements(block_idx) => {
+                (pipelined_blocks[block_idx].spec_id, None)
+            }
+        },
+        &mut results,
+    );
+    pevm.recycle((mv_memory, scheduler));
+    evaluated?;
+
+    // Split the results back into blocks.
+    let mut excluded = excluded.into_iter().peekable();
+    let mut slot_results = results.into_iter().zip(outputs);
+    let mut block_results = Vec::with_capacity(blocks.len());
+    // The state before each block to diff against.
+    let mut pre_state = StateOverlay::new(storage);
+    for block in &pipelined_blocks {
+        let mut requests = Requests::default();
+        let pre_block_state =
+            system_call_transitions(&block.pre_block_calls, &mut slot_results, &mut requests)?;
+
+        let mut tx_results = Vec::with_capacity(block.txs.len());
+        let mut excluded_txs = Vec::new();
+        let mut cumulative_gas_used: u64 = 0;
+        for tx_idx in block.first_tx_idx..block.first_tx_idx + block.txs.len() {
+            let (mut tx_result, _) = slot_results.next().ok_or(PevmError::UnreachableError)?;
+            if let Some((_, err)) = excluded.next_if(|(idx, _)| *idx == tx_idx) {
+                excluded_txs.push((tx_idx - block.first_tx_idx, err));
+                continue;
+            }
+            cumulative_gas_used =
+                cumulative_gas_used.saturating_add(tx_result.receipt.cumulative_gas_used);
+            tx_result.receipt.cumulative_gas_used = cumulative_gas_used;
+            tx_results.push(tx_result);
+        }
+
+        if let Some(deposit_contract) = block.deposit_contract {
+            collect_deposit_requests(deposit_contract, &tx_results, &mut requests)?;
+        }
+        let post_block_state =
+            system_call_transitions(&block.post_block_calls, &mut slot_results, &mut requests)?;
+        let (balance_increments_result, _) =
+            slot_results.next().ok_or(PevmError::UnreachableError)?;
+        let balance_increment_state = balance_increments_result.state;
+
+        let state_diff = BlockStateDiff::from_transitions(
+            &pre_state,
+            iter::once(&pre_block_state)
+                .chain(tx_results.iter().map(|tx_result| &tx_result.state))
+                .chain([&post_block_state, &balance_increment_state]),
+        )
+        .map_err(|err| PevmError::StorageError(err.to_string()))?;
+
+        let block_result = PevmBlockExecutionResult {
+            pre_block_state,
+            tx_results,
+            post_block_state,
+            balance_increment_state,
+            requests,
+            state_diff,
+            excluded_txs,
+        };
+        apply_block_result(&mut pre_state, &block_result);
+        block_results.push(block_result);
+    }
+    Ok(block_results)
+}
+
+// Merge the results of system call slots, collecting the requests from their
+// outputs.
+fn system_call_transitions<C: PevmChain>(
+    calls: &[SystemCall],
+    slot_results: &mut impl Iterator<Item = (PevmTxExecutionResult, Mutex<Option<SystemCallOutput>>)>,
+    requests: &mut Requests,
+) -> Result<EvmStateTransitions, PevmError<C>> {
+    let mut transitions = EvmStateTransitions::default();
+    for call in calls {
+        let (result, output) = slot_results.next().ok_or(PevmError::UnreachableError)?;
+        if let Some(request_type) = call.request_type {
+            match output.into_inner().unwrap() {
+                Some(SystemCallOutput::Success(output)) => {
+                    requests.push_request_with_type(request_type, output);
+                }
+                Some(SystemCallOutput::MissingCode) => {
+                    return Err(PevmError::MissingSystemContract(call.address));
+                }
+                _ => return Err(PevmError::SystemCallFailed(call.address)),
+            }
+        }
+        merge_transitions(&mut transitions, result.state);
+    }
+    Ok(transitions)
+}
+
+// Layer the state transitions of an executed block on top of [overlay].
+fn apply_block_result<S: Storage>(
+    overlay: &mut StateOverlay<'_, S>,
+    result: &PevmBlockExecutionResult,
+) {
+    overlay.apply(&result.pre_block_state);
+    for tx_result in &result.tx_results {
+        overlay.apply(&tx_result.state);
+    }
+    overlay.apply(&result.post_block_state);
+    overlay.apply(&result.balance_increment_state);
+}
+
+// Execute blocks one after another on top of the state of the previous ones.
+fn execute_blocks_one_by_one<S, C>(
+    pevm: &mut Pevm,
+    chain: &C,
+    storage: &S,
+    blocks: &[Block<C::Transaction>],
+    concurrency_level: NonZeroUsize,
+) -> Result<Vec<PevmBlockExecutionResult>, PevmError<C>>
+where
+    C: PevmChain + Send + Sync,
+    S: Storage + Send + Sync + Debug,
+{
+    let mut overlay = StateOverlay::new(storage);
+    let mut block_results = Vec::with_capacity(blocks.len());
+    for block in blocks {
+        let block_result = pevm.execute(chain, &overlay, block, concurrency_level, false)?;
+        apply_block_result(&mut overlay, &block_result);
+        overlay.insert_block_hash(block.header.number, block.header.hash);
+        block_results.push(block_result);
+    }
+    Ok(block_results)
+}
@@ -0,0 +1,169 @@
+use std::{fmt::Debug, num::NonZeroUsize};
+
+use alloy_primitives::Address;
+use hashbrown::HashSet;
+use revm::context::TxEnv;
+
+use crate::BuildSuffixHasher;
+
+/// Cheap features of a block's transactions, from which an
+/// [`ExecutionPolicy`] decides how to execute it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BlockFeatures {
+    /// The number of transactions.
+    pub num_txs: usize,
+    /// The total gas limit of the transactions, an upper bound of the gas
+    /// they will use as that is unknown before execution, like when building
+    /// blocks.
+    pub total_gas_limit: u64,
+    /// The number of distinct senders.
+    pub num_senders: usize,
+    /// The number of distinct recipients, counting each contract creation as
+    /// a new recipient.
+    pub num_recipients: usize,
+    /// The number of transactions that likely execute contract code, being
+    /// contract creations or calls with input data.
+    pub num_contract_calls: usize,
+    /// The gas used by the block from its header, which is unknown when
+    /// building blocks.
+    pub gas_used: Option<u64>,
+}
+
+impl BlockFeatures {
+    /// Extract the features of a block's transactions.
+    pub fn new<'a>(txs: impl IntoIterator<Item = &'a TxEnv>) -> Self {
+        let mut features = Self::default();
+        let mut senders = HashSet::<Address, BuildSuffixHasher>::default();
+        let mut recipients = HashSet::<Address, BuildSuffixHasher>::default();
+        for tx in txs {
+            features.num_txs += 1;
+            features.total_gas_limit = features.total_gas_limit.saturating_add(tx.gas_limit);
+            senders.insert(tx.caller);
+            match tx.kind.to() {
+                Some(to) => {
+                    recipients.insert(*to);
+                    if !tx.data.is_empty() {
+                        features.num_contract_calls += 1;
+                    }
+                }
+                None => {
+                    features.num_recipients += 1;
+                    features.num_contract_calls += 1;
+                }
+            }
+        }
+        features.num_senders = senders.len();
+        features.num_recipients += recipients.len();
+        features
+    }
+
+    /// The share of transactions whose sender already sent an earlier
+    /// transaction in the block, from 0 to 1.
+    pub fn sender_collision_ratio(&self) -> f64 {
+        self.collision_ratio(self.num_senders)
+    }
+
+    /// The share of transactions whose recipient already received an
+    /// earlier transaction in the block, from 0 to 1.
+    pub fn recipient_collision_ratio(&self) -> f64 {
+        self.collision_ratio(self.num_recipients)
+    }
+
+    /// The share of transactions that likely execute contract code, from 0 to 1.
+    pub fn contract_call_share(&self) -> f64 {
+        if self.num_txs == 0 {
+            return 0.0;
+        }
+        self.num_contract_calls as f64 / self.num_txs as f64
+    }
+
+    fn collision_ratio(&self, num_distinct: usize) -> f64 {
+        if self.num_txs == 0 {
+            return 0.0;
+        }
+        1.0 - num_distinct as f64 / self.num_txs as f64
+    }
+}
+
+/// How to execute a block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutionStrategy {
+    /// Execute the transactions one after another on the calling thread.
+    Sequential,
+    /// Execute the transactions in parallel with this many worker threads,
+    /// capped at the concurrency level given to pevm.
+    Parallel(NonZeroUsize),
+}
+
+/// A policy that decides how to execute each block from its features. Custom
+/// policies can be tuned to the running machine and workload, and are set with
+/// [`crate::Pevm::with_policy`].
+pub trait ExecutionPolicy: Debug + Send + Sync {
+    /// Decide how to execute a block with `features`, using at most
+    /// `concurrency_level` worker threads.
+    fn decide(
+        &self,
+        features: &BlockFeatures,
+        concurrency_level: NonZeroUsize,
+    ) -> ExecutionStrategy;
+}
+
+// Below this gas, thread overheads outweigh the gains of parallel execution.
+const MIN_PARALLEL_GAS: u64 = 4_000_000;
+
+/// The default policy, which executes blocks with fewer transactions than the
+/// concurrency level or using under 4M gas sequentially, and the others with
+/// every worker. Blocks without a known gas used, like those being built, are
+/// judged by the total gas limit of their transactions.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DefaultExecutionPolicy;
+
+impl ExecutionPolicy for DefaultExecutionPolicy {
+    fn decide(
+        &self,
+        features: &BlockFeatures,
+        concurrency_level: NonZeroUsize,
+    ) -> ExecutionStrategy {
+        let gas = features.gas_used.unwrap_or(features.total_gas_limit);
+        if gas < MIN_PARALLEL_GAS || features.num_txs < concurrency_level.get() {
+            ExecutionStrategy::Sequential
+        } else {
+            ExecutionStrategy::Parallel(concurrency_level)
+        }
+    }
+}
+
+// Below these, thread overheads outweigh the gains of parallel execution.
+const MIN_TXS_PER_WORKER: usize = 4;
+const MIN_GAS_PER_WORKER: u64 = 2_000_000;
+
+/// A policy that gives each worker enough transactions and gas to outweigh
+/// thread overheads, and executes blocks that would not use at least two
+/// workers sequentially. It is not benchmarked on mainnet blocks yet, so it
+/// must be set with [`crate::Pevm::with_policy`].
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AdaptiveExecutionPolicy;
+
+impl ExecutionPolicy for AdaptiveExecutionPolicy {
+    fn decide(
+        &self,
+        features: &BlockFeatures,
+        concurrency_level: NonZeroUsize,
+    ) -> ExecutionStrategy {
+        let mut workers = concurrency_level
+            .get()
+            .min(features.num_txs / MIN_TXS_PER_WORKER)
+            .min((features.total_gas_limit / MIN_GAS_PER_WORKER) as usize);
+        // The contract calls of the same sender are chained by their nonces,
+        // unlike raw transfers that are lazily updated. Recipient collisions
+        // are left to custom policies, as calls to the same contract often
+        // write different slots, like ERC20 transfers between distinct holders.
+        if features.contract_call_share() >= 0.5 {
+            workers = workers.min(features.num_senders);
+        }
+        match NonZeroUsize::new(workers) {
+            Some(workers) if workers.get() >= 2 => ExecutionStrategy::Parallel(workers),
+            _ => ExecutionStrategy::Sequential,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+use alloy_primitives::{Address, U256};
+use hashbrown::HashSet;
+use revm::{context::BlockEnv, context_interface::either::Either};
+
+use crate::{Storage, chain::PevmChain, storage::CachedStorage, worker_pool::Helper};
+
+// Prefetches the state that transactions are likely to read into a
+// [CachedStorage] on the spare threads of the worker pool, running ahead of
+// the executions so cold reads are less likely to stall them. The locations
+// come from the transactions' senders, recipients, access lists, and EIP-7702
+// authorizations.
+#[derive(Debug)]
+pub(crate) struct Prefetcher<'a, S: Storage> {
+    storage: &'a CachedStorage<'a, S>,
+    // The accounts and their storage slots to prefetch, in transaction order.
+    locations: Vec<(Address, Option<U256>)>,
+    // The next location to prefetch, shared by the prefetching threads.
+    next_idx: AtomicUsize,
+    // Set when the block is executed to stop prefetching.
+    stopped: AtomicBool,
+}
+
+impl<'a, S: Storage> Prefetcher<'a, S> {
+    pub(crate) fn new<C: PevmChain>(
+        storage: &'a CachedStorage<'a, S>,
+        chain: &C,
+        block_env: &BlockEnv,
+        txs: &[C::EvmTx],
+    ) -> Self {
+        let mut locations = Vec::new();
+        let mut seen = HashSet::new();
+        let mut push = |address: Address, index: Option<U256>| {
+            if seen.insert((address, index)) {
+                locations.push((address, index));
+            }
+        };
+        push(block_env.beneficiary, None);
+        for tx in txs {
+            let tx = chain.tx_env(tx);
+            push(tx.caller, None);
+            if let Some(to) = tx.kind.to() {
+                push(*to, None);
+            }
+            for item in tx.access_list.iter() {
+                push(item.address, None);
+                for key in &item.storage_keys {
+                    push(item.address, Some((*key).into()));
+                }
+            }
+            for authorization in &tx.authorization_list {
+                let (authority, delegated) = match authorization {
+                    Either::Left(signed) => (signed.recover_authority().ok(), signed.address),
+                    Either::Right(recovered) => (recovered.authority(), recovered.address),
+                };
+                if let Some(authority) = authority {
+                    push(authority, None);
+                }
+                push(delegated, None);
+            }
+        }
+        Self {
+            storage,
+            locations,
+            next_idx: AtomicUsize::new(0),
+            stopped: AtomicBool::new(false),
+        }
+    }
+}
+
+impl<S: Storage + Sync> Helper for Prefetcher<'_, S> {
+    // Fetch the next locations until all of them are fetched or stopped. Each
+    // thread helping fetches different locations.
+    fn help(&self) {
+        while !self.stopped.load(Ordering::Relaxed) {
+            let Some((address, index)) = self
+                .locations
+                .get(self.next_idx.fetch_add(1, Ordering::Relaxed))
+            else {
+                return;
+            };
+            self.storage.prefetch(address, index.as_ref());
+        }
+    }
+
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,237 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    ReadOrigin, ReadSet, Task, TxIdx, TxIncarnation, TxVersion, hash_deterministic,
+    mv_memory::MvMemory, pevm::Pevm, scheduler::Scheduler, vm::TxExecutor,
+};
+
+/// An event of the scheduler while executing a block in parallel, in the
+/// global order that the workers interleaved them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScheduleEvent {
+    /// A worker started executing an incarnation of a transaction.
+    Execution {
+        /// The index of the transaction.
+        tx_idx: usize,
+        /// The incarnation executed, counting from 0.
+        incarnation: usize,
+    },
+    /// An incarnation of a transaction finished executing, after its writes
+    /// were recorded for higher transactions to read.
+    FinishedExecution {
+        /// The index of the transaction.
+        tx_idx: usize,
+        /// The incarnation executed, counting from 0.
+        incarnation: usize,
+        /// A digest of the transaction versions that the execution read each
+        /// memory location from, to detect replays that read others.
+        reads: u64,
+    },
+    /// A worker started validating an incarnation of a transaction.
+    Validation {
+        /// The index of the transaction.
+        tx_idx: usize,
+        /// The incarnation validated, counting from 0.
+        incarnation: usize,
+    },
+    /// A validation aborted an incarnation of a transaction.
+    ValidationAbort {
+        /// The index of the transaction.
+        tx_idx: usize,
+        /// The incarnation aborted, counting from 0.
+        incarnation: usize,
+    },
+    /// An execution blocked on a lower transaction, to be re-executed after it.
+    Dependency {
+        /// The index of the blocked transaction.
+        tx_idx: usize,
+        /// The index of the transaction it waits for.
+        blocking_tx_idx: usize,
+    },
+}
+
+impl ScheduleEvent {
+    // The event of handing [task] to a worker. Idle tasks are not recorded,
+    // as only their aborts have effects, which are recorded on their own.
+    pub(crate) const fn from_task(task: &Task) -> Option<Self> {
+        match task {
+            Task::Execution(tx_version) => Some(Self::Execution {
+                tx_idx: tx_version.tx_idx,
+                incarnation: tx_version.tx_incarnation,
+            }),
+            Task::Validation(tx_version) => Some(Self::Validation {
+                tx_idx: tx_version.tx_idx,
+                incarnation: tx_version.tx_incarnation,
+            }),
+            Task::Idle(_) => None,
+        }
+    }
+}
+
+/// The interleaving of the tasks of a block executed in parallel, recorded
+/// when [`crate::Pevm::with_schedule_recording`] is set and taken with
+/// [`crate::Pevm::take_schedule_log`]. It can be replayed on a single thread
+/// with [`crate::Pevm::replay_revm_parallel`] to step through a failing
+/// parallel execution deterministically.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduleLog {
+    /// The events in the order they happened.
+    pub events: Vec<ScheduleEvent>,
+}
+
+// The events of the scheduler and the multi-version memory in a global order.
+// Executions are logged as finished right before their writes are recorded,
+// as higher transactions may read them before the scheduler is notified.
+#[derive(Debug, Default)]
+pub(crate) struct ScheduleRecorder(Mutex<Vec<ScheduleEvent>>);
+
+impl ScheduleRecorder {
+    pub(crate) fn record(&self, event: ScheduleEvent) {
+        self.0.lock().unwrap().push(event);
+    }
+
+    pub(crate) fn take(&self) -> ScheduleLog {
+        ScheduleLog {
+            events: std::mem::take(&mut *self.0.lock().unwrap()),
+        }
+    }
+}
+
+// A digest of the origins of [read_set], independent of its iteration order.
+// Commutative reads are validated by their constraints instead of by origin,
+// so only their kind is digested.
+pub(crate) fn read_set_digest(read_set: &ReadSet) -> u64 {
+    let mut reads: Vec<_> = read_set
+        .iter()
+        .map(|(location_hash, origins)| {
+            let origins: Vec<_> = origins
+                .iter()
+                .map(|origin| match origin {
+                    ReadOrigin::MvMemory(tx_version) => {
+                        (0, tx_version.tx_idx, tx_version.tx_incarnation)
+                    }
+                    ReadOrigin::Storage => (1, 0, 0),
+                    ReadOrigin::SelfDestruct(tx_idx) => (2, *tx_idx, 0),
+                    ReadOrigin::Commutative(_) => (3, 0, 0),
+                })
+                .collect();
+            (*location_hash, origins)
+        })
+        .collect();
+    reads.sort_unstable();
+    hash_deterministic(reads)
+}
+
+// Force the transitions of [log] in order on the calling thread. Executions
+// run when they finished in the recording, so their writes are recorded in
+// the same order, and blocked executions and aborting validations are forced
+// instead of re-evaluated. Executions that never finished, like when the
+// block was aborted, run at the end. The values read cannot be forced, as
+// the multi-version memory only keeps the latest incarnation of each
+// transaction, so executions that read from other transaction versions than
+// recorded are detected instead. Return the position of the first event that
+// cannot be replayed, or the log length if the replayed block is not valid,
+// as the replay has diverged from the recording.
+pub(crate) fn replay_tasks<E: TxExecutor>(
+    pevm: &Pevm,
+    log: &ScheduleLog,
+    mv_memory: &MvMemory,
+    scheduler: &Scheduler,
+    mut executor: E,
+) -> Result<(), usize> {
+    // The executions that have started but neither finished nor blocked.
+    let mut executing: BTreeMap<TxIdx, TxIncarnation> = BTreeMap::new();
+    for (position, event) in log.events.iter().enumerate() {
+        match *event {
+            ScheduleEvent::Execution {
+                tx_idx,
+                incarnation,
+            } => {
+                let tx_version = TxVersion {
+                    tx_idx,
+                    tx_incarnation: incarnation,
+                };
+                if executing.get(&tx_idx) != Some(&incarnation)
+                    && !scheduler.claim_execution(&tx_version)
+                {
+                    return Err(position);
+                }
+                executing.insert(tx_idx, incarnation);
+            }
+            ScheduleEvent::FinishedExecution {
+                tx_idx,
+                incarnation,
+                reads,
+            } => {
+                if executing.remove(&tx_idx) != Some(incarnation) {
+                    return Err(position);
+                }
+                let tx_version = TxVersion {
+                    tx_idx,
+                    tx_incarnation: incarnation,
+                };
+                // The follow-up validation is recorded separately.
+                pevm.try_execute(&mut executor, scheduler, tx_version.clone());
+                if pevm.is_aborted() {
+                    return Ok(());
+                }
+                if !scheduler.is_executed(&tx_version) || mv_memory.read_set_digest(tx_idx) != reads
+                {
+                    return Err(position);
+                }
+            }
+            ScheduleEvent::Dependency {
+                tx_idx,
+                blocking_tx_idx,
+            } => {
+                if executing.remove(&tx_idx).is_none()
+                    || !scheduler.replay_dependency(tx_idx, blocking_tx_idx)
+                {
+                    return Err(position);
+                }
+            }
+            ScheduleEvent::ValidationAbort {
+                tx_idx,
+                incarnation,
+            } => {
+                let tx_version = TxVersion {
+                    tx_idx,
+                    tx_incarnation: incarnation,
+                };
+                if !scheduler.try_validation_abort(&tx_version) {
+                    return Err(position);
+                }
+                mv_memory.convert_writes_to_estimates(tx_idx);
+                if let Some(Task::Execution(tx_version)) =
+                    scheduler.finish_validation(&tx_version, true)
+                {
+                    executing.insert(tx_version.tx_idx, tx_version.tx_incarnation);
+                }
+            }
+            // Only aborting validations have effects.
+            ScheduleEvent::Validation { .. } => {}
+        }
+    }
+
+    for (tx_idx, incarnation) in executing {
+        pevm.try_execute(
+            &mut executor,
+            scheduler,
+            TxVersion {
+                tx_idx,
+                tx_incarnation: incarnation,
+            },
+        );
+        if pevm.is_aborted() {
+            return Ok(());
+        }
+    }
+    if !scheduler.is_fully_executed()
+        || !(0..mv_memory.block_size()).all(|tx_idx| mv_memory.validate_read_locations(tx_idx))
+    {
+        return Err(log.events.len());
+    }
+    Ok(())
+}
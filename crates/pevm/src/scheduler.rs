@@ -1,7 +1,7 @@
 use std::{
     cmp::min,
     sync::{
-        Mutex,
+        Arc, Mutex,
         atomic::{AtomicBool, AtomicUsize, Ordering},
     },
     thread,
@@ -9,7 +9,10 @@ use std::{
 
 use smallvec::SmallVec;
 
-use crate::{FinishExecFlags, IncarnationStatus, Task, TxIdx, TxStatus, TxVersion};
+use crate::{
+    FinishExecFlags, IdleTask, IncarnationStatus, ScheduleEvent, Task, TxIdx, TxIncarnation,
+    TxStatus, TxVersion, replay::ScheduleRecorder,
+};
 
 // The Pevm collaborative scheduler coordinates execution & validation
 // tasks among work threads.
@@ -36,7 +39,39 @@ use crate::{FinishExecFlags, IncarnationStatus, Task, TxIdx, TxStatus, TxVersion
 // transactions. Threads that perform these tasks can already detect validation
 // failure due to the ESTIMATE markers on memory locations, instead of waiting
 // for a subsequent incarnation to finish.
+//
+// A transaction that keeps aborting likely starts a long chain of dependent
+// transactions, like swaps in a hot AMM pool or transactions of the same
+// sender. Executing them speculatively in parallel mostly wastes incarnations,
+// so the scheduler hands them out as a sequential segment instead: one at a
+// time and in order, each reading the fresh writes of the previous ones in the
+// multi-version memory. Other workers skip the transactions ahead in the
+// segment to keep executing and validating the rest of the block.
+//
+// When there is no task left but the block is not done yet, like near its
+// end or while waiting for a huge blocking transaction, idle workers sweep the
+// block once after each finished execution instead of spinning. They prefetch
+// the state of the lazily updated locations and of the transactions waiting
+// to re-execute, and revalidate the executed transactions ahead of the
+// validation tasks, to abort those that read stale values sooner.
+
+// The incarnation at which a transaction starts a sequential segment.
+const SEGMENT_MIN_INCARNATION: TxIncarnation = 2;
+// The number of transactions a sequential segment reserves at a time. It
+// reserves more while the transactions after it keep aborting.
+const SEGMENT_LENGTH: usize = 16;
+
 #[derive(Debug)]
+struct SequentialSegment {
+    // The next transaction to execute in the segment.
+    next_idx: TxIdx,
+    // The end of the reserved transactions, exclusive.
+    end_idx: TxIdx,
+    // The transaction of the segment being executed, if any.
+    executing_idx: Option<TxIdx>,
+}
+
+#[derive(Debug, Default)]
 pub(crate) struct Scheduler {
     // The number of transactions in this block.
     block_size: usize,
@@ -48,6 +83,11 @@ pub(crate) struct Scheduler {
     // The list of dependent transactions to resume when the
     // key transaction is re-executed.
     transactions_dependents: Vec<Mutex<SmallVec<[TxIdx; 1]>>>,
+    // The transactions parked while their reads are fetched from an
+    // asynchronous storage, to resume when there are no other tasks.
+    parked_transactions: Mutex<Vec<TxIdx>>,
+    // Whether a worker is waiting to resume the parked transactions.
+    resuming_parked: AtomicBool,
     // The next transaction to try and execute.
     execution_idx: AtomicUsize,
     // The next transaction to try and validate.
@@ -60,31 +100,191 @@ pub(crate) struct Scheduler {
     // True if the scheduler has been aborted, likely due to fatal execution
     // errors.
     aborted: AtomicBool,
+    // The segment of transactions that keep aborting, executed in order.
+    segment: Mutex<Option<SequentialSegment>>,
+    // Whether there is a segment, to skip locking it in the common case.
+    has_segment: AtomicBool,
+    // The number of validations that aborted an executed incarnation.
+    validation_aborts: AtomicUsize,
+    // The transactions that blocked on lower transactions with the transaction
+    // they waited for, only recorded when collecting statistics.
+    dependencies: Option<Mutex<Vec<(TxIdx, TxIdx)>>>,
+    // The interleaving of the tasks handed to workers and their aborts, only
+    // recorded when debugging.
+    log: Option<Arc<ScheduleRecorder>>,
+    // The next step of the idle workers' sweep of the block.
+    idle_idx: AtomicUsize,
+    // The number of tasks handed to idle workers.
+    idle_tasks: AtomicUsize,
 }
 
 // TODO: Better error handling.
 // Like returning errors instead of panicking on [unreachable]s.
 impl Scheduler {
-    pub(crate) fn new(block_size: usize) -> Self {
-        Self {
-            block_size,
-            execution_idx: AtomicUsize::new(0),
-            transactions_status: (0..block_size)
-                .map(|_| {
-                    Mutex::new(TxStatus {
-                        incarnation: 0,
-                        status: IncarnationStatus::ReadyToExecute,
-                    })
-                })
-                .collect(),
-            transactions_dependents: (0..block_size).map(|_| Mutex::default()).collect(),
-            // We won't validate until we find the first non-lazy transaction that
-            // needs to read explicit values. We also skip the first transaction.
-            validation_idx: AtomicUsize::new(block_size),
-            min_validation_idx: AtomicUsize::new(block_size),
-            num_validated: AtomicUsize::new(0),
-            aborted: AtomicBool::new(false),
+    // Reset the scheduler of the previous block for a new one in place,
+    // keeping the allocated capacity of its transaction lists.
+    pub(crate) fn reset(&mut self, block_size: usize) {
+        self.block_size = block_size;
+        self.transactions_status.clear();
+        self.transactions_status.resize_with(block_size, || {
+            Mutex::new(TxStatus {
+                incarnation: 0,
+                status: IncarnationStatus::ReadyToExecute,
+            })
+        });
+        self.transactions_dependents.truncate(block_size);
+        for dependents in &mut self.transactions_dependents {
+            dependents.get_mut().unwrap().clear();
         }
+        self.transactions_dependents
+            .resize_with(block_size, Mutex::default);
+        self.parked_transactions.get_mut().unwrap().clear();
+        *self.resuming_parked.get_mut() = false;
+        *self.execution_idx.get_mut() = 0;
+        // We won't validate until we find the first non-lazy transaction that
+        // needs to read explicit values. We also skip the first transaction.
+        *self.validation_idx.get_mut() = block_size;
+        *self.min_validation_idx.get_mut() = block_size;
+        *self.num_validated.get_mut() = 0;
+        *self.aborted.get_mut() = false;
+        *self.segment.get_mut().unwrap() = None;
+        *self.has_segment.get_mut() = false;
+        *self.validation_aborts.get_mut() = 0;
+        self.dependencies = None;
+        self.log = None;
+        *self.idle_idx.get_mut() = 0;
+        *self.idle_tasks.get_mut() = 0;
+    }
+
+    // Record the interleaving of tasks to replay it later.
+    pub(crate) fn record_schedule(&mut self, recorder: Arc<ScheduleRecorder>) {
+        self.log = Some(recorder);
+    }
+
+    fn log_event(&self, event: ScheduleEvent) {
+        if let Some(log) = &self.log {
+            log.record(event);
+        }
+    }
+
+    // Log the task handed to a worker, if any.
+    fn log_task(&self, task: Option<Task>) -> Option<Task> {
+        if let Some(event) = task.as_ref().and_then(ScheduleEvent::from_task) {
+            self.log_event(event);
+        }
+        task
+    }
+
+    // Claim a recorded execution while replaying, if the transaction is ready
+    // to execute the same incarnation.
+    pub(crate) fn claim_execution(&self, tx_version: &TxVersion) -> bool {
+        let mut tx = index_mutex!(self.transactions_status, tx_version.tx_idx);
+        if tx.status == IncarnationStatus::ReadyToExecute
+            && tx.incarnation == tx_version.tx_incarnation
+        {
+            tx.status = IncarnationStatus::Executing;
+            return true;
+        }
+        false
+    }
+
+    // Force a recorded dependency while replaying. Executions are replayed when
+    // their writes are recorded, before they resume their dependents, so the
+    // blocking transaction may have executed already. The blocked transaction
+    // is then ready to execute again, like when it is resumed.
+    pub(crate) fn replay_dependency(&self, tx_idx: TxIdx, blocking_tx_idx: TxIdx) -> bool {
+        if self.add_dependency(tx_idx, blocking_tx_idx) {
+            return true;
+        }
+        {
+            let mut tx = index_mutex!(self.transactions_status, tx_idx);
+            if tx.status != IncarnationStatus::Executing {
+                return false;
+            }
+            tx.status = IncarnationStatus::Aborting;
+        }
+        self.leave_segment(tx_idx, true);
+        self.set_ready_status(tx_idx);
+        self.execution_idx.fetch_min(tx_idx, Ordering::Relaxed);
+        true
+    }
+
+    // Whether the incarnation has been executed and not aborted.
+    pub(crate) fn is_executed(&self, tx_version: &TxVersion) -> bool {
+        let tx = index_mutex!(self.transactions_status, tx_version.tx_idx);
+        tx.incarnation == tx_version.tx_incarnation
+            && matches!(
+                tx.status,
+                IncarnationStatus::Executed | IncarnationStatus::Validated
+            )
+    }
+
+    // Whether the last incarnations of all transactions have been executed.
+    pub(crate) fn is_fully_executed(&self) -> bool {
+        self.transactions_status.iter().all(|tx| {
+            matches!(
+                tx.lock().unwrap().status,
+                IncarnationStatus::Executed | IncarnationStatus::Validated
+            )
+        })
+    }
+
+    // Record the dependencies that transactions block on, for statistics.
+    pub(crate) fn record_dependencies(&mut self) {
+        self.dependencies = Some(Mutex::default());
+    }
+
+    // The number of incarnations that each transaction was executed with.
+    pub(crate) fn incarnations(&self) -> Vec<usize> {
+        self.transactions_status
+            .iter()
+            .map(|tx| tx.lock().unwrap().incarnation + 1)
+            .collect()
+    }
+
+    pub(crate) fn validation_aborts(&self) -> usize {
+        self.validation_aborts.load(Ordering::Relaxed)
+    }
+
+    // The number of tasks handed to idle workers.
+    pub(crate) fn idle_tasks(&self) -> usize {
+        self.idle_tasks.load(Ordering::Relaxed)
+    }
+
+    // Take the recorded dependencies, in the order they were added.
+    pub(crate) fn take_dependencies(&self) -> Vec<(TxIdx, TxIdx)> {
+        self.dependencies
+            .as_ref()
+            .map(|dependencies| std::mem::take(&mut *dependencies.lock().unwrap()))
+            .unwrap_or_default()
+    }
+
+    // Append [additional] transactions to a block whose previous transactions
+    // have all been executed and validated, so workers can resume scheduling
+    // from the new transactions.
+    pub(crate) fn extend(&mut self, additional: usize) {
+        let old_size = self.block_size;
+        let new_size = old_size + additional;
+        self.transactions_status.extend((0..additional).map(|_| {
+            Mutex::new(TxStatus {
+                incarnation: 0,
+                status: IncarnationStatus::ReadyToExecute,
+            })
+        }));
+        self.transactions_dependents
+            .extend((0..additional).map(|_| Mutex::default()));
+        self.execution_idx = AtomicUsize::new(old_size);
+        self.validation_idx = AtomicUsize::new(new_size);
+        // The previous transactions are final, so we only count the validated ones
+        // from the min validation index for termination.
+        let min_validation_idx = *self.min_validation_idx.get_mut();
+        if min_validation_idx >= old_size {
+            self.min_validation_idx = AtomicUsize::new(new_size);
+        }
+        self.num_validated = AtomicUsize::new(old_size - min(min_validation_idx, old_size));
+        *self.segment.get_mut().unwrap() = None;
+        *self.has_segment.get_mut() = false;
+        self.block_size = new_size;
     }
 
     pub(crate) fn abort(&self) {
@@ -92,29 +292,172 @@ impl Scheduler {
     }
 
     fn try_execute(&self, tx_idx: TxIdx) -> Option<TxVersion> {
-        if tx_idx < self.block_size {
+        if tx_idx < self.block_size && !self.is_reserved(tx_idx) {
+            let tx_version = self.try_incarnate(tx_idx)?;
+            self.try_start_segment(&tx_version);
+            return Some(tx_version);
+        }
+        None
+    }
+
+    fn try_incarnate(&self, tx_idx: TxIdx) -> Option<TxVersion> {
+        let mut tx = index_mutex!(self.transactions_status, tx_idx);
+        if tx.status == IncarnationStatus::ReadyToExecute {
+            tx.status = IncarnationStatus::Executing;
+            return Some(TxVersion {
+                tx_idx,
+                tx_incarnation: tx.incarnation,
+            });
+        }
+        None
+    }
+
+    // Whether [tx_idx] is reserved for the sequential segment, which other
+    // workers must skip. Transactions shortly after the segment that have
+    // aborted before join it, as they likely continue the chain.
+    fn is_reserved(&self, tx_idx: TxIdx) -> bool {
+        if !self.has_segment.load(Ordering::Relaxed) {
+            return false;
+        }
+        let mut segment = self.segment.lock().unwrap();
+        let Some(segment) = segment.as_mut() else {
+            return false;
+        };
+        if tx_idx < segment.next_idx {
+            return false;
+        }
+        if tx_idx < segment.end_idx {
+            return true;
+        }
+        if tx_idx < segment.end_idx + SEGMENT_LENGTH
+            && index_mutex!(self.transactions_status, tx_idx).incarnation > 0
+        {
+            segment.end_idx = tx_idx + 1;
+            return true;
+        }
+        false
+    }
+
+    // Start a sequential segment from the transaction being executed if it
+    // keeps aborting and there is no segment yet.
+    fn try_start_segment(&self, tx_version: &TxVersion) {
+        if tx_version.tx_incarnation < SEGMENT_MIN_INCARNATION {
+            return;
+        }
+        let mut segment = self.segment.lock().unwrap();
+        if segment.is_none() {
+            *segment = Some(SequentialSegment {
+                next_idx: tx_version.tx_idx + 1,
+                end_idx: min(tx_version.tx_idx + 1 + SEGMENT_LENGTH, self.block_size),
+                executing_idx: Some(tx_version.tx_idx),
+            });
+            self.has_segment.store(true, Ordering::Relaxed);
+        }
+    }
+
+    // Get the next transaction of the sequential segment to execute, once
+    // the previous one has been executed.
+    fn next_segment_task(&self) -> Option<Task> {
+        if !self.has_segment.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut guard = self.segment.lock().unwrap();
+        let segment = guard.as_mut()?;
+        if segment.executing_idx.is_some() {
+            return None;
+        }
+        loop {
+            if segment.next_idx == segment.end_idx {
+                // Keep going while the transactions after the segment keep
+                // aborting as they were executed in parallel.
+                let keeps_aborting = segment.end_idx < self.block_size
+                    && index_mutex!(self.transactions_status, segment.end_idx).incarnation > 0;
+                if !keeps_aborting {
+                    *guard = None;
+                    self.has_segment.store(false, Ordering::Relaxed);
+                    return None;
+                }
+                segment.end_idx = min(segment.end_idx + SEGMENT_LENGTH, self.block_size);
+            }
+            let tx_idx = segment.next_idx;
             let mut tx = index_mutex!(self.transactions_status, tx_idx);
-            if tx.status == IncarnationStatus::ReadyToExecute {
-                tx.status = IncarnationStatus::Executing;
-                return Some(TxVersion {
-                    tx_idx,
-                    tx_incarnation: tx.incarnation,
-                });
+            match tx.status {
+                IncarnationStatus::ReadyToExecute => {
+                    tx.status = IncarnationStatus::Executing;
+                    segment.next_idx += 1;
+                    segment.executing_idx = Some(tx_idx);
+                    return Some(Task::Execution(TxVersion {
+                        tx_idx,
+                        tx_incarnation: tx.incarnation,
+                    }));
+                }
+                // Validate the transactions executed by other workers before
+                // moving on, as they likely read stale values.
+                IncarnationStatus::Executed => {
+                    segment.next_idx += 1;
+                    segment.executing_idx = Some(tx_idx);
+                    return Some(Task::Validation(TxVersion {
+                        tx_idx,
+                        tx_incarnation: tx.incarnation,
+                    }));
+                }
+                // Wait for the transactions being executed or aborted, as the
+                // next ones likely depend on them.
+                IncarnationStatus::Executing | IncarnationStatus::Aborting => return None,
+                IncarnationStatus::Validated => segment.next_idx += 1,
             }
         }
-        None
+    }
+
+    // Let the next transaction of the sequential segment execute after
+    // [tx_idx], or continue from [tx_idx] again if [revisit], like when it
+    // is blocked by a lower transaction or its validation may have failed.
+    fn leave_segment(&self, tx_idx: TxIdx, revisit: bool) {
+        if !self.has_segment.load(Ordering::Relaxed) {
+            return;
+        }
+        let mut guard = self.segment.lock().unwrap();
+        let Some(segment) = guard.as_mut() else {
+            return;
+        };
+        if segment.executing_idx != Some(tx_idx) {
+            return;
+        }
+        segment.executing_idx = None;
+        if revisit {
+            segment.next_idx = tx_idx;
+        }
     }
 
     pub(crate) fn next_task(&self) -> Option<Task> {
+        let task = self.find_task();
+        self.log_task(task)
+    }
+
+    fn find_task(&self) -> Option<Task> {
         while !self.aborted.load(Ordering::Relaxed) {
+            if let Some(task) = self.next_segment_task() {
+                return Some(task);
+            }
             let execution_idx = self.execution_idx.load(Ordering::Relaxed);
             let validation_idx = self.validation_idx.load(Ordering::Relaxed);
             if execution_idx >= self.block_size && validation_idx >= self.block_size {
+                // Have a worker wait for the reads of the parked transactions
+                // to be fetched before resuming them, as they would otherwise
+                // be parked again.
+                if !self.parked_transactions.lock().unwrap().is_empty()
+                    && !self.resuming_parked.swap(true, Ordering::Relaxed)
+                {
+                    return Some(Task::Idle(IdleTask::ResumeParked));
+                }
                 if self.num_validated.load(Ordering::Relaxed)
                     >= self.block_size - self.min_validation_idx.load(Ordering::Relaxed)
                 {
                     break;
                 }
+                if let Some(task) = self.next_idle_task() {
+                    return Some(Task::Idle(task));
+                }
                 thread::yield_now();
                 continue;
             }
@@ -123,14 +466,18 @@ impl Scheduler {
             if validation_idx < execution_idx {
                 let tx_idx = self.validation_idx.fetch_add(1, Ordering::Relaxed);
                 if tx_idx < self.block_size {
+                    let is_reserved = self.is_reserved(tx_idx);
                     let mut tx = index_mutex!(self.transactions_status, tx_idx);
                     // "Steal" execution job while holding the lock
-                    if tx.status == IncarnationStatus::ReadyToExecute {
+                    if tx.status == IncarnationStatus::ReadyToExecute && !is_reserved {
                         tx.status = IncarnationStatus::Executing;
-                        return Some(Task::Execution(TxVersion {
+                        let tx_version = TxVersion {
                             tx_idx,
                             tx_incarnation: tx.incarnation,
-                        }));
+                        };
+                        drop(tx);
+                        self.try_start_segment(&tx_version);
+                        return Some(Task::Execution(tx_version));
                     }
                     // Start a typical validation task
                     if matches!(
@@ -164,31 +511,100 @@ impl Scheduler {
         None
     }
 
+    // The next step of the idle workers' sweep of the block, if it is not
+    // done yet: prefetching the lazily updated locations, then prefetching for
+    // each transaction waiting to re-execute and validating each executed
+    // transaction that still awaits validation ahead of the validation tasks.
+    fn next_idle_task(&self) -> Option<IdleTask> {
+        let min_validation_idx = self.min_validation_idx.load(Ordering::Relaxed);
+        loop {
+            let idle_idx = self.idle_idx.fetch_add(1, Ordering::Relaxed);
+            let task = match idle_idx.checked_sub(1) {
+                None => IdleTask::PrefetchLazy,
+                Some(tx_idx) if tx_idx < self.block_size => {
+                    let tx = index_mutex!(self.transactions_status, tx_idx);
+                    match tx.status {
+                        IncarnationStatus::Aborting => IdleTask::PrefetchTx(tx_idx),
+                        // Validated transactions were either validated already
+                        // or executed lazily without needing validation.
+                        IncarnationStatus::Executed if tx_idx >= min_validation_idx => {
+                            IdleTask::Revalidation(TxVersion {
+                                tx_idx,
+                                tx_incarnation: tx.incarnation,
+                            })
+                        }
+                        _ => continue,
+                    }
+                }
+                Some(_) => return None,
+            };
+            self.idle_tasks.fetch_add(1, Ordering::Relaxed);
+            return Some(task);
+        }
+    }
+
     // Add [tx_idx] as a dependent of [blocking_tx_idx] so [tx_idx] is
     // re-executed when the next [blocking_tx_idx] incarnation is executed.
     // Return [false] if we encounter a race condition when [blocking_tx_idx]
     // gets re-executed before the dependency can be added.
     pub(crate) fn add_dependency(&self, tx_idx: TxIdx, blocking_tx_idx: TxIdx) -> bool {
-        // This is an important lock to prevent a race condition where the blocking
-        // transaction completes re-execution before this dependency can be added.
-        let blocking_tx = index_mutex!(self.transactions_status, blocking_tx_idx);
-        if matches!(
-            blocking_tx.status,
-            IncarnationStatus::Executed | IncarnationStatus::Validated
-        ) {
-            return false;
+        {
+            // This is an important lock to prevent a race condition where the blocking
+            // transaction completes re-execution before this dependency can be added.
+            let blocking_tx = index_mutex!(self.transactions_status, blocking_tx_idx);
+            if matches!(
+                blocking_tx.status,
+                IncarnationStatus::Executed | IncarnationStatus::Validated
+            ) {
+                return false;
+            }
+
+            let mut tx = index_mutex!(self.transactions_status, tx_idx);
+            debug_assert_eq!(tx.status, IncarnationStatus::Executing);
+            tx.status = IncarnationStatus::Aborting;
+
+            let mut blocking_dependents =
+                index_mutex!(self.transactions_dependents, blocking_tx_idx);
+            blocking_dependents.push(tx_idx);
+
+            self.log_event(ScheduleEvent::Dependency {
+                tx_idx,
+                blocking_tx_idx,
+            });
         }
 
-        let mut tx = index_mutex!(self.transactions_status, tx_idx);
-        debug_assert_eq!(tx.status, IncarnationStatus::Executing);
-        tx.status = IncarnationStatus::Aborting;
+        if let Some(dependencies) = &self.dependencies {
+            dependencies.lock().unwrap().push((tx_idx, blocking_tx_idx));
+        }
 
-        let mut blocking_dependents = index_mutex!(self.transactions_dependents, blocking_tx_idx);
-        blocking_dependents.push(tx_idx);
+        self.leave_segment(tx_idx, true);
 
         true
     }
 
+    // Park [tx_idx] as it reads from storage that is not ready yet, until
+    // workers run out of other tasks.
+    pub(crate) fn park(&self, tx_idx: TxIdx) {
+        {
+            let mut tx = index_mutex!(self.transactions_status, tx_idx);
+            debug_assert_eq!(tx.status, IncarnationStatus::Executing);
+            tx.status = IncarnationStatus::Aborting;
+        }
+        self.leave_segment(tx_idx, true);
+        self.parked_transactions.lock().unwrap().push(tx_idx);
+    }
+
+    // Resume the parked transactions, after the worker handed
+    // [IdleTask::ResumeParked] waited for their reads to be fetched.
+    pub(crate) fn resume_parked(&self) {
+        let parked_transactions = std::mem::take(&mut *self.parked_transactions.lock().unwrap());
+        for tx_idx in parked_transactions {
+            self.set_ready_status(tx_idx);
+            self.execution_idx.fetch_min(tx_idx, Ordering::Relaxed);
+        }
+        self.resuming_parked.store(false, Ordering::Relaxed);
+    }
+
     fn set_ready_status(&self, tx_idx: TxIdx) {
         let mut tx = index_mutex!(self.transactions_status, tx_idx);
         debug_assert_eq!(tx.status, IncarnationStatus::Aborting);
@@ -201,6 +617,14 @@ impl Scheduler {
         tx_version: TxVersion,
         flags: FinishExecFlags,
     ) -> Option<Task> {
+        // The writes are already in the multi-version memory for the next
+        // transaction of the segment to read.
+        self.leave_segment(tx_version.tx_idx, false);
+        // The execution may leave new work for the idle workers.
+        if self.idle_idx.load(Ordering::Relaxed) > 0 {
+            self.idle_idx.store(0, Ordering::Relaxed);
+        }
+
         let mut tx = index_mutex!(self.transactions_status, tx_version.tx_idx);
         debug_assert_eq!(tx.status, IncarnationStatus::Executing);
         debug_assert_eq!(tx.incarnation, tx_version.tx_incarnation);
@@ -241,7 +665,7 @@ impl Scheduler {
                 }
                 if flags.contains(FinishExecFlags::NeedValidation) {
                     tx.status = IncarnationStatus::Executed;
-                    return Some(Task::Validation(tx_version));
+                    return self.log_task(Some(Task::Validation(tx_version)));
                 }
                 tx.status = IncarnationStatus::Validated;
                 self.num_validated.fetch_add(1, Ordering::Relaxed);
@@ -275,6 +699,7 @@ impl Scheduler {
         );
         if aborting {
             tx.status = IncarnationStatus::Aborting;
+            self.validation_aborts.fetch_add(1, Ordering::Relaxed);
         }
         aborting
     }
@@ -283,12 +708,22 @@ impl Scheduler {
     // and the higher transactions for validation. The re-execution task is returned
     // for the aborted transaction.
     pub(crate) fn finish_validation(&self, tx_version: &TxVersion, aborted: bool) -> Option<Task> {
+        // Revisit the transaction in the segment, which is cheap if it was
+        // validated, as its validation may have failed and been aborted by
+        // another worker.
+        self.leave_segment(tx_version.tx_idx, true);
         if aborted {
+            // Logged once the writes of the aborted incarnation are estimates,
+            // as executions may still read them until then.
+            self.log_event(ScheduleEvent::ValidationAbort {
+                tx_idx: tx_version.tx_idx,
+                incarnation: tx_version.tx_incarnation,
+            });
             self.set_ready_status(tx_version.tx_idx);
             self.validation_idx
                 .fetch_min(tx_version.tx_idx + 1, Ordering::Relaxed);
             if self.execution_idx.load(Ordering::Relaxed) > tx_version.tx_idx {
-                return self.try_execute(tx_version.tx_idx).map(Task::Execution);
+                return self.log_task(self.try_execute(tx_version.tx_idx).map(Task::Execution));
             }
         } else {
             let mut tx = index_mutex!(self.transactions_status, tx_version.tx_idx);
@@ -300,3 +735,61 @@ impl Scheduler {
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run the scheduler's tasks of a block without dependencies on the calling
+    // thread until it has no tasks but idle ones, finishing the validations
+    // that [validate] accepts. Return the validated transactions.
+    fn run(scheduler: &Scheduler, validate: impl Fn(TxIdx) -> bool) -> Vec<TxIdx> {
+        let mut validated = Vec::new();
+        let mut next_task = scheduler.next_task();
+        while let Some(task) = next_task {
+            next_task = match task {
+                Task::Execution(tx_version) => {
+                    scheduler.finish_execution(tx_version, FinishExecFlags::empty())
+                }
+                Task::Validation(tx_version) if validate(tx_version.tx_idx) => {
+                    validated.push(tx_version.tx_idx);
+                    scheduler.finish_validation(&tx_version, false)
+                }
+                Task::Validation(_) => None,
+                Task::Idle(_) => break,
+            }
+            .or_else(|| scheduler.next_task());
+        }
+        validated
+    }
+
+    // Every executed transaction is validated before the block terminates.
+    #[test]
+    fn validate_every_transaction() {
+        let mut scheduler = Scheduler::default();
+        scheduler.reset(4);
+        let validated = run(&scheduler, |_| true);
+        assert_eq!(validated, vec![0, 1, 2, 3]);
+        assert_eq!(scheduler.num_validated.load(Ordering::Relaxed), 4);
+        assert!(scheduler.next_task().is_none());
+    }
+
+    // Idle workers only validate ahead the transactions that await validation,
+    // not those already validated.
+    #[test]
+    fn idle_revalidation() {
+        let mut scheduler = Scheduler::default();
+        scheduler.reset(3);
+        run(&scheduler, |tx_idx| tx_idx == 0);
+
+        assert!(matches!(
+            scheduler.next_idle_task(),
+            Some(IdleTask::Revalidation(TxVersion { tx_idx: 1, .. }))
+        ));
+        assert!(matches!(
+            scheduler.next_idle_task(),
+            Some(IdleTask::Revalidation(TxVersion { tx_idx: 2, .. }))
+        ));
+        assert!(scheduler.next_idle_task().is_none());
+    }
+}
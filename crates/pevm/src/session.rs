@@ -0,0 +1,561 @@
+use std::{
+    fmt::Debug,
+    num::NonZeroUsize,
+    sync::{Condvar, Mutex},
+    thread,
+};
+
+use alloy_rpc_types_eth::Receipt;
+use revm::context::BlockEnv;
+
+use crate::{
+    EvmStateTransitions, ExecutionStrategy, Pevm, PevmError, PevmMode, PevmTxExecutionResult,
+    Storage, TxIdx,
+    chain::PevmChain,
+    mv_memory::MvMemory,
+    pevm::{
+        AbortReason, ExclusiveResult, WorkerContext, execute_revm_sequential_excluding,
+        remove_excluded_results,
+    },
+    scheduler::Scheduler,
+    storage::StateOverlay,
+    vm::ExecutionError,
+};
+
+/// The finalized receipt of a pushed transaction, or the error that excluded
+/// it from the block.
+pub type PevmSessionReceipt = (TxIdx, Result<Receipt, ExecutionError>);
+
+/// An in-flight block that transactions are continuously appended to, like
+/// by a sequencer that needs receipts before the block closes. It is handed
+/// to the producer of [`Pevm::session`], which runs on its own thread.
+///
+/// Pushed transactions are executed on the persistent workers while more are
+/// pushed: each batch takes all the transactions pushed while the previous
+/// one executed, and reads the final writes of the previous ones in the
+/// multi-version memory without re-executing them. If a batch needs
+/// sequential execution, like when two memory locations share a hash, the
+/// transactions of the session are re-executed sequentially, and the next
+/// batches continue sequentially. Invalid transactions are excluded like in
+/// [`PevmMode::Build`], or [`PevmMode::Test`] if the [`Pevm`] is in test mode.
+pub struct PevmSession<T> {
+    state: Mutex<SessionState<T>>,
+    // Notifies the coordinator of pushed transactions and of the end of the
+    // session.
+    pushed: Condvar,
+    // Notifies the producer of finalized receipts.
+    finalized: Condvar,
+}
+
+struct SessionState<T> {
+    // The transactions pushed since the last batch.
+    pending: Vec<T>,
+    // The finalized receipts not returned to the producer yet.
+    receipts: Vec<PevmSessionReceipt>,
+    num_pushed: usize,
+    num_finalized: usize,
+    // Whether the producer is done pushing transactions.
+    closed: bool,
+    // Whether the block failed, so pushed transactions are dropped.
+    failed: bool,
+}
+
+// [PevmChain::EvmTx] is not necessarily [Debug].
+impl<T> Debug for PevmSession<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let state = self.state.lock().unwrap();
+        f.debug_struct("PevmSession")
+            .field("num_pushed", &state.num_pushed)
+            .field("num_finalized", &state.num_finalized)
+            .field("closed", &state.closed)
+            .field("failed", &state.failed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T> PevmSession<T> {
+    const fn new() -> Self {
+        Self {
+            state: Mutex::new(SessionState {
+                pending: Vec::new(),
+                receipts: Vec::new(),
+                num_pushed: 0,
+                num_finalized: 0,
+                closed: false,
+                failed: false,
+            }),
+            pushed: Condvar::new(),
+            finalized: Condvar::new(),
+        }
+    }
+
+    /// Append a transaction to the block, to be executed once the workers are
+    /// done with the previous batch, and return the receipts finalized since
+    /// the last call. Transactions pushed after the block failed are dropped,
+    /// as [`Pevm::session`] returns its error.
+    pub fn push_tx(&self, tx: T) -> Vec<PevmSessionReceipt> {
+        let mut state = self.state.lock().unwrap();
+        if !state.failed {
+            state.pending.push(tx);
+            state.num_pushed += 1;
+            self.pushed.notify_one();
+        }
+        std::mem::take(&mut state.receipts)
+    }
+
+    /// Wait for all pushed transactions to be executed, returning the
+    /// receipts finalized since the last call.
+    pub fn flush(&self) -> Vec<PevmSessionReceipt> {
+        let mut state = self
+            .finalized
+            .wait_while(self.state.lock().unwrap(), |state| {
+                !state.failed && state.num_finalized < state.num_pushed
+            })
+            .unwrap();
+        std::mem::take(&mut state.receipts)
+    }
+
+    // Wait for the transactions pushed since the last batch, or return [None]
+    // once the producer is done and all of them were taken.
+    fn next_batch(&self) -> Option<Vec<T>> {
+        let mut state = self
+            .pushed
+            .wait_while(self.state.lock().unwrap(), |state| {
+                state.pending.is_empty() && !state.closed
+            })
+            .unwrap();
+        if state.pending.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut state.pending))
+    }
+
+    fn finalize(&self, receipts: Vec<PevmSessionReceipt>) {
+        let mut state = self.state.lock().unwrap();
+        state.num_finalized += receipts.len();
+        state.receipts.extend(receipts);
+        self.finalized.notify_all();
+    }
+
+    // Stop waiting for the producer.
+    fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.pushed.notify_one();
+    }
+
+    // Stop executing transactions, for the producer to stop waiting for them.
+    fn fail(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.failed = true;
+        state.pending.clear();
+        self.finalized.notify_all();
+    }
+}
+
+// Call the function when dropped, including when unwinding from a panic.
+struct OnDrop<F: FnMut()>(F);
+
+impl<F: FnMut()> Drop for OnDrop<F> {
+    fn drop(&mut self) {
+        (self.0)();
+    }
+}
+
+// Run a session whose [producer] pushes transactions from a scoped thread,
+// while the calling thread executes them in batches on the workers of [pevm].
+// Return the output of [producer] and the results of the block.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_session<S, C, R>(
+    pevm: &mut Pevm,
+    chain: &C,
+    storage: &S,
+    spec_id: C::EvmSpecId,
+    block_env: BlockEnv,
+    mode: PevmMode,
+    concurrency_level: NonZeroUsize,
+    producer: impl FnOnce(&PevmSession<C::EvmTx>) -> R + Send,
+) -> (R, ExclusiveResult<C>)
+where
+    C: PevmChain + Send + Sync,
+    S: Storage + Send + Sync + Debug,
+    R: Send,
+{
+    let session = PevmSession::new();
+    thread::scope(|scope| {
+        let producer = scope.spawn(|| {
+            let _close = OnDrop(|| session.close());
+            producer(&session)
+        });
+        let result = {
+            let _fail = OnDrop(|| {
+                if thread::panicking() {
+                    session.fail();
+                }
+            });
+            let mut block = SessionBlock::new(pevm, chain, storage, spec_id, block_env, mode);
+            loop {
+                let Some(batch) = session.next_batch() else {
+                    break block.finish();
+                };
+                match block.execute_batch(batch, concurrency_level) {
+                    Ok(receipts) => session.finalize(receipts),
+                    Err(err) => {
+                        session.fail();
+                        break Err(err);
+                    }
+                }
+            }
+        };
+        let output = producer
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+        (output, result)
+    })
+}
+
+// A block executed in batches of transactions appended to it, keeping the
+// multi-version memory and the scheduler of the previous batches.
+pub(crate) struct SessionBlock<'a, S: Storage, C: PevmChain> {
+    pevm: &'a mut Pevm,
+    chain: &'a C,
+    storage: &'a S,
+    spec_id: C::EvmSpecId,
+    block_env: BlockEnv,
+    mode: PevmMode,
+    txs: Vec<C::EvmTx>,
+    mv_memory: MvMemory,
+    scheduler: Scheduler,
+    // The results of the executed transactions, with placeholders for the
+    // excluded ones until [Self::finish].
+    results: Vec<PevmTxExecutionResult>,
+    excluded_txs: Vec<(TxIdx, ExecutionError)>,
+    cumulative_gas_used: u64,
+    // The state after the executed transactions, once the block has fallen
+    // back to sequential execution.
+    sequential_state: Option<StateOverlay<'a, S>>,
+}
+
+impl<'a, S, C> SessionBlock<'a, S, C>
+where
+    C: PevmChain + Send + Sync,
+    S: Storage + Send + Sync + Debug,
+{
+    pub(crate) fn new(
+        pevm: &'a mut Pevm,
+        chain: &'a C,
+        storage: &'a S,
+        spec_id: C::EvmSpecId,
+        block_env: BlockEnv,
+        mode: PevmMode,
+    ) -> Self {
+        let (mut mv_memory, mut scheduler) = pevm.take_recycled();
+        // Only register the lazy beneficiary, as there are no transactions yet.
+        chain.prepare_mv_memory(&mut mv_memory, &block_env, &[]);
+        scheduler.reset(0);
+        Self {
+            pevm,
+            chain,
+            storage,
+            spec_id,
+            block_env,
+            mode,
+            txs: Vec::new(),
+            mv_memory,
+            scheduler,
+            results: Vec::new(),
+            excluded_txs: Vec::new(),
+            cumulative_gas_used: 0,
+            sequential_state: None,
+        }
+    }
+
+    // Decide how to execute [batch] with the execution policy of the [Pevm].
+    pub(crate) fn decide_strategy(
+        &self,
+        batch: &[C::EvmTx],
+        concurrency_level: NonZeroUsize,
+    ) -> ExecutionStrategy {
+        self.pevm
+            .decide_strategy(self.chain, batch, None, concurrency_level)
+    }
+
+    // Append [batch] to the block and execute it with [concurrency_level]
+    // workers, returning the receipts of its transactions.
+    pub(crate) fn execute_batch(
+        &mut self,
+        batch: Vec<C::EvmTx>,
+        concurrency_level: NonZeroUsize,
+    ) -> Result<Vec<PevmSessionReceipt>, PevmError<C>> {
+        let first_pending = self.txs.len();
+        self.txs.extend(batch);
+        if self.txs.len() == first_pending {
+            return Ok(Vec::new());
+        }
+
+        if self.sequential_state.is_some() {
+            self.execute_sequential(first_pending)?;
+        } else {
+            self.execute_parallel(first_pending, concurrency_level)?;
+        }
+
+        let first_excluded = self
+            .excluded_txs
+            .partition_point(|(tx_idx, _)| *tx_idx < first_pending);
+        let mut excluded_txs = self.excluded_txs[first_excluded..].iter().peekable();
+        Ok((first_pending..self.txs.len())
+            .map(
+                |tx_idx| match excluded_txs.next_if(|(idx, _)| *idx == tx_idx) {
+                    Some((_, err)) => (tx_idx, Err(err.clone())),
+                    None => (tx_idx, Ok(self.results[tx_idx].receipt.clone())),
+                },
+            )
+            .collect())
+    }
+
+    // Append the result of the next transaction, whose receipt has the gas
+    // used by the transaction alone, or the error that excluded it.
+    fn push_result(&mut self, result: Result<PevmTxExecutionResult, ExecutionError>) {
+        match result {
+            Ok(mut execution_result) => {
+                self.cumulative_gas_used = self
+                    .cumulative_gas_used
+                    .saturating_add(execution_result.receipt.cumulative_gas_used);
+                execution_result.receipt.cumulative_gas_used = self.cumulative_gas_used;
+                self.results.push(execution_result);
+            }
+            Err(err) => {
+                // The lazy evaluation never touches excluded transactions as
+                // they have no writes.
+                self.excluded_txs.push((self.results.len(), err));
+                self.results.push(PevmTxExecutionResult {
+                    receipt: Default::default(),
+                    state: EvmStateTransitions::default(),
+                });
+            }
+        }
+    }
+
+    fn execute_parallel(
+        &mut self,
+        first_pending: TxIdx,
+        concurrency_level: NonZeroUsize,
+    ) -> Result<(), PevmError<C>> {
+        // No workers are running between batches, so the previous transactions
+        // are final and the new ones resume scheduling from them.
+        let additional = self.txs.len() - first_pending;
+        self.scheduler.extend(additional);
+        self.mv_memory.extend(additional);
+
+        let ctx = WorkerContext {
+            chain: self.chain,
+            storage: self.storage,
+            spec_id: self.spec_id,
+            block_env: &self.block_env,
+            txs: &self.txs,
+            mv_memory: &self.mv_memory,
+            mode: self.mode,
+        };
+        match self.pevm.run_workers(
+            self.txs.len(),
+            &self.mv_memory,
+            &self.scheduler,
+            concurrency_level,
+            || ctx.new_vm(),
+            None,
+        ) {
+            // The multi-version memory cannot resume from a partially executed
+            // batch, so the block continues sequentially.
+            Some(AbortReason::FallbackToSequential(_)) => {
+                return self.fall_back_to_sequential();
+            }
+            Some(AbortReason::ExecutionError(err)) => {
+                return Err(PevmError::ExecutionError(err));
+            }
+            None => {}
+        }
+
+        for tx_idx in first_pending..self.txs.len() {
+            let result = self
+                .pevm
+                .take_execution_result(tx_idx)
+                .ok_or(PevmError::UnreachableError)?;
+            self.push_result(result);
+        }
+        Ok(())
+    }
+
+    // Re-execute the transactions of the block sequentially, which gives the
+    // same results for those executed in the previous batches.
+    fn fall_back_to_sequential(&mut self) -> Result<(), PevmError<C>> {
+        self.results.clear();
+        self.excluded_txs.clear();
+        self.cumulative_gas_used = 0;
+        self.sequential_state = Some(StateOverlay::new(self.storage));
+        self.execute_sequential(0)
+    }
+
+    // Execute the transactions from [first_pending] sequentially on top of
+    // the state of the previous ones.
+    fn execute_sequential(&mut self, first_pending: TxIdx) -> Result<(), PevmError<C>> {
+        let state = self.sequential_state.as_mut().unwrap();
+        let (results, excluded_txs) = execute_revm_sequential_excluding(
+            self.chain,
+            &*state,
+            self.spec_id,
+            self.block_env.clone(),
+            self.txs[first_pending..].to_vec(),
+            self.mode,
+        )?;
+        for tx_result in &results {
+            state.apply(&tx_result.state);
+        }
+
+        let mut results = results.into_iter();
+        let mut excluded_txs = excluded_txs.into_iter().peekable();
+        let mut batch_gas_used = 0;
+        for batch_idx in 0..self.txs.len() - first_pending {
+            let result = match excluded_txs.next_if(|(idx, _)| *idx == batch_idx) {
+                Some((_, err)) => Err(err),
+                None => {
+                    let mut tx_result = results.next().ok_or(PevmError::UnreachableError)?;
+                    let cumulative_gas_used = tx_result.receipt.cumulative_gas_used;
+                    tx_result.receipt.cumulative_gas_used = cumulative_gas_used - batch_gas_used;
+                    batch_gas_used = cumulative_gas_used;
+                    Ok(tx_result)
+                }
+            };
+            self.push_result(result);
+        }
+        Ok(())
+    }
+
+    // Close the block, returning the execution results of the included
+    // transactions with the fully evaluated lazy accounts, and the indices of
+    // the excluded ones with their errors.
+    pub(crate) fn finish(mut self) -> ExclusiveResult<C> {
+        if self.sequential_state.is_none() {
+            let ctx = WorkerContext {
+                chain: self.chain,
+                storage: self.storage,
+                spec_id: self.spec_id,
+                block_env: &self.block_env,
+                txs: &self.txs,
+                mv_memory: &self.mv_memory,
+                mode: self.mode,
+            };
+            ctx.evaluate_lazy_addresses(&mut self.results)?;
+        }
+
+        let Self {
+            pevm,
+            mv_memory,
+            scheduler,
+            mut results,
+            excluded_txs,
+            ..
+        } = self;
+        pevm.recycle((mv_memory, scheduler));
+
+        remove_excluded_results(&mut results, &excluded_txs);
+        Ok((results, excluded_txs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::{
+        context::{TransactTo, TxEnv},
+        primitives::{Address, U256, alloy_primitives::U160},
+    };
+
+    use super::*;
+    use crate::{EvmAccount, InMemoryStorage, chain::PevmEthereum, execute_revm_sequential};
+
+    #[test]
+    fn sequential_fallback() {
+        let chain = PevmEthereum::mainnet();
+        let account = EvmAccount {
+            balance: U256::from(1_000_000_000),
+            nonce: 1,
+            ..EvmAccount::default()
+        };
+        let storage = InMemoryStorage::new(
+            (1..=10)
+                .map(|i| (Address::from(U160::from(i)), account.clone()))
+                .collect(),
+            Default::default(),
+            Default::default(),
+        );
+        // Each sender transfers twice to the same recipient, across batches,
+        // and the last transaction is excluded as its sender is unfunded.
+        let txs: Vec<TxEnv> = (0..21)
+            .map(|i| TxEnv {
+                caller: Address::from(U160::from(if i == 20 { 99 } else { i % 10 + 1 })),
+                nonce: if i == 20 { 0 } else { i / 10 + 1 },
+                kind: TransactTo::Call(Address::from(U160::from(100))),
+                value: U256::from(1_000),
+                gas_limit: 21_000,
+                gas_price: 1,
+                ..TxEnv::default()
+            })
+            .collect();
+
+        let mut pevm = Pevm::default();
+        let mut block = SessionBlock::new(
+            &mut pevm,
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            PevmMode::Build,
+        );
+        let concurrency_level = NonZeroUsize::new(4).unwrap();
+        let parallel_receipts = block
+            .execute_batch(txs[..7].to_vec(), concurrency_level)
+            .unwrap();
+        // Like a batch aborting to sequential execution.
+        block.fall_back_to_sequential().unwrap();
+        assert_eq!(
+            block
+                .results
+                .iter()
+                .map(|tx_result| Ok(tx_result.receipt.clone()))
+                .collect::<Vec<_>>(),
+            parallel_receipts
+                .into_iter()
+                .map(|(_, receipt)| receipt)
+                .collect::<Vec<_>>()
+        );
+        let receipts = block
+            .execute_batch(txs[7..].to_vec(), concurrency_level)
+            .unwrap();
+        assert_eq!(
+            receipts
+                .iter()
+                .map(|(tx_idx, _)| *tx_idx)
+                .collect::<Vec<_>>(),
+            (7..21).collect::<Vec<_>>()
+        );
+        assert!(receipts[13].1.is_err());
+        let gas_used = block.cumulative_gas_used;
+        let (tx_results, excluded_txs) = block.finish().unwrap();
+
+        assert_eq!(
+            excluded_txs
+                .iter()
+                .map(|(tx_idx, _)| *tx_idx)
+                .collect::<Vec<_>>(),
+            vec![20]
+        );
+        let sequential_results = execute_revm_sequential(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            txs[..20].to_vec(),
+        )
+        .unwrap();
+        assert_eq!(tx_results, sequential_results);
+        assert_eq!(gas_used, 20 * 21_000);
+    }
+}
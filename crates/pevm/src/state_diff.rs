@@ -0,0 +1,139 @@
+use alloy_primitives::{Address, B256, U256};
+use hashbrown::{HashMap, hash_map::Entry};
+use rustc_hash::FxBuildHasher;
+
+use crate::{AccountBasic, BuildSuffixHasher, Bytecodes, EvmStateTransitions, Storage};
+
+/// The original and new values of a storage slot changed in a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageSlotDiff {
+    /// The value before the block.
+    pub original: U256,
+    /// The value after the block.
+    pub present: U256,
+}
+
+/// The changes of an account in a block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountDiff {
+    /// The account before the block, [None] if it did not exist.
+    pub original: Option<AccountBasic>,
+    /// The account after the block, [None] if it was destroyed.
+    pub present: Option<AccountBasic>,
+    /// The code hash before the block.
+    pub original_code_hash: Option<B256>,
+    /// The code hash after the block.
+    pub code_hash: Option<B256>,
+    /// Whether the account was destroyed at some point in the block, so its
+    /// storage before the block must be wiped before applying [`Self::storage`].
+    pub destroyed: bool,
+    /// The changed storage slots.
+    pub storage: HashMap<U256, StorageSlotDiff, FxBuildHasher>,
+}
+
+/// The merged state changes of a whole block, from the pre-block system calls
+/// to the end-of-block balance increments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BlockStateDiff {
+    /// The changed accounts.
+    pub accounts: HashMap<Address, AccountDiff, BuildSuffixHasher>,
+    /// The bytecodes deployed in the block.
+    pub new_bytecodes: Bytecodes,
+}
+
+impl BlockStateDiff {
+    /// Merge the state transitions of a block, in execution order, on top of
+    /// the [storage] before the block.
+    pub fn from_transitions<'a, S: Storage>(
+        storage: &S,
+        transitions: impl IntoIterator<Item = &'a EvmStateTransitions>,
+    ) -> Result<Self, S::Error> {
+        let mut diff = Self::default();
+        for transitions in transitions {
+            for (address, account) in transitions {
+                let account_diff = match diff.accounts.entry(*address) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        let original = storage.basic(address)?;
+                        let original_code_hash = storage.code_hash(address)?;
+                        entry.insert(AccountDiff {
+                            present: original.clone(),
+                            original,
+                            original_code_hash,
+                            code_hash: original_code_hash,
+                            destroyed: false,
+                            storage: HashMap::default(),
+                        })
+                    }
+                };
+                let Some(account) = account else {
+                    // Clearing an account that never existed, like touched empty
+                    // accounts post EIP-161, is a no-op.
+                    account_diff.destroyed |= account_diff.present.is_some();
+                    account_diff.present = None;
+                    account_diff.code_hash = None;
+                    account_diff.storage.clear();
+                    continue;
+                };
+                account_diff.present = Some(AccountBasic {
+                    balance: account.balance,
+                    nonce: account.nonce,
+                });
+                account_diff.code_hash = account.code_hash;
+                if let (Some(code_hash), Some(code)) = (account.code_hash, &account.code)
+                    && account_diff.original_code_hash != Some(code_hash)
+                {
+                    diff.new_bytecodes
+                        .entry(code_hash)
+                        .or_insert_with(|| code.clone());
+                }
+                for (slot, value) in &account.storage {
+                    let slot_diff = match account_diff.storage.entry(*slot) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => {
+                            // New accounts have no storage to read.
+                            let original = if account_diff.original.is_some() {
+                                storage.storage(address, slot)?
+                            } else {
+                                U256::ZERO
+                            };
+                            entry.insert(StorageSlotDiff {
+                                original,
+                                present: original,
+                            })
+                        }
+                    };
+                    slot_diff.present = *value;
+                }
+            }
+        }
+
+        // Drop the reads and the changes that have been reverted within the block.
+        for account_diff in diff.accounts.values_mut() {
+            let destroyed = account_diff.destroyed;
+            account_diff.storage.retain(|_, slot| {
+                if destroyed {
+                    !slot.present.is_zero()
+                } else {
+                    slot.original != slot.present
+                }
+            });
+        }
+        diff.accounts.retain(|_, account_diff| {
+            account_diff.destroyed
+                || account_diff.original != account_diff.present
+                || account_diff.original_code_hash != account_diff.code_hash
+                || !account_diff.storage.is_empty()
+        });
+
+        Ok(diff)
+    }
+
+    /// Get the accounts that were destroyed at some point in the block.
+    pub fn destroyed_accounts(&self) -> impl Iterator<Item = &Address> {
+        self.accounts
+            .iter()
+            .filter(|(_, account_diff)| account_diff.destroyed)
+            .map(|(address, _)| address)
+    }
+}
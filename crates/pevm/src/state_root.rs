@@ -0,0 +1,133 @@
+use alloy_primitives::{Address, B256, U256, keccak256};
+use alloy_trie::{EMPTY_ROOT_HASH, HashBuilder, KECCAK_EMPTY, Nibbles, TrieAccount};
+use hashbrown::{HashMap, HashSet};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use rustc_hash::FxBuildHasher;
+
+use crate::{AccountDiff, BlockStateDiff, BuildSuffixHasher, Storage};
+
+/// A storage that provides the full state before a block, to compute the
+/// state root after it.
+pub trait StateRootProvider: Storage {
+    /// Get the addresses of all accounts in the state.
+    fn addresses(&self) -> Result<Vec<Address>, Self::Error>;
+
+    /// Get all the storage slots of an account.
+    fn storage_slots(&self, address: &Address) -> Result<Vec<(U256, U256)>, Self::Error>;
+
+    /// Get the storage root of an account. Providers that store tries should
+    /// return their cached roots instead of recomputing them from all slots.
+    fn storage_root(&self, address: &Address) -> Result<B256, Self::Error> {
+        Ok(calculate_storage_root(self.storage_slots(address)?))
+    }
+}
+
+/// Calculate the state root after applying a block's state diff on top of the
+/// full state before the block. Accounts are processed in parallel, and only
+/// the storage tries of accounts with changed storage are recomputed.
+///
+/// This is not incremental: without the intermediate nodes of the previous
+/// account trie, every account returned by [`StateRootProvider::addresses`]
+/// is read and hashed again for every block. The cost grows with the whole
+/// state rather than the diff, which suits tests and small chains, not
+/// mainnet-sized states.
+pub fn calculate_state_root<P>(provider: &P, diff: &BlockStateDiff) -> Result<B256, P::Error>
+where
+    P: StateRootProvider + Sync,
+    P::Error: Send,
+{
+    let mut addresses: HashSet<Address, BuildSuffixHasher> =
+        provider.addresses()?.into_iter().collect();
+    addresses.extend(diff.accounts.keys().copied());
+
+    let mut leaves = Vec::from_iter(addresses)
+        .into_par_iter()
+        .filter_map(|address| {
+            let account = match diff.accounts.get(&address) {
+                Some(account_diff) => trie_account_from_diff(provider, &address, account_diff),
+                None => trie_account_from_storage(provider, &address),
+            };
+            account
+                .map(|account| account.map(|account| (keccak256(address), account)))
+                .transpose()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    leaves.sort_unstable_by_key(|(hashed_address, _)| *hashed_address);
+
+    let mut hash_builder = HashBuilder::default();
+    let mut account_rlp = Vec::new();
+    for (hashed_address, account) in leaves {
+        account_rlp.clear();
+        alloy_rlp::Encodable::encode(&account, &mut account_rlp);
+        hash_builder.add_leaf(Nibbles::unpack(hashed_address), &account_rlp);
+    }
+    Ok(hash_builder.root())
+}
+
+fn trie_account_from_storage<P: StateRootProvider>(
+    provider: &P,
+    address: &Address,
+) -> Result<Option<TrieAccount>, P::Error> {
+    let Some(basic) = provider.basic(address)? else {
+        return Ok(None);
+    };
+    Ok(Some(TrieAccount {
+        nonce: basic.nonce,
+        balance: basic.balance,
+        storage_root: provider.storage_root(address)?,
+        code_hash: provider.code_hash(address)?.unwrap_or(KECCAK_EMPTY),
+    }))
+}
+
+fn trie_account_from_diff<P: StateRootProvider>(
+    provider: &P,
+    address: &Address,
+    account_diff: &AccountDiff,
+) -> Result<Option<TrieAccount>, P::Error> {
+    let Some(present) = &account_diff.present else {
+        return Ok(None);
+    };
+    let had_storage = account_diff.original.is_some() && !account_diff.destroyed;
+    let storage_root = if account_diff.storage.is_empty() {
+        if had_storage {
+            provider.storage_root(address)?
+        } else {
+            EMPTY_ROOT_HASH
+        }
+    } else {
+        let mut storage: HashMap<U256, U256, FxBuildHasher> = if had_storage {
+            provider.storage_slots(address)?.into_iter().collect()
+        } else {
+            Default::default()
+        };
+        for (slot, slot_diff) in &account_diff.storage {
+            storage.insert(*slot, slot_diff.present);
+        }
+        calculate_storage_root(storage)
+    };
+    Ok(Some(TrieAccount {
+        nonce: present.nonce,
+        balance: present.balance,
+        storage_root,
+        code_hash: account_diff.code_hash.unwrap_or(KECCAK_EMPTY),
+    }))
+}
+
+// Zero slots are not stored in the trie.
+fn calculate_storage_root(storage: impl IntoIterator<Item = (U256, U256)>) -> B256 {
+    let mut leaves = storage
+        .into_iter()
+        .filter(|(_, value)| !value.is_zero())
+        .map(|(slot, value)| (keccak256(B256::from(slot)), value))
+        .collect::<Vec<_>>();
+    leaves.sort_unstable_by_key(|(hashed_slot, _)| *hashed_slot);
+
+    let mut hash_builder = HashBuilder::default();
+    for (hashed_slot, value) in leaves {
+        hash_builder.add_leaf(
+            Nibbles::unpack(hashed_slot),
+            alloy_rlp::encode_fixed_size(&value).as_ref(),
+        );
+    }
+    hash_builder.root()
+}
@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+/// The reason a block executed in parallel fell back to sequential execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FallbackReason {
+    /// Two different memory locations have the same hash, so the
+    /// multi-version memory cannot tell their values apart.
+    LocationCollision,
+    /// A transaction kept blocking on transactions that had already been
+    /// re-executed, like on a nonce gap that no transaction fills.
+    BlockingRetries,
+}
+
+/// Statistics of how a block was executed in parallel, collected when
+/// [`crate::Pevm::with_stats`] is set and taken with [`crate::Pevm::take_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PevmStats {
+    /// The number of transactions in the block.
+    pub block_size: usize,
+    /// The number of workers that executed the block.
+    pub concurrency_level: usize,
+    /// The number of incarnations that each transaction was executed with,
+    /// including those that blocked on lower transactions.
+    pub incarnations: Vec<usize>,
+    /// The number of validations that aborted an executed incarnation.
+    pub validation_aborts: usize,
+    /// The number of tasks handed to idle workers instead of spinning, like
+    /// prefetching state and revalidating transactions ahead.
+    pub idle_tasks: usize,
+    /// The transactions that blocked on lower transactions, each with the
+    /// transaction it waited for, in the order they blocked.
+    pub dependencies: Vec<(usize, usize)>,
+    /// The number of memory locations in the multi-version memory.
+    pub memory_locations: usize,
+    /// The reason the block fell back to sequential execution, if it did.
+    pub fallback: Option<FallbackReason>,
+    /// The time spent preparing the block, like building its multi-version
+    /// memory and scheduler.
+    pub preparation_time: Duration,
+    /// The time spent executing and validating the block in parallel.
+    pub parallel_time: Duration,
+    /// The time spent fully evaluating the lazy addresses and storage slots.
+    pub lazy_evaluation_time: Duration,
+}
+
+impl PevmStats {
+    /// The total number of incarnations beyond the first of each transaction.
+    pub fn re_executions(&self) -> usize {
+        self.incarnations
+            .iter()
+            .map(|incarnations| incarnations.saturating_sub(1))
+            .sum()
+    }
+}
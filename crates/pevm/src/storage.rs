@@ -1,5 +1,6 @@
 use std::error::Error as StdError;
 use std::fmt::Debug;
+use std::time::Duration;
 
 use alloy_primitives::{Address, B256, Bytes, U256};
 use hashbrown::HashMap;
@@ -150,6 +151,61 @@ pub trait Storage {
 
     /// Get block hash by block number.
     fn block_hash(&self, number: &u64) -> Result<B256, Self::Error>;
+
+    /// Whether the basic information and code hash of an account, and its
+    /// storage value at [index] if given, can be read without blocking on I/O.
+    /// Storages that fetch state asynchronously can start fetching them here
+    /// and return [false] for Pevm to park the reading transaction and execute
+    /// others in the meantime. Always ready by default.
+    fn is_ready(&self, _address: &Address, _index: Option<&U256>) -> bool {
+        true
+    }
+
+    /// Block until the reads started by [`Self::is_ready`] are fetched, or
+    /// [timeout] passes, before Pevm resumes the transactions parked on them.
+    /// Returns immediately by default.
+    fn wait_for_fetches(&self, _timeout: Duration) {}
+}
+
+/// An asynchronous interface to provide chain state, for backends bound by
+/// disk or network I/O. Wrap it in a `PrefetchStorage` to execute with Pevm.
+pub trait AsyncStorage {
+    /// Errors when querying data from storage.
+    type Error: StdError + DBErrorMarker;
+
+    /// Get basic account information.
+    fn basic(
+        &self,
+        address: &Address,
+    ) -> impl Future<Output = Result<Option<AccountBasic>, Self::Error>> + Send;
+
+    /// Get the code of an account.
+    fn code_hash(
+        &self,
+        address: &Address,
+    ) -> impl Future<Output = Result<Option<B256>, Self::Error>> + Send;
+
+    /// Get account code by its hash.
+    fn code_by_hash(
+        &self,
+        code_hash: &B256,
+    ) -> impl Future<Output = Result<Option<EvmCode>, Self::Error>> + Send;
+
+    /// Get if the account already has storage (to support EIP-7610).
+    fn has_storage(
+        &self,
+        address: &Address,
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send;
+
+    /// Get storage value of address at index.
+    fn storage(
+        &self,
+        address: &Address,
+        index: &U256,
+    ) -> impl Future<Output = Result<U256, Self::Error>> + Send;
+
+    /// Get block hash by block number.
+    fn block_hash(&self, number: &u64) -> impl Future<Output = Result<B256, Self::Error>> + Send;
 }
 
 /// A Storage wrapper that implements REVM's [`DatabaseRef`] for ease of
@@ -197,8 +253,16 @@ impl<S: Storage + Debug> DatabaseRef for StorageWrapper<'_, S> {
     }
 }
 
+mod cached;
+pub(crate) use cached::CachedStorage;
 mod in_memory;
 pub use in_memory::InMemoryStorage;
+mod overlay;
+pub(crate) use overlay::StateOverlay;
+#[cfg(feature = "async-storage")]
+mod prefetch;
+#[cfg(feature = "async-storage")]
+pub use prefetch::PrefetchStorage;
 #[cfg(feature = "rpc-storage")]
 mod rpc;
 #[cfg(feature = "rpc-storage")]
@@ -0,0 +1,105 @@
+use std::time::Duration;
+
+use alloy_primitives::{Address, B256, U256};
+use dashmap::DashMap;
+
+use crate::{AccountBasic, BuildSuffixHasher, EvmCode, Storage};
+
+// The cached basic information and code hash of an account.
+type CachedAccount = (Option<AccountBasic>, Option<B256>);
+
+// A shared cache in front of a storage, for prefetching the state that
+// transactions are likely to read while workers execute them.
+#[derive(Debug)]
+pub(crate) struct CachedStorage<'a, S: Storage> {
+    storage: &'a S,
+    accounts: DashMap<Address, CachedAccount, BuildSuffixHasher>,
+    slots: DashMap<(Address, U256), U256>,
+    bytecodes: DashMap<B256, Option<EvmCode>, BuildSuffixHasher>,
+}
+
+impl<'a, S: Storage> CachedStorage<'a, S> {
+    pub(crate) fn new(storage: &'a S) -> Self {
+        Self {
+            storage,
+            accounts: DashMap::default(),
+            slots: DashMap::default(),
+            bytecodes: DashMap::default(),
+        }
+    }
+
+    fn account(&self, address: &Address) -> Result<CachedAccount, S::Error> {
+        if let Some(account) = self.accounts.get(address) {
+            return Ok(account.clone());
+        }
+        let account = (
+            self.storage.basic(address)?,
+            self.storage.code_hash(address)?,
+        );
+        self.accounts.insert(*address, account.clone());
+        Ok(account)
+    }
+
+    // Fetch an account, its code, and its storage value at [index] if given
+    // into the cache. Errors are left to the execution that reads them.
+    pub(crate) fn prefetch(&self, address: &Address, index: Option<&U256>) {
+        if let Ok((_, Some(code_hash))) = self.account(address) {
+            let _ = self.code_by_hash(&code_hash);
+        }
+        if let Some(index) = index {
+            let _ = self.storage(address, index);
+        }
+    }
+}
+
+impl<S: Storage> Storage for CachedStorage<'_, S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        self.account(address).map(|(basic, _)| basic)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.account(address).map(|(_, code_hash)| code_hash)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        if let Some(code) = self.bytecodes.get(code_hash) {
+            return Ok(code.clone());
+        }
+        let code = self.storage.code_by_hash(code_hash)?;
+        self.bytecodes.insert(*code_hash, code.clone());
+        Ok(code)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.storage.has_storage(address)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        let key = (*address, *index);
+        if let Some(value) = self.slots.get(&key) {
+            return Ok(*value);
+        }
+        let value = self.storage.storage(address, index)?;
+        self.slots.insert(key, value);
+        Ok(value)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.storage.block_hash(number)
+    }
+
+    fn is_ready(&self, address: &Address, index: Option<&U256>) -> bool {
+        if self.accounts.contains_key(address)
+            && index.is_none_or(|index| self.slots.contains_key(&(*address, *index)))
+        {
+            return true;
+        }
+        self.storage.is_ready(address, index)
+    }
+
+    fn wait_for_fetches(&self, timeout: Duration) {
+        self.storage.wait_for_fetches(timeout);
+    }
+}
@@ -5,7 +5,7 @@ use std::sync::Arc;
 use alloy_primitives::{Address, B256, U256, keccak256};
 
 use super::{BlockHashes, Bytecodes, ChainState, EvmCode};
-use crate::{AccountBasic, Storage};
+use crate::{AccountBasic, BlockStateDiff, StateRootProvider, Storage};
 
 /// A storage that stores chain data in memory.
 #[derive(Debug, Clone, Default)]
@@ -28,6 +28,42 @@ impl InMemoryStorage {
             block_hashes,
         }
     }
+
+    /// Apply the state changes of a block on top of this storage, making it
+    /// the post-block state.
+    pub fn apply_state_diff(&mut self, diff: &BlockStateDiff) {
+        if !diff.new_bytecodes.is_empty() {
+            Arc::make_mut(&mut self.bytecodes).extend(
+                diff.new_bytecodes
+                    .iter()
+                    .map(|(code_hash, code)| (*code_hash, code.clone())),
+            );
+        }
+        for (address, account_diff) in &diff.accounts {
+            let Some(present) = &account_diff.present else {
+                self.accounts.remove(address);
+                continue;
+            };
+            let account = self.accounts.entry(*address).or_default();
+            if account_diff.destroyed {
+                account.storage.clear();
+            }
+            account.balance = present.balance;
+            account.nonce = present.nonce;
+            if account.code_hash != account_diff.code_hash {
+                // Codes are looked up by hash from [Self::bytecodes].
+                account.code_hash = account_diff.code_hash;
+                account.code = None;
+            }
+            for (slot, slot_diff) in &account_diff.storage {
+                if slot_diff.present.is_zero() {
+                    account.storage.remove(slot);
+                } else {
+                    account.storage.insert(*slot, slot_diff.present);
+                }
+            }
+        }
+    }
 }
 
 impl Storage for InMemoryStorage {
@@ -76,3 +112,23 @@ impl Storage for InMemoryStorage {
             .unwrap_or_else(|| keccak256(number.to_string().as_bytes())))
     }
 }
+
+impl StateRootProvider for InMemoryStorage {
+    fn addresses(&self) -> Result<Vec<Address>, Self::Error> {
+        Ok(self.accounts.keys().copied().collect())
+    }
+
+    fn storage_slots(&self, address: &Address) -> Result<Vec<(U256, U256)>, Self::Error> {
+        Ok(self
+            .accounts
+            .get(address)
+            .map(|account| {
+                account
+                    .storage
+                    .iter()
+                    .map(|(slot, value)| (*slot, *value))
+                    .collect()
+            })
+            .unwrap_or_default())
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use alloy_primitives::{Address, B256, U256};
+use hashbrown::HashMap;
+
+use super::{Bytecodes, EvmCode};
+use crate::{AccountBasic, BuildSuffixHasher, EvmAccount, EvmStateTransitions, Storage};
+
+#[derive(Debug)]
+struct OverlayAccount {
+    // [None] if the account has been destroyed.
+    account: Option<EvmAccount>,
+    // Whether the account has been destroyed at some point, so its storage
+    // in the underlying storage must be ignored.
+    storage_cleared: bool,
+}
+
+/// A storage that layers block-level state transitions, like those of
+/// system calls and executed transactions, on top of another storage.
+#[derive(Debug)]
+pub(crate) struct StateOverlay<'a, S: Storage> {
+    storage: &'a S,
+    accounts: HashMap<Address, OverlayAccount, BuildSuffixHasher>,
+    bytecodes: Bytecodes,
+    // The hashes of blocks executed on top of the underlying storage.
+    block_hashes: HashMap<u64, B256>,
+}
+
+impl<'a, S: Storage> StateOverlay<'a, S> {
+    pub(crate) fn new(storage: &'a S) -> Self {
+        Self {
+            storage,
+            accounts: HashMap::default(),
+            bytecodes: Bytecodes::default(),
+            block_hashes: HashMap::default(),
+        }
+    }
+
+    // Register the hash of a block that is executed on top of the storage.
+    pub(crate) fn insert_block_hash(&mut self, number: u64, hash: B256) {
+        self.block_hashes.insert(number, hash);
+    }
+
+    // Apply the next state transitions in the block on top of the current ones.
+    pub(crate) fn apply(&mut self, transitions: &EvmStateTransitions) {
+        for (address, account) in transitions {
+            let Some(account) = account else {
+                self.accounts.insert(
+                    *address,
+                    OverlayAccount {
+                        account: None,
+                        storage_cleared: true,
+                    },
+                );
+                continue;
+            };
+            if let (Some(code_hash), Some(code)) = (account.code_hash, &account.code) {
+                self.bytecodes
+                    .entry(code_hash)
+                    .or_insert_with(|| code.clone());
+            }
+            match self.accounts.get_mut(address) {
+                Some(OverlayAccount {
+                    account: Some(prev),
+                    ..
+                }) => {
+                    prev.balance = account.balance;
+                    prev.nonce = account.nonce;
+                    prev.code_hash = account.code_hash;
+                    prev.code.clone_from(&account.code);
+                    prev.storage.extend(&account.storage);
+                }
+                Some(overlay_account) => overlay_account.account = Some(account.clone()),
+                None => {
+                    self.accounts.insert(
+                        *address,
+                        OverlayAccount {
+                            account: Some(account.clone()),
+                            storage_cleared: false,
+                        },
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl<S: Storage> Storage for StateOverlay<'_, S> {
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        match self.accounts.get(address) {
+            Some(overlay_account) => {
+                Ok(overlay_account
+                    .account
+                    .as_ref()
+                    .map(|account| AccountBasic {
+                        balance: account.balance,
+                        nonce: account.nonce,
+                    }))
+            }
+            None => self.storage.basic(address),
+        }
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        match self.accounts.get(address) {
+            Some(overlay_account) => Ok(overlay_account
+                .account
+                .as_ref()
+                .and_then(|account| account.code_hash)),
+            None => self.storage.code_hash(address),
+        }
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        match self.bytecodes.get(code_hash) {
+            Some(code) => Ok(Some(code.clone())),
+            None => self.storage.code_by_hash(code_hash),
+        }
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        match self.accounts.get(address) {
+            Some(overlay_account) => {
+                if overlay_account
+                    .account
+                    .as_ref()
+                    .is_some_and(|account| account.storage.values().any(|value| !value.is_zero()))
+                {
+                    return Ok(true);
+                }
+                if overlay_account.storage_cleared {
+                    return Ok(false);
+                }
+                self.storage.has_storage(address)
+            }
+            None => self.storage.has_storage(address),
+        }
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        if let Some(overlay_account) = self.accounts.get(address) {
+            if let Some(value) = overlay_account
+                .account
+                .as_ref()
+                .and_then(|account| account.storage.get(index))
+            {
+                return Ok(*value);
+            }
+            if overlay_account.storage_cleared {
+                return Ok(U256::ZERO);
+            }
+        }
+        self.storage.storage(address, index)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        if let Some(hash) = self.block_hashes.get(number) {
+            return Ok(*hash);
+        }
+        self.storage.block_hash(number)
+    }
+
+    fn is_ready(&self, address: &Address, index: Option<&U256>) -> bool {
+        let Some(overlay_account) = self.accounts.get(address) else {
+            return self.storage.is_ready(address, index);
+        };
+        match index {
+            Some(index)
+                if !overlay_account.storage_cleared
+                    && overlay_account
+                        .account
+                        .as_ref()
+                        .is_none_or(|account| !account.storage.contains_key(index)) =>
+            {
+                self.storage.is_ready(address, Some(index))
+            }
+            _ => true,
+        }
+    }
+
+    fn wait_for_fetches(&self, timeout: Duration) {
+        self.storage.wait_for_fetches(timeout);
+    }
+}
@@ -0,0 +1,230 @@
+use std::{
+    fmt::Debug,
+    future::Future,
+    sync::{Arc, Condvar, Mutex},
+    time::Duration,
+};
+
+use alloy_primitives::{Address, B256, U256};
+use hashbrown::HashMap;
+use tokio::{runtime::Handle, task};
+
+use crate::{AccountBasic, AsyncStorage, BuildSuffixHasher, EvmCode, Storage};
+
+// The state of a read that is fetched in the background.
+#[derive(Debug)]
+enum Fetch<T, E> {
+    Pending,
+    Ready(T),
+    // Kept for the blocking read to return, instead of fetching again and
+    // parking the reading transaction forever on a persistent error.
+    Failed(E),
+}
+
+// The prefetched basic information and code hash of an account.
+type PrefetchedAccount = (Option<AccountBasic>, Option<B256>);
+
+type PrefetchedAccounts<E> = HashMap<Address, Fetch<PrefetchedAccount, E>, BuildSuffixHasher>;
+type PrefetchedSlots<E> = HashMap<(Address, U256), Fetch<U256, E>>;
+
+/// A [`Storage`] that fetches accounts and storage slots from an
+/// [`AsyncStorage`] in the background. Transactions reading state that has not
+/// been fetched yet are parked, so workers keep executing other transactions
+/// instead of blocking on I/O. Other reads, like from sequential execution,
+/// block until the data is fetched.
+#[derive(Debug)]
+pub struct PrefetchStorage<S: AsyncStorage> {
+    storage: Arc<S>,
+    // The Tokio runtime to fetch state on.
+    handle: Handle,
+    // Using [Arc]s to share the fetched data with the background tasks.
+    accounts: Arc<Mutex<PrefetchedAccounts<S::Error>>>,
+    slots: Arc<Mutex<PrefetchedSlots<S::Error>>>,
+    // The number of background fetches in flight, notified as they finish.
+    pending_fetches: Arc<(Mutex<usize>, Condvar)>,
+}
+
+impl<S> PrefetchStorage<S>
+where
+    S: AsyncStorage + Send + Sync + 'static,
+    S::Error: Send,
+{
+    /// Create a new prefetch storage that fetches state on the runtime of [handle].
+    pub fn new(storage: S, handle: Handle) -> Self {
+        Self {
+            storage: Arc::new(storage),
+            handle,
+            accounts: Arc::default(),
+            slots: Arc::default(),
+            pending_fetches: Arc::default(),
+        }
+    }
+
+    /// Get the underlying asynchronous storage.
+    pub fn inner(&self) -> &S {
+        &self.storage
+    }
+
+    // Block on a future in both synchronous code and a Tokio multi-thread runtime.
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        if Handle::try_current().is_ok() {
+            task::block_in_place(|| self.handle.block_on(future))
+        } else {
+            self.handle.block_on(future)
+        }
+    }
+
+    async fn fetch_account(storage: &S, address: &Address) -> Result<PrefetchedAccount, S::Error> {
+        let (basic, code_hash) = tokio::join!(storage.basic(address), storage.code_hash(address));
+        Ok((basic?, code_hash?))
+    }
+
+    fn account(&self, address: &Address) -> Result<PrefetchedAccount, S::Error> {
+        {
+            let mut accounts = self.accounts.lock().unwrap();
+            match accounts.get(address) {
+                Some(Fetch::Ready(account)) => return Ok(account.clone()),
+                // Return the error once, and fetch again on the next read.
+                Some(Fetch::Failed(_)) => {
+                    if let Some(Fetch::Failed(err)) = accounts.remove(address) {
+                        return Err(err);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let account = self.block_on(Self::fetch_account(&self.storage, address))?;
+        self.accounts
+            .lock()
+            .unwrap()
+            .insert(*address, Fetch::Ready(account.clone()));
+        Ok(account)
+    }
+
+    // Count a background fetch in flight, returning a guard to finish it.
+    fn start_fetch(&self) -> FetchGuard {
+        *self.pending_fetches.0.lock().unwrap() += 1;
+        FetchGuard(self.pending_fetches.clone())
+    }
+
+    // Return whether the account is ready, else start fetching it in the
+    // background if it is not already.
+    fn prefetch_account(&self, address: &Address) -> bool {
+        let mut accounts = self.accounts.lock().unwrap();
+        match accounts.get(address) {
+            Some(Fetch::Ready(_) | Fetch::Failed(_)) => return true,
+            Some(Fetch::Pending) => return false,
+            None => {}
+        }
+        accounts.insert(*address, Fetch::Pending);
+        let (storage, accounts, address) = (self.storage.clone(), self.accounts.clone(), *address);
+        let fetch_guard = self.start_fetch();
+        self.handle.spawn(async move {
+            let account = Self::fetch_account(&storage, &address).await;
+            // Leave errors to the blocking read.
+            let fetch = account.map_or_else(Fetch::Failed, Fetch::Ready);
+            accounts.lock().unwrap().insert(address, fetch);
+            drop(fetch_guard);
+        });
+        false
+    }
+
+    // Return whether the storage slot is ready, else start fetching it in the
+    // background if it is not already.
+    fn prefetch_slot(&self, address: &Address, index: &U256) -> bool {
+        let mut slots = self.slots.lock().unwrap();
+        let key = (*address, *index);
+        match slots.get(&key) {
+            Some(Fetch::Ready(_) | Fetch::Failed(_)) => return true,
+            Some(Fetch::Pending) => return false,
+            None => {}
+        }
+        slots.insert(key, Fetch::Pending);
+        let (storage, slots) = (self.storage.clone(), self.slots.clone());
+        let fetch_guard = self.start_fetch();
+        self.handle.spawn(async move {
+            let value = storage.storage(&key.0, &key.1).await;
+            // Leave errors to the blocking read.
+            let fetch = value.map_or_else(Fetch::Failed, Fetch::Ready);
+            slots.lock().unwrap().insert(key, fetch);
+            drop(fetch_guard);
+        });
+        false
+    }
+}
+
+impl<S> Storage for PrefetchStorage<S>
+where
+    S: AsyncStorage + Send + Sync + 'static,
+    S::Error: Send,
+{
+    type Error = S::Error;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        self.account(address).map(|(basic, _)| basic)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.account(address).map(|(_, code_hash)| code_hash)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.block_on(self.storage.code_by_hash(code_hash))
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.block_on(self.storage.has_storage(address))
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        let key = (*address, *index);
+        {
+            let mut slots = self.slots.lock().unwrap();
+            match slots.get(&key) {
+                Some(Fetch::Ready(value)) => return Ok(*value),
+                // Return the error once, and fetch again on the next read.
+                Some(Fetch::Failed(_)) => {
+                    if let Some(Fetch::Failed(err)) = slots.remove(&key) {
+                        return Err(err);
+                    }
+                }
+                _ => {}
+            }
+        }
+        let value = self.block_on(self.storage.storage(address, index))?;
+        self.slots.lock().unwrap().insert(key, Fetch::Ready(value));
+        Ok(value)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.block_on(self.storage.block_hash(number))
+    }
+
+    fn is_ready(&self, address: &Address, index: Option<&U256>) -> bool {
+        // Fetch both the account and the slot at once if neither is ready.
+        let is_account_ready = self.prefetch_account(address);
+        index.is_none_or(|index| self.prefetch_slot(address, index)) && is_account_ready
+    }
+
+    fn wait_for_fetches(&self, timeout: Duration) {
+        let (pending_fetches, fetched) = &*self.pending_fetches;
+        let pending_fetches = pending_fetches.lock().unwrap();
+        let _ = fetched
+            .wait_timeout_while(pending_fetches, timeout, |pending_fetches| {
+                *pending_fetches > 0
+            })
+            .unwrap();
+    }
+}
+
+// Finish a background fetch when dropped, even if the fetching task panics or
+// is cancelled, to not keep waiting workers until they time out.
+struct FetchGuard(Arc<(Mutex<usize>, Condvar)>);
+
+impl Drop for FetchGuard {
+    fn drop(&mut self) {
+        let (pending_fetches, fetched) = &*self.0;
+        *pending_fetches.lock().unwrap() -= 1;
+        fetched.notify_all();
+    }
+}
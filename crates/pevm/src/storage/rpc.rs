@@ -24,7 +24,7 @@ use tokio::{
     task,
 };
 
-use crate::{AccountBasic, EvmAccount, Storage};
+use crate::{AccountBasic, AsyncStorage, EvmAccount, Storage};
 
 use super::{BlockHashes, Bytecodes, ChainState, EvmCode};
 
@@ -119,10 +119,10 @@ impl<N: Network> RpcStorage<N> {
     }
 }
 
-impl<N: Network> Storage for RpcStorage<N> {
+impl<N: Network> AsyncStorage for RpcStorage<N> {
     type Error = RpcStorageError;
 
-    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+    async fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
         if let Some(account) = self.cache_accounts.lock().unwrap().get(address) {
             return Ok(Some(AccountBasic {
                 balance: account.balance,
@@ -130,17 +130,15 @@ impl<N: Network> Storage for RpcStorage<N> {
             }));
         }
 
-        let (nonce, balance, code) = self.block_on(async {
-            tokio::join!(
-                self.fetch(|| {
-                    self.provider
-                        .get_transaction_count(*address)
-                        .block_id(self.block_id)
-                }),
-                self.fetch(|| self.provider.get_balance(*address).block_id(self.block_id)),
-                self.fetch(|| self.provider.get_code_at(*address).block_id(self.block_id)),
-            )
-        });
+        let (nonce, balance, code) = tokio::join!(
+            self.fetch(|| {
+                self.provider
+                    .get_transaction_count(*address)
+                    .block_id(self.block_id)
+            }),
+            self.fetch(|| self.provider.get_balance(*address).block_id(self.block_id)),
+            self.fetch(|| self.provider.get_code_at(*address).block_id(self.block_id)),
+        );
         let nonce = nonce?;
         let balance = balance?;
         let code = code?;
@@ -181,8 +179,8 @@ impl<N: Network> Storage for RpcStorage<N> {
         Ok(Some(AccountBasic { balance, nonce }))
     }
 
-    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
-        self.basic(address)?;
+    async fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        AsyncStorage::basic(self, address).await?;
         Ok(self
             .cache_accounts
             .lock()
@@ -191,37 +189,41 @@ impl<N: Network> Storage for RpcStorage<N> {
             .and_then(|account| account.code_hash))
     }
 
-    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+    async fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
         Ok(self.cache_bytecodes.lock().unwrap().get(code_hash).cloned())
     }
 
-    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
-        let proof = self.block_on(self.fetch(|| {
-            self.provider
-                // [get_account] is simpler but it yields deserialization
-                // error on an empty account.
-                .get_proof(*address, Vec::new())
-                .block_id(self.block_id)
-        }))?;
+    async fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        let proof = self
+            .fetch(|| {
+                self.provider
+                    // [get_account] is simpler but it yields deserialization
+                    // error on an empty account.
+                    .get_proof(*address, Vec::new())
+                    .block_id(self.block_id)
+            })
+            .await?;
         Ok(proof.storage_hash != alloy_consensus::EMPTY_ROOT_HASH)
     }
 
-    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+    async fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
         if let Some(account) = self.cache_accounts.lock().unwrap().get(address)
             && let Some(value) = account.storage.get(index)
         {
             return Ok(*value);
         }
-        let value = self.block_on(self.fetch(|| {
-            self.provider
-                .get_storage_at(*address, *index)
-                .block_id(self.block_id)
-        }))?;
+        let value = self
+            .fetch(|| {
+                self.provider
+                    .get_storage_at(*address, *index)
+                    .block_id(self.block_id)
+            })
+            .await?;
         // We only cache if the pre-state account is non-empty. Else this
         // could be a false alarm that results in the default 0. Caching
         // that would make this account non-empty and may fail a tx that
         // deploys a contract here (EIP-7610).
-        self.basic(address)?;
+        AsyncStorage::basic(self, address).await?;
         if let Some(account) = self.cache_accounts.lock().unwrap().get_mut(address) {
             account.storage.insert(*index, value);
         }
@@ -229,16 +231,17 @@ impl<N: Network> Storage for RpcStorage<N> {
         Ok(value)
     }
 
-    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+    async fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
         if let Some(&block_hash) = self.cache_block_hashes.lock().unwrap().get(number) {
             return Ok(block_hash);
         }
 
         let block_hash = self
-            .block_on(self.fetch(|| {
+            .fetch(|| {
                 self.provider
                     .get_block_by_number(BlockNumberOrTag::Number(*number))
-            }))
+            })
+            .await
             .map(|block| block.unwrap().header().hash())?;
 
         self.cache_block_hashes
@@ -249,3 +252,31 @@ impl<N: Network> Storage for RpcStorage<N> {
         Ok(block_hash)
     }
 }
+
+impl<N: Network> Storage for RpcStorage<N> {
+    type Error = RpcStorageError;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        self.block_on(AsyncStorage::basic(self, address))
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.block_on(AsyncStorage::code_hash(self, address))
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.block_on(AsyncStorage::code_by_hash(self, code_hash))
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.block_on(AsyncStorage::has_storage(self, address))
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        self.block_on(AsyncStorage::storage(self, address, index))
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.block_on(AsyncStorage::block_hash(self, number))
+    }
+}
@@ -0,0 +1,260 @@
+use std::{collections::BTreeSet, fmt, num::NonZeroUsize};
+
+use alloy_primitives::{Address, U256};
+
+use crate::{EvmAccount, EvmStateTransitions, ExecutionError, PevmTxExecutionResult, TxIdx};
+
+/// How often blocks executed in parallel are verified against a sequential
+/// execution of the same block, to catch pevm bugs on live traffic.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyMode {
+    /// Trust the parallel execution.
+    #[default]
+    Never,
+    /// Verify every block.
+    Always,
+    /// Verify one in every `n` blocks executed in parallel, starting with the
+    /// first one.
+    Sampled(NonZeroUsize),
+}
+
+/// Where the results of a parallel and a sequential execution diverge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DivergenceLocation {
+    /// Whether the block executed at all.
+    Outcome,
+    /// Whether the transaction was excluded from the block, and why.
+    Exclusion,
+    /// The receipt of the transaction.
+    Receipt,
+    /// Whether the account changed, or was destroyed.
+    Account(Address),
+    /// The balance of an account.
+    Balance(Address),
+    /// The nonce of an account.
+    Nonce(Address),
+    /// The code of an account.
+    Code(Address),
+    /// A storage slot of an account.
+    Storage(Address, U256),
+}
+
+impl fmt::Display for DivergenceLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Outcome => write!(f, "outcome"),
+            Self::Exclusion => write!(f, "exclusion"),
+            Self::Receipt => write!(f, "receipt"),
+            Self::Account(address) => write!(f, "account {address}"),
+            Self::Balance(address) => write!(f, "balance of {address}"),
+            Self::Nonce(address) => write!(f, "nonce of {address}"),
+            Self::Code(address) => write!(f, "code of {address}"),
+            Self::Storage(address, slot) => write!(f, "storage {slot} of {address}"),
+        }
+    }
+}
+
+/// The first divergence between the results of a parallel and a sequential
+/// execution of the same block, with both values debug-formatted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Divergence {
+    /// The index of the transaction in the block, [None] if the block itself
+    /// failed to execute sequentially.
+    pub tx_idx: Option<TxIdx>,
+    /// What diverged.
+    pub location: DivergenceLocation,
+    /// The value from the parallel execution.
+    pub parallel: String,
+    /// The value from the sequential execution.
+    pub sequential: String,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(tx_idx) = self.tx_idx {
+            write!(f, "tx #{tx_idx} ")?;
+        }
+        write!(
+            f,
+            "{}: parallel {}, sequential {}",
+            self.location, self.parallel, self.sequential
+        )
+    }
+}
+
+impl Divergence {
+    fn new(
+        tx_idx: Option<TxIdx>,
+        location: DivergenceLocation,
+        parallel: impl fmt::Debug,
+        sequential: impl fmt::Debug,
+    ) -> Self {
+        Self {
+            tx_idx,
+            location,
+            parallel: format!("{parallel:?}"),
+            sequential: format!("{sequential:?}"),
+        }
+    }
+
+    /// Find the first divergence between the results of the same transactions
+    /// executed in parallel and sequentially, comparing their receipts then
+    /// their state transitions by address and storage slot.
+    pub fn find(
+        parallel: &[PevmTxExecutionResult],
+        sequential: &[PevmTxExecutionResult],
+    ) -> Option<Self> {
+        for (tx_idx, (parallel, sequential)) in parallel.iter().zip(sequential).enumerate() {
+            if parallel.receipt != sequential.receipt {
+                return Some(Self::new(
+                    Some(tx_idx),
+                    DivergenceLocation::Receipt,
+                    &parallel.receipt,
+                    &sequential.receipt,
+                ));
+            }
+            if let Some(divergence) = find_state_divergence(&parallel.state, &sequential.state) {
+                return Some(Self {
+                    tx_idx: Some(tx_idx),
+                    ..divergence
+                });
+            }
+        }
+        // Only reachable with different numbers of transactions.
+        (parallel.len() != sequential.len()).then(|| {
+            Self::new(
+                Some(parallel.len().min(sequential.len())),
+                DivergenceLocation::Outcome,
+                parallel.len(),
+                sequential.len(),
+            )
+        })
+    }
+}
+
+// Find the first divergence between two state transitions of a transaction,
+// in the order of their addresses and storage slots for reproducible reports.
+fn find_state_divergence(
+    parallel: &EvmStateTransitions,
+    sequential: &EvmStateTransitions,
+) -> Option<Divergence> {
+    let addresses: BTreeSet<&Address> = parallel.keys().chain(sequential.keys()).collect();
+    for address in addresses {
+        let (Some(Some(parallel)), Some(Some(sequential))) =
+            (parallel.get(address), sequential.get(address))
+        else {
+            let (parallel, sequential) = (parallel.get(address), sequential.get(address));
+            if parallel != sequential {
+                return Some(Divergence::new(
+                    None,
+                    DivergenceLocation::Account(*address),
+                    parallel,
+                    sequential,
+                ));
+            }
+            continue;
+        };
+        if let Some(divergence) = find_account_divergence(*address, parallel, sequential) {
+            return Some(divergence);
+        }
+    }
+    None
+}
+
+fn find_account_divergence(
+    address: Address,
+    parallel: &EvmAccount,
+    sequential: &EvmAccount,
+) -> Option<Divergence> {
+    if parallel.balance != sequential.balance {
+        return Some(Divergence::new(
+            None,
+            DivergenceLocation::Balance(address),
+            parallel.balance,
+            sequential.balance,
+        ));
+    }
+    if parallel.nonce != sequential.nonce {
+        return Some(Divergence::new(
+            None,
+            DivergenceLocation::Nonce(address),
+            parallel.nonce,
+            sequential.nonce,
+        ));
+    }
+    if parallel.code_hash != sequential.code_hash || parallel.code != sequential.code {
+        return Some(Divergence::new(
+            None,
+            DivergenceLocation::Code(address),
+            parallel.code_hash,
+            sequential.code_hash,
+        ));
+    }
+    let slots: BTreeSet<&U256> = parallel
+        .storage
+        .keys()
+        .chain(sequential.storage.keys())
+        .collect();
+    slots.into_iter().find_map(|slot| {
+        let (parallel, sequential) = (parallel.storage.get(slot), sequential.storage.get(slot));
+        (parallel != sequential).then(|| {
+            Divergence::new(
+                None,
+                DivergenceLocation::Storage(address, *slot),
+                parallel,
+                sequential,
+            )
+        })
+    })
+}
+
+// Find the first divergence between the included results and excluded
+// transactions of a block executed in parallel and sequentially, reporting
+// the indices of the transactions in the block.
+pub(crate) fn find_block_divergence(
+    parallel: (&[PevmTxExecutionResult], &[(TxIdx, ExecutionError)]),
+    sequential: (&[PevmTxExecutionResult], &[(TxIdx, ExecutionError)]),
+) -> Option<Divergence> {
+    let (parallel_results, parallel_excluded) = parallel;
+    let (sequential_results, sequential_excluded) = sequential;
+    if parallel_excluded != sequential_excluded {
+        // The first transaction excluded by one execution but not the other,
+        // or excluded by both with different errors.
+        let position = parallel_excluded
+            .iter()
+            .zip(sequential_excluded)
+            .position(|(parallel, sequential)| parallel != sequential)
+            .unwrap_or(parallel_excluded.len().min(sequential_excluded.len()));
+        let (parallel, sequential) = (
+            parallel_excluded.get(position),
+            sequential_excluded.get(position),
+        );
+        let tx_idx = parallel
+            .map_or(usize::MAX, |(tx_idx, _)| *tx_idx)
+            .min(sequential.map_or(usize::MAX, |(tx_idx, _)| *tx_idx));
+        let excluded_error = |excluded: &[(TxIdx, ExecutionError)]| {
+            excluded
+                .iter()
+                .find(|(excluded_idx, _)| *excluded_idx == tx_idx)
+                .map(|(_, err)| err.clone())
+        };
+        return Some(Divergence::new(
+            Some(tx_idx),
+            DivergenceLocation::Exclusion,
+            excluded_error(parallel_excluded),
+            excluded_error(sequential_excluded),
+        ));
+    }
+    // With the same excluded transactions, the included results line up.
+    let mut divergence = Divergence::find(parallel_results, sequential_results)?;
+    if let Some(included_idx) = divergence.tx_idx {
+        let mut tx_idx = included_idx;
+        for (excluded_idx, _) in parallel_excluded {
+            if *excluded_idx <= tx_idx {
+                tx_idx += 1;
+            }
+        }
+        divergence.tx_idx = Some(tx_idx);
+    }
+    Some(divergence)
+}
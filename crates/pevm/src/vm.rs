@@ -1,13 +1,17 @@
-use alloy_primitives::{Address, B256, TxKind, U256};
+use std::{cell::Cell, sync::LazyLock, time::Duration};
+
+use alloy_primitives::{Address, B256, Bytes, TxKind, U256};
 use alloy_rpc_types_eth::Receipt;
 use hashbrown::HashMap;
 use revm::{
     Database,
     context::{
         BlockEnv, ContextSetters, ContextTr, DBErrorMarker, JournalTr, TxEnv,
-        result::{EVMError, InvalidTransaction, ResultAndState},
+        result::{EVMError, ExecutionResult, InvalidTransaction, ResultAndState},
     },
-    handler::{EvmTr, FrameResult, Handler},
+    handler::{EvmTr, FrameResult, Handler, SYSTEM_ADDRESS, SystemCallEvm},
+    inspector::{InspectorEvmTr, InspectorHandler},
+    interpreter::interpreter::EthInterpreter,
     primitives::KECCAK_EMPTY,
     state::{AccountInfo, Bytecode},
 };
@@ -15,18 +19,38 @@ use smallvec::SmallVec;
 
 use crate::{
     AccountBasic, BuildIdentityHasher, BuildSuffixHasher, EvmAccount, FinishExecFlags, MemoryEntry,
-    MemoryLocation, MemoryLocationHash, MemoryValue, ReadOrigin, ReadOrigins, ReadSet, Storage,
-    TxIdx, TxVersion, WriteSet, chain::PevmChain, hash_deterministic, mv_memory::MvMemory,
+    MemoryLocation, MemoryLocationHash, MemoryValue, PevmMode, ReadOrigin, ReadOrigins, ReadSet,
+    Storage, TxIdx, TxIncarnation, TxVersion, WriteSet,
+    chain::{PevmChain, SystemCall},
+    commutative::{CommutativeRead, PevmInspector},
+    hash_deterministic,
+    mv_memory::MvMemory,
 };
 
 /// The execution error from the underlying EVM executor.
 // Will there be DB errors outside of read?
 pub type ExecutionError = EVMError<ReadError>;
 
+// Convert an error of [PevmChain::Evm] to the typed [ExecutionError] that
+// parallel execution reports, for sequential execution to report the same
+// errors.
+pub(crate) fn execution_error<C: PevmChain, E: ToString>(
+    chain: &C,
+    err: EVMError<E, C::EvmErrorType>,
+) -> ExecutionError {
+    match err.map_db_err(|err| ReadError::StorageError(err.to_string())) {
+        EVMError::Transaction(err) => chain.transaction_error(err),
+        EVMError::Header(err) => EVMError::Header(err),
+        EVMError::Database(err) => EVMError::Database(err),
+        EVMError::Custom(err) => EVMError::Custom(err),
+        EVMError::CustomAny(err) => EVMError::CustomAny(err),
+    }
+}
+
 /// Represents the state transitions of the EVM accounts after execution.
 /// If the value is [None], it indicates that the account is marked for removal.
 /// If the value is [`Some(new_state)`], it indicates that the account has become [`new_state`].
-type EvmStateTransitions = HashMap<Address, Option<EvmAccount>, BuildSuffixHasher>;
+pub type EvmStateTransitions = HashMap<Address, Option<EvmAccount>, BuildSuffixHasher>;
 
 /// Execution result of a transaction
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -74,6 +98,7 @@ pub(crate) enum VmExecutionError {
     Retry,
     FallbackToSequential,
     Blocking(TxIdx),
+    Pending,
     ExecutionError(ExecutionError),
 }
 
@@ -87,6 +112,9 @@ pub enum ReadError {
     /// This memory location has been written by a lower transaction.
     #[error("Read of memory location is blocked by tx #{0}")]
     Blocking(TxIdx),
+    /// The memory location is still being fetched from an asynchronous storage.
+    #[error("Read of memory location is pending on storage")]
+    Pending,
     /// There has been an inconsistent read like reading the same
     /// location from storage in the first call but from [`VmMemory`] in
     /// the next.
@@ -96,14 +124,14 @@ pub enum ReadError {
     /// not having a (+1) nonce from storage.
     #[error("Tx #{0} has invalid nonce")]
     InvalidNonce(TxIdx),
-    /// Read a self-destructed account that is very hard to handle, as
-    /// there is no performant way to mark all storage slots as cleared.
-    #[error("Tried to read self-destructed account")]
-    SelfDestructedAccount,
     /// The stored memory value type doesn't match its location type.
     // TODO: Handle this at the type level?
     #[error("Invalid type of stored memory value")]
     InvalidMemoryValueType,
+    /// Two different memory locations have the same hash, so the
+    /// multi-version memory cannot tell their values apart.
+    #[error("Memory locations collide on hash {0}")]
+    LocationCollision(MemoryLocationHash),
 }
 
 impl DBErrorMarker for ReadError {}
@@ -112,15 +140,34 @@ impl From<ReadError> for VmExecutionError {
     fn from(err: ReadError) -> Self {
         match err {
             ReadError::InconsistentRead => Self::Retry,
-            ReadError::SelfDestructedAccount => Self::FallbackToSequential,
+            ReadError::LocationCollision(_) => Self::FallbackToSequential,
             ReadError::Blocking(tx_idx) => Self::Blocking(tx_idx),
+            ReadError::Pending => Self::Pending,
             _ => Self::ExecutionError(EVMError::Database(err)),
         }
     }
 }
 
+// The mock transaction that [VmDb] reads system calls with, which neither
+// have a nonce nor are lazy updated.
+static SYSTEM_TX: LazyLock<TxEnv> = LazyLock::new(|| TxEnv {
+    caller: SYSTEM_ADDRESS,
+    kind: TxKind::Create,
+    ..TxEnv::default()
+});
+
+// What a system call leaves for the requests of its block.
+#[derive(Debug)]
+pub(crate) enum SystemCallOutput {
+    Success(Bytes),
+    Failure,
+    // The called contract has no code, so the call was skipped.
+    MissingCode,
+}
+
 pub(crate) struct VmExecutionResult {
-    pub(crate) execution_result: PevmTxExecutionResult,
+    // An error if the transaction is excluded from the block by [PevmMode].
+    pub(crate) execution_result: Result<PevmTxExecutionResult, ExecutionError>,
     pub(crate) flags: FinishExecFlags,
 }
 
@@ -131,6 +178,7 @@ struct VmDb<'a, S: Storage> {
     storage: &'a S,
     mv_memory: &'a MvMemory,
     tx_idx: TxIdx,
+    tx_incarnation: TxIncarnation,
     tx: &'a TxEnv,
     from_hash: MemoryLocationHash,
     to_hash: Option<MemoryLocationHash>,
@@ -138,12 +186,71 @@ struct VmDb<'a, S: Storage> {
     // Indicates if we lazy update this transaction.
     // Only applied to raw transfers' senders & recipients at the moment.
     is_lazy: bool,
+    // Whether to lazy update transactions at all. Lazy senders skip balance
+    // checks until the end of the block, which is only safe for canonical
+    // blocks that cannot have invalid transactions.
+    allow_lazy: bool,
     // Whether to enforce the sender-nonce ordering check for this transaction.
-    // False for transaction types with no nonce (e.g. OP deposits).
+    // False for transaction types with no nonce (e.g. OP deposits), and when
+    // the EVM should reject nonce mismatches itself for the transaction to be
+    // excluded from the block.
     has_nonce: bool,
     read_set: ReadSet,
-    // TODO: Clearer type for [AccountBasic] plus code hash
-    read_accounts: HashMap<MemoryLocationHash, (AccountBasic, Option<B256>), BuildIdentityHasher>,
+    read_accounts: ReadAccounts,
+}
+
+// TODO: Clearer type for [AccountBasic] plus code hash
+type ReadAccounts = HashMap<Address, (AccountBasic, Option<B256>), BuildSuffixHasher>;
+
+// The net lazy balance updates written to a balance location after its
+// closest full value.
+struct LazyBalance {
+    addition: U256,
+    // The sign of [addition] since it can be negative for lazy senders.
+    positive: bool,
+}
+
+impl Default for LazyBalance {
+    fn default() -> Self {
+        Self {
+            addition: U256::ZERO,
+            positive: true,
+        }
+    }
+}
+
+impl LazyBalance {
+    fn add(&mut self, amount: U256) {
+        if self.positive {
+            self.addition = self.addition.saturating_add(amount);
+        } else {
+            self.positive = amount >= self.addition;
+            self.addition = self.addition.abs_diff(amount);
+        }
+    }
+
+    fn sub(&mut self, amount: U256) {
+        if self.positive {
+            self.positive = self.addition >= amount;
+            self.addition = self.addition.abs_diff(amount);
+        } else {
+            self.addition = self.addition.saturating_add(amount);
+        }
+    }
+
+    const fn apply(&self, balance: U256) -> U256 {
+        if self.positive {
+            balance.saturating_add(self.addition)
+        } else {
+            balance.saturating_sub(self.addition)
+        }
+    }
+}
+
+thread_local! {
+    // The read buffers of the last [Vm] dropped on this thread for the next
+    // one to reuse, across blocks on the persistent workers of [crate::Pevm].
+    static VM_BUFFERS: Cell<Option<(ReadSet, ReadAccounts)>> = const { Cell::new(None) };
 }
 
 impl<'a, S: Storage> VmDb<'a, S> {
@@ -151,13 +258,14 @@ impl<'a, S: Storage> VmDb<'a, S> {
     // Must be called before each transaction execution.
     fn set_tx(
         &mut self,
-        tx_idx: TxIdx,
+        tx_version: &TxVersion,
         tx: &'a TxEnv,
         from_hash: MemoryLocationHash,
         to_hash: Option<MemoryLocationHash>,
         has_nonce: bool,
     ) -> Result<(), ReadError> {
-        self.tx_idx = tx_idx;
+        self.tx_idx = tx_version.tx_idx;
+        self.tx_incarnation = tx_version.tx_incarnation;
         self.tx = tx;
         self.from_hash = from_hash;
         self.to_hash = to_hash;
@@ -173,16 +281,15 @@ impl<'a, S: Storage> VmDb<'a, S> {
             // or recipient in [MvMemory] since sequentially evaluating memory
             // locations with only one entry is much costlier than fully
             // evaluating it concurrently.
-            // TODO: Only lazy update in block syncing mode, not for block
-            // building.
-            self.is_lazy = self.to_code_hash.is_none()
+            self.is_lazy = self.allow_lazy
+                && self.to_code_hash.is_none()
                 && (self.mv_memory.data.contains_key(&from_hash)
                     || self.mv_memory.data.contains_key(&to_hash.unwrap()));
         }
         Ok(())
     }
 
-    fn hash_basic(&self, address: &Address) -> MemoryLocationHash {
+    fn hash_balance(&self, address: &Address) -> MemoryLocationHash {
         if address == &self.tx.caller {
             return self.from_hash;
         }
@@ -191,81 +298,22 @@ impl<'a, S: Storage> VmDb<'a, S> {
         {
             return self.to_hash.unwrap();
         }
-        hash_deterministic(MemoryLocation::Basic(*address))
-    }
-
-    // Push a new read origin. Return an error when there's already
-    // an origin but doesn't match the new one to force re-execution.
-    fn push_origin(read_origins: &mut ReadOrigins, origin: ReadOrigin) -> Result<(), ReadError> {
-        if let Some(prev_origin) = read_origins.last() {
-            if prev_origin != &origin {
-                return Err(ReadError::InconsistentRead);
-            }
-        } else {
-            read_origins.push(origin);
-        }
-        Ok(())
-    }
-
-    fn get_code_hash(&mut self, address: Address) -> Result<Option<B256>, ReadError> {
-        let location_hash = hash_deterministic(MemoryLocation::CodeHash(address));
-        let read_origins = self.read_set.entry(location_hash).or_default();
-
-        // Try to read the latest code hash in [MvMemory]
-        // TODO: Memoize read locations (expected to be small) here in [Vm] to avoid
-        // contention in [MvMemory]
-        if let Some(written_transactions) = self.mv_memory.data.get(&location_hash)
-            && let Some((tx_idx, MemoryEntry::Data(tx_incarnation, value))) =
-                written_transactions.range(..self.tx_idx).next_back()
-        {
-            match value {
-                MemoryValue::SelfDestructed => {
-                    return Err(ReadError::SelfDestructedAccount);
-                }
-                MemoryValue::CodeHash(code_hash) => {
-                    Self::push_origin(
-                        read_origins,
-                        ReadOrigin::MvMemory(TxVersion {
-                            tx_idx: *tx_idx,
-                            tx_incarnation: *tx_incarnation,
-                        }),
-                    )?;
-                    return Ok(Some(*code_hash));
-                }
-                _ => {}
-            }
-        };
-
-        // Fallback to storage
-        Self::push_origin(read_origins, ReadOrigin::Storage)?;
-        self.storage
-            .code_hash(&address)
-            .map_err(|err| ReadError::StorageError(err.to_string()))
+        hash_deterministic(MemoryLocation::Balance(*address))
     }
-}
-
-impl<S: Storage> Database for VmDb<'_, S> {
-    type Error = ReadError;
-
-    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
-        let location_hash = self.hash_basic(&address);
-
-        // We return a mock for non-contract addresses (for lazy updates) to avoid
-        // unnecessarily evaluating its balance here.
-        if self.is_lazy {
-            if location_hash == self.from_hash {
-                return Ok(Some(AccountInfo {
-                    nonce: self.tx.nonce,
-                    balance: U256::MAX,
-                    code: None,
-                    code_hash: KECCAK_EMPTY,
-                    account_id: None,
-                }));
-            } else if Some(location_hash) == self.to_hash {
-                return Ok(None);
-            }
-        }
 
+    // Read the closest full value of a location before this transaction, and
+    // pass the lazy updates written after it to [add_lazy]. Register the read
+    // origins on the first read, and check that they match on later reads. No
+    // full value means the caller must read from storage, or that the location
+    // was cleared by the self-destruct of its account at [cleared_by], which
+    // ignores the writes up to it.
+    fn read_lazy_location(
+        &mut self,
+        location_hash: MemoryLocationHash,
+        location: MemoryLocation,
+        cleared_by: Option<TxIdx>,
+        mut add_lazy: impl FnMut(&MemoryValue) -> Result<(), ReadError>,
+    ) -> Result<Option<MemoryValue>, ReadError> {
         let read_origins = self.read_set.entry(location_hash).or_default();
         let has_prev_origins = !read_origins.is_empty();
         // We accumulate new origins to either:
@@ -273,25 +321,27 @@ impl<S: Storage> Database for VmDb<'_, S> {
         // - register origins on the first read
         let mut new_origins = SmallVec::new();
 
-        let mut final_account = None;
-        let mut balance_addition = U256::ZERO;
-        // The sign of [balance_addition] since it can be negative for lazy senders.
-        let mut positive_addition = true;
-        let mut nonce_addition = 0;
+        let mut value = None;
 
         // Try reading from multi-version data
         if self.tx_idx > 0
-            && let Some(written_transactions) = self.mv_memory.data.get(&location_hash)
+            && let Some(written_transactions) =
+                self.mv_memory.location_writes(location_hash, &location)?
         {
-            let mut iter = written_transactions.range(..self.tx_idx);
+            let mut iter = written_transactions.entries.range(..self.tx_idx);
 
             // Fully evaluate lazy updates
             loop {
                 match iter.next_back() {
+                    Some((tx_idx, _))
+                        if cleared_by.is_some_and(|cleared_by| *tx_idx <= cleared_by) =>
+                    {
+                        break;
+                    }
                     Some((blocking_idx, MemoryEntry::Estimate)) => {
                         return Err(ReadError::Blocking(*blocking_idx));
                     }
-                    Some((closest_idx, MemoryEntry::Data(tx_incarnation, value))) => {
+                    Some((closest_idx, MemoryEntry::Data(tx_incarnation, memory_value))) => {
                         // About to push a new origin
                         // Inconsistent: new origin will be longer than the previous!
                         if has_prev_origins && read_origins.len() == new_origins.len() {
@@ -308,34 +358,15 @@ impl<S: Storage> Database for VmDb<'_, S> {
                             return Err(ReadError::InconsistentRead);
                         }
                         new_origins.push(origin);
-                        match value {
-                            MemoryValue::Basic(basic) => {
-                                // TODO: Return [SelfDestructedAccount] if [basic] is
-                                // [SelfDestructed]?
-                                // For now we are betting on [code_hash] triggering the
-                                // sequential fallback when we read a self-destructed contract.
-                                final_account = Some(basic.clone());
+                        match memory_value {
+                            MemoryValue::Balance(_)
+                            | MemoryValue::Nonce(_)
+                            | MemoryValue::Storage(_)
+                            | MemoryValue::SelfDestructed => {
+                                value = Some(memory_value.clone());
                                 break;
                             }
-                            MemoryValue::LazyRecipient(addition) => {
-                                if positive_addition {
-                                    balance_addition = balance_addition.saturating_add(*addition);
-                                } else {
-                                    positive_addition = *addition >= balance_addition;
-                                    balance_addition = balance_addition.abs_diff(*addition);
-                                }
-                            }
-                            MemoryValue::LazySender(subtraction) => {
-                                if positive_addition {
-                                    positive_addition = balance_addition >= *subtraction;
-                                    balance_addition = balance_addition.abs_diff(*subtraction);
-                                } else {
-                                    balance_addition =
-                                        balance_addition.saturating_add(*subtraction);
-                                }
-                                nonce_addition += 1;
-                            }
-                            _ => return Err(ReadError::InvalidMemoryValueType),
+                            _ => add_lazy(memory_value)?,
                         }
                     }
                     None => {
@@ -345,24 +376,19 @@ impl<S: Storage> Database for VmDb<'_, S> {
             }
         }
 
-        // Fall back to storage
-        if final_account.is_none() {
-            // Populate [Storage] on the first read
+        if value.is_none() {
+            let origin = cleared_by.map_or(ReadOrigin::Storage, ReadOrigin::SelfDestruct);
+            // Populate [Storage] or [SelfDestruct] on the first read
             if !has_prev_origins {
-                new_origins.push(ReadOrigin::Storage);
+                new_origins.push(origin);
             }
-            // Inconsistent: previous origin is longer or didn't read
-            // from storage for the last origin.
+            // Inconsistent: previous origin is longer or didn't end at
+            // the same origin.
             else if read_origins.len() != new_origins.len() + 1
-                || read_origins.last() != Some(&ReadOrigin::Storage)
+                || read_origins.last() != Some(&origin)
             {
                 return Err(ReadError::InconsistentRead);
             }
-            final_account = match self.storage.basic(&address) {
-                Ok(Some(basic)) => Some(basic),
-                Ok(None) => (balance_addition > U256::ZERO).then(AccountBasic::default),
-                Err(err) => return Err(ReadError::StorageError(err.to_string())),
-            };
         }
 
         // Populate read origins on the first read.
@@ -371,28 +397,200 @@ impl<S: Storage> Database for VmDb<'_, S> {
             *read_origins = new_origins;
         }
 
-        if let Some(mut account) = final_account {
+        Ok(value)
+    }
+
+    // Push a new read origin. Return an error when there's already
+    // an origin but doesn't match the new one to force re-execution.
+    fn push_origin(read_origins: &mut ReadOrigins, origin: ReadOrigin) -> Result<(), ReadError> {
+        if let Some(prev_origin) = read_origins.last() {
+            if prev_origin != &origin {
+                return Err(ReadError::InconsistentRead);
+            }
+        } else {
+            read_origins.push(origin);
+        }
+        Ok(())
+    }
+
+    fn get_code_hash(&mut self, address: Address) -> Result<Option<B256>, ReadError> {
+        let location = MemoryLocation::CodeHash(address);
+        let location_hash = hash_deterministic(location);
+        let read_origins = self.read_set.entry(location_hash).or_default();
+
+        // Try to read the latest code hash in [MvMemory]
+        // TODO: Memoize read locations (expected to be small) here in [Vm] to avoid
+        // contention in [MvMemory]
+        if let Some(written_transactions) =
+            self.mv_memory.location_writes(location_hash, &location)?
+            && let Some((tx_idx, MemoryEntry::Data(tx_incarnation, value))) = written_transactions
+                .entries
+                .range(..self.tx_idx)
+                .next_back()
+        {
+            let code_hash = match value {
+                MemoryValue::CodeHash(code_hash) => Some(*code_hash),
+                MemoryValue::SelfDestructed => None,
+                _ => return Err(ReadError::InvalidMemoryValueType),
+            };
+            Self::push_origin(
+                read_origins,
+                ReadOrigin::MvMemory(TxVersion {
+                    tx_idx: *tx_idx,
+                    tx_incarnation: *tx_incarnation,
+                }),
+            )?;
+            return Ok(code_hash);
+        };
+
+        // Fallback to storage
+        Self::push_origin(read_origins, ReadOrigin::Storage)?;
+        if !self.storage.is_ready(&address, None) {
+            return Err(ReadError::Pending);
+        }
+        self.storage
+            .code_hash(&address)
+            .map_err(|err| ReadError::StorageError(err.to_string()))
+    }
+
+    // Get the last transaction before this one that self-destructed [address],
+    // which cleared its storage.
+    fn get_self_destruct(&mut self, address: Address) -> Result<Option<TxIdx>, ReadError> {
+        let location = MemoryLocation::SelfDestruct(address);
+        let location_hash = hash_deterministic(location);
+        let read_origins = self.read_set.entry(location_hash).or_default();
+
+        if self.tx_idx > 0
+            && let Some(written_transactions) =
+                self.mv_memory.location_writes(location_hash, &location)?
+            && let Some((tx_idx, entry)) = written_transactions
+                .entries
+                .range(..self.tx_idx)
+                .next_back()
+        {
+            let MemoryEntry::Data(tx_incarnation, _) = entry else {
+                return Err(ReadError::Blocking(*tx_idx));
+            };
+            Self::push_origin(
+                read_origins,
+                ReadOrigin::MvMemory(TxVersion {
+                    tx_idx: *tx_idx,
+                    tx_incarnation: *tx_incarnation,
+                }),
+            )?;
+            return Ok(Some(*tx_idx));
+        }
+
+        Self::push_origin(read_origins, ReadOrigin::Storage)?;
+        Ok(None)
+    }
+}
+
+impl<S: Storage> Database for VmDb<'_, S> {
+    type Error = ReadError;
+
+    fn basic(&mut self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let balance_hash = self.hash_balance(&address);
+
+        // We return a mock for non-contract addresses (for lazy updates) to avoid
+        // unnecessarily evaluating its balance here.
+        if self.is_lazy {
+            if balance_hash == self.from_hash {
+                return Ok(Some(AccountInfo {
+                    nonce: self.tx.nonce,
+                    balance: U256::MAX,
+                    code: None,
+                    code_hash: KECCAK_EMPTY,
+                    account_id: None,
+                }));
+            } else if Some(balance_hash) == self.to_hash {
+                return Ok(None);
+            }
+        }
+
+        let mut lazy_balance = LazyBalance::default();
+        let balance = self.read_lazy_location(
+            balance_hash,
+            MemoryLocation::Balance(address),
+            None,
+            |value| {
+                match value {
+                    MemoryValue::LazyRecipient(addition) => lazy_balance.add(*addition),
+                    MemoryValue::LazySender(subtraction) => lazy_balance.sub(*subtraction),
+                    _ => return Err(ReadError::InvalidMemoryValueType),
+                }
+                Ok(())
+            },
+        )?;
+        let nonce = self.read_lazy_location(
+            hash_deterministic(MemoryLocation::Nonce(address)),
+            MemoryLocation::Nonce(address),
+            None,
+            |_| Err(ReadError::InvalidMemoryValueType),
+        )?;
+        // A self-destructed account no longer exists unless it is written to
+        // again, but does not fall back to storage either.
+        let mut exists = false;
+        let mut balance = match balance {
+            Some(MemoryValue::Balance(balance)) => {
+                exists = true;
+                Some(balance)
+            }
+            Some(MemoryValue::SelfDestructed) => Some(U256::ZERO),
+            None => None,
+            _ => return Err(ReadError::InvalidMemoryValueType),
+        };
+        let mut nonce = match nonce {
+            Some(MemoryValue::Nonce(nonce)) => {
+                exists = true;
+                Some(nonce)
+            }
+            Some(MemoryValue::SelfDestructed) => Some(0),
+            None => None,
+            _ => return Err(ReadError::InvalidMemoryValueType),
+        };
+
+        // Fall back to storage
+        if balance.is_none() || nonce.is_none() {
+            if !self.storage.is_ready(&address, None) {
+                return Err(ReadError::Pending);
+            }
+            match self.storage.basic(&address) {
+                Ok(Some(basic)) => {
+                    exists = true;
+                    balance.get_or_insert(basic.balance);
+                    nonce.get_or_insert(basic.nonce);
+                }
+                Ok(None) => {}
+                Err(err) => return Err(ReadError::StorageError(err.to_string())),
+            }
+        }
+        exists |= lazy_balance.positive && lazy_balance.addition > U256::ZERO;
+
+        if exists {
+            // Fully evaluate the account and register it to read cache
+            // to later check if they have changed (been written to).
+            let account = AccountBasic {
+                balance: lazy_balance.apply(balance.unwrap_or_default()),
+                nonce: nonce.unwrap_or_default(),
+            };
+
             // Check sender nonce
-            account.nonce += nonce_addition;
-            if self.has_nonce && location_hash == self.from_hash && self.tx.nonce != account.nonce {
+            if self.has_nonce && balance_hash == self.from_hash && self.tx.nonce != account.nonce {
                 return if self.tx_idx > 0 {
-                    // TODO: Better retry strategy -- immediately, to the
-                    // closest sender tx, to the missing sender tx, etc.
-                    Err(ReadError::Blocking(self.tx_idx - 1))
+                    Err(ReadError::Blocking(self.mv_memory.sender_dependency(
+                        &TxVersion {
+                            tx_idx: self.tx_idx,
+                            tx_incarnation: self.tx_incarnation,
+                        },
+                        false,
+                    )))
                 } else {
                     Err(ReadError::InvalidNonce(self.tx_idx))
                 };
             }
 
-            // Fully evaluate the account and register it to read cache
-            // to later check if they have changed (been written to).
-            if positive_addition {
-                account.balance = account.balance.saturating_add(balance_addition);
-            } else {
-                account.balance = account.balance.saturating_sub(balance_addition);
-            };
-
-            let code_hash = if Some(location_hash) == self.to_hash {
+            let code_hash = if Some(balance_hash) == self.to_hash {
                 self.to_code_hash
             } else {
                 self.get_code_hash(address)?
@@ -410,7 +608,7 @@ impl<S: Storage> Database for VmDb<'_, S> {
                 None
             };
             self.read_accounts
-                .insert(location_hash, (account.clone(), code_hash));
+                .insert(address, (account.clone(), code_hash));
 
             return Ok(Some(AccountInfo {
                 balance: account.balance,
@@ -436,37 +634,36 @@ impl<S: Storage> Database for VmDb<'_, S> {
     }
 
     fn storage(&mut self, address: Address, index: U256) -> Result<U256, Self::Error> {
-        let location_hash = hash_deterministic(MemoryLocation::Storage(address, index));
-
-        let read_origins = self.read_set.entry(location_hash).or_default();
-
-        // Try reading from multi-version data
-        if self.tx_idx > 0
-            && let Some(written_transactions) = self.mv_memory.data.get(&location_hash)
-            && let Some((closest_idx, entry)) =
-                written_transactions.range(..self.tx_idx).next_back()
-        {
-            match entry {
-                MemoryEntry::Data(tx_incarnation, MemoryValue::Storage(value)) => {
-                    Self::push_origin(
-                        read_origins,
-                        ReadOrigin::MvMemory(TxVersion {
-                            tx_idx: *closest_idx,
-                            tx_incarnation: *tx_incarnation,
-                        }),
-                    )?;
-                    return Ok(*value);
+        let location = MemoryLocation::Storage(address, index);
+        let self_destruct = self.get_self_destruct(address)?;
+        let mut addition = U256::ZERO;
+        let value = self.read_lazy_location(
+            hash_deterministic(location),
+            location,
+            self_destruct,
+            |value| {
+                match value {
+                    MemoryValue::LazyStorage(delta) => addition = addition.wrapping_add(*delta),
+                    _ => return Err(ReadError::InvalidMemoryValueType),
                 }
-                MemoryEntry::Estimate => return Err(ReadError::Blocking(*closest_idx)),
-                _ => return Err(ReadError::InvalidMemoryValueType),
+                Ok(())
+            },
+        )?;
+        let value = match value {
+            Some(MemoryValue::Storage(value)) => value,
+            None if self_destruct.is_some() => U256::ZERO,
+            None => {
+                // Fall back to storage
+                if !self.storage.is_ready(&address, Some(&index)) {
+                    return Err(ReadError::Pending);
+                }
+                self.storage
+                    .storage(&address, &index)
+                    .map_err(|err| ReadError::StorageError(err.to_string()))?
             }
-        }
-
-        // Fall back to storage
-        Self::push_origin(read_origins, ReadOrigin::Storage)?;
-        self.storage
-            .storage(&address, &index)
-            .map_err(|err| ReadError::StorageError(err.to_string()))
+            _ => return Err(ReadError::InvalidMemoryValueType),
+        };
+        Ok(value.wrapping_add(addition))
     }
 
     fn block_hash(&mut self, number: u64) -> Result<B256, Self::Error> {
@@ -484,11 +681,94 @@ pub(crate) struct Vm<'a, S: Storage, C: PevmChain> {
     block_env: &'a BlockEnv,
     txs: &'a [C::EvmTx],
     mv_memory: &'a MvMemory,
+    mode: PevmMode,
     beneficiary_location_hash: MemoryLocationHash,
+    // The index of the first of [txs] in [MvMemory], when it holds several blocks.
+    first_tx_idx: TxIdx,
     // Dedicated EVM for the worker, reset before each transaction exectution.
     evm: C::Evm<VmDb<'a, S>>,
 }
 
+// The longest an idle worker waits for the storage to fetch the reads of the
+// parked transactions before resuming them, which park again on the reads
+// that are still pending.
+const PARKED_FETCH_TIMEOUT: Duration = Duration::from_millis(50);
+
+// The most deltas that a commutative read of a storage slot walks before the
+// transaction writes the full value of the slot instead of its delta.
+const MAX_LAZY_STORAGE_DELTAS: usize = 16;
+
+// Executes the transactions scheduled to a worker.
+pub(crate) trait TxExecutor {
+    fn execute(&mut self, tx_version: &TxVersion) -> Result<VmExecutionResult, VmExecutionError>;
+
+    // Fetch the state that a transaction likely reads from the storage, for
+    // idle workers to warm caching storages before it is executed.
+    fn prefetch_tx(&mut self, _tx_idx: TxIdx) {}
+
+    // Fetch an account and its storage value at [index] if given from the
+    // storage, for idle workers to warm caching storages before it is read.
+    fn prefetch(&mut self, _address: &Address, _index: Option<&U256>) {}
+
+    // Wait for the storage to fetch the reads that parked transactions, for
+    // the idle worker resuming them.
+    fn wait_for_fetches(&mut self) {}
+}
+
+impl<S: Storage, C: PevmChain> Drop for Vm<'_, S, C> {
+    fn drop(&mut self) {
+        let db = self.evm.ctx().db_mut();
+        let mut read_set = std::mem::take(&mut db.read_set);
+        let mut read_accounts = std::mem::take(&mut db.read_accounts);
+        read_set.clear();
+        read_accounts.clear();
+        VM_BUFFERS.set(Some((read_set, read_accounts)));
+    }
+}
+
+impl<S: Storage, C: PevmChain> TxExecutor for Vm<'_, S, C> {
+    fn execute(&mut self, tx_version: &TxVersion) -> Result<VmExecutionResult, VmExecutionError> {
+        Vm::execute(self, tx_version)
+    }
+
+    fn prefetch_tx(&mut self, tx_idx: TxIdx) {
+        // SAFETY: A correct scheduler would guarantee this index to be inbound.
+        let tx = self
+            .chain
+            .tx_env(unsafe { self.txs.get_unchecked(tx_idx - self.first_tx_idx) });
+        self.prefetch(&tx.caller, None);
+        if let Some(to) = tx.kind.to() {
+            self.prefetch(to, None);
+        }
+        for item in tx.access_list.iter() {
+            self.prefetch(&item.address, None);
+            for key in &item.storage_keys {
+                self.prefetch(&item.address, Some(&(*key).into()));
+            }
+        }
+    }
+
+    fn prefetch(&mut self, address: &Address, index: Option<&U256>) {
+        // Errors are left to the executions that read the state.
+        let storage = self.evm.ctx().db().storage;
+        if let Ok(Some(code_hash)) = storage.code_hash(address) {
+            let _ = storage.code_by_hash(&code_hash);
+        }
+        let _ = storage.basic(address);
+        if let Some(index) = index {
+            let _ = storage.storage(address, index);
+        }
+    }
+
+    fn wait_for_fetches(&mut self) {
+        self.evm
+            .ctx()
+            .db()
+            .storage
+            .wait_for_fetches(PARKED_FETCH_TIMEOUT);
+    }
+}
+
 impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
     pub(crate) fn new(
         chain: &'a C,
@@ -497,24 +777,32 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
         txs: &'a [C::EvmTx],
         storage: &'a S,
         mv_memory: &'a MvMemory,
+        mode: PevmMode,
     ) -> Self {
+        // Unless it is a raw transfer that is lazy updated, we'll
+        // read at least from the sender and recipient accounts.
+        let (read_set, read_accounts) = VM_BUFFERS.take().unwrap_or_else(|| {
+            (
+                ReadSet::with_capacity_and_hasher(2, BuildIdentityHasher::default()),
+                ReadAccounts::with_capacity_and_hasher(2, BuildSuffixHasher::default()),
+            )
+        });
         // The DB is initialised with mock values; each transaction execution
         // [VmDb::set_tx] the intended transaction before executing.
         let db = VmDb {
             storage,
             mv_memory,
             tx_idx: 0,
-            // SAFETY: txs is non-empty (checked by the caller before spawning threads).
-            tx: chain.tx_env(unsafe { txs.get_unchecked(0) }),
+            tx_incarnation: 0,
+            tx: &SYSTEM_TX,
             from_hash: 0,
             to_hash: None,
             to_code_hash: None,
             is_lazy: false,
+            allow_lazy: mode == PevmMode::Sync,
             has_nonce: true,
-            // Unless it is a raw transfer that is lazy updated, we'll
-            // read at least from the sender and recipient accounts.
-            read_set: ReadSet::with_capacity_and_hasher(2, BuildIdentityHasher::default()),
-            read_accounts: HashMap::with_capacity_and_hasher(2, BuildIdentityHasher::default()),
+            read_set,
+            read_accounts,
         };
         Self {
             chain,
@@ -522,13 +810,180 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
             block_env,
             txs,
             mv_memory,
-            beneficiary_location_hash: hash_deterministic(MemoryLocation::Basic(
+            mode,
+            beneficiary_location_hash: hash_deterministic(MemoryLocation::Balance(
                 block_env.beneficiary,
             )),
+            first_tx_idx: 0,
+            // Unlike the read buffers, the EVM is rebuilt for each block: its type
+            // borrows the storage and multi-version memory of the block through
+            // [VmDb], so it cannot be kept on the worker past the block. Building
+            // it takes about 30µs, mostly to preallocate its call frames.
             evm: chain.build_evm(spec_id, block_env.clone(), db),
         }
     }
 
+    // Execute [txs] at an offset in [MvMemory], after the transactions and
+    // system calls of previous blocks.
+    pub(crate) const fn with_first_tx_idx(mut self, first_tx_idx: TxIdx) -> Self {
+        self.first_tx_idx = first_tx_idx;
+        self
+    }
+
+    // Execute a system call of the block like a transaction, reading from and
+    // writing to the multi-version memory. Also return the output of the
+    // call, for requests.
+    pub(crate) fn execute_system_call(
+        &mut self,
+        tx_version: &TxVersion,
+        call: &SystemCall,
+    ) -> Result<(VmExecutionResult, SystemCallOutput), VmExecutionError> {
+        let flags = if tx_version.tx_idx > 0 {
+            FinishExecFlags::NeedValidation
+        } else {
+            FinishExecFlags::empty()
+        };
+        {
+            let ctx = self.evm.ctx();
+            ctx.db_mut()
+                .set_tx(
+                    tx_version,
+                    &SYSTEM_TX,
+                    hash_deterministic(MemoryLocation::Balance(SYSTEM_ADDRESS)),
+                    None,
+                    false,
+                )
+                .map_err(VmExecutionError::from)?;
+            ctx.journal_mut().clear();
+        }
+
+        // Calls to contracts without code are skipped. The contract read is
+        // still recorded, to re-execute the call if a lower transaction
+        // deploys the contract.
+        let db = self.evm.ctx().db_mut();
+        if db
+            .basic(call.address)
+            .map_err(VmExecutionError::from)?
+            .is_none_or(|account| account.code_hash == KECCAK_EMPTY)
+        {
+            self.mv_memory
+                .record(tx_version, &mut db.read_set, WriteSet::new())
+                .map_err(VmExecutionError::from)?;
+            return Ok((
+                VmExecutionResult {
+                    execution_result: Ok(PevmTxExecutionResult {
+                        receipt: Receipt::default(),
+                        state: EvmStateTransitions::default(),
+                    }),
+                    flags,
+                },
+                SystemCallOutput::MissingCode,
+            ));
+        }
+
+        let ResultAndState { result, mut state } =
+            match self.evm.system_call(call.address, call.data.clone()) {
+                Ok(result_and_state) => result_and_state,
+                Err(EVMError::Database(read_error)) => {
+                    return Err(VmExecutionError::from(read_error));
+                }
+                Err(err) => {
+                    return Err(VmExecutionError::ExecutionError(execution_error(
+                        self.chain, err,
+                    )));
+                }
+            };
+        // System calls neither charge the caller nor reward the beneficiary,
+        // but revm still loads and marks them as touched.
+        state.remove(&SYSTEM_ADDRESS);
+        state.remove(&self.block_env.beneficiary);
+
+        let db = self.evm.ctx().db_mut();
+        let mut write_set = WriteSet::new();
+        for (address, account) in &state {
+            if account.is_touched() && !account.is_empty() {
+                write_account_fields(
+                    &mut write_set,
+                    *address,
+                    db.read_accounts.get(address).map(|(basic, _)| basic),
+                    &account.info,
+                );
+            }
+            for (slot, value) in account.changed_storage_slots() {
+                let location = MemoryLocation::Storage(*address, *slot);
+                write_set.push((
+                    hash_deterministic(location),
+                    location,
+                    MemoryValue::Storage(value.present_value),
+                ));
+            }
+        }
+
+        let mut flags = flags;
+        if self
+            .mv_memory
+            .record(tx_version, &mut db.read_set, write_set)
+            .map_err(VmExecutionError::from)?
+        {
+            flags |= FinishExecFlags::WroteNewLocation;
+        }
+
+        let output = match &result {
+            ExecutionResult::Success { output, .. } => {
+                SystemCallOutput::Success(output.data().clone())
+            }
+            _ => SystemCallOutput::Failure,
+        };
+        Ok((
+            VmExecutionResult {
+                execution_result: Ok(PevmTxExecutionResult::from_revm(
+                    self.chain,
+                    self.spec_id,
+                    ResultAndState::new(result, state),
+                )),
+                flags,
+            },
+            output,
+        ))
+    }
+
+    // Credit end-of-block balance increments as lazy updates, which are fully
+    // evaluated into the result at the end of execution.
+    pub(crate) fn execute_balance_increments(
+        &self,
+        tx_version: &TxVersion,
+        balance_increments: &[(Address, U256)],
+    ) -> Result<VmExecutionResult, VmExecutionError> {
+        self.mv_memory
+            .add_lazy_addresses(balance_increments.iter().map(|(address, _)| *address));
+        let write_set = balance_increments
+            .iter()
+            .map(|(address, amount)| {
+                let location = MemoryLocation::Balance(*address);
+                (
+                    hash_deterministic(location),
+                    location,
+                    MemoryValue::LazyRecipient(*amount),
+                )
+            })
+            .collect();
+        let flags = if self
+            .mv_memory
+            .record(tx_version, &mut ReadSet::default(), write_set)?
+        {
+            FinishExecFlags::WroteNewLocation
+        } else {
+            FinishExecFlags::empty()
+        };
+        Ok(VmExecutionResult {
+            execution_result: Ok(PevmTxExecutionResult {
+                receipt: Receipt::default(),
+                state: EvmStateTransitions::default(),
+            }),
+            flags,
+        })
+    }
+
     // Execute a transaction. This can read from memory but cannot modify any state.
     // A successful execution returns:
     //   - A write-set consisting of memory locations and their updated values.
@@ -550,23 +1005,26 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
         tx_version: &TxVersion,
     ) -> Result<VmExecutionResult, VmExecutionError> {
         // SAFETY: A correct scheduler would guarantee this index to be inbound.
-        let full_tx = unsafe { self.txs.get_unchecked(tx_version.tx_idx) };
+        let full_tx = unsafe {
+            self.txs
+                .get_unchecked(tx_version.tx_idx - self.first_tx_idx)
+        };
         let tx = self.chain.tx_env(full_tx);
 
-        let from_hash = hash_deterministic(MemoryLocation::Basic(tx.caller));
+        let from_hash = hash_deterministic(MemoryLocation::Balance(tx.caller));
         let to_hash = tx
             .kind
             .to()
-            .map(|to| hash_deterministic(MemoryLocation::Basic(*to)));
+            .map(|to| hash_deterministic(MemoryLocation::Balance(*to)));
 
-        let has_nonce = self.chain.has_nonce(&mut self.evm, full_tx);
+        let has_nonce = self.chain.has_nonce(&mut self.evm, full_tx) && self.mode == PevmMode::Sync;
 
         // Prepare state for execution
         {
             let ctx = self.evm.ctx();
 
             ctx.db_mut()
-                .set_tx(tx_version.tx_idx, tx, from_hash, to_hash, has_nonce)
+                .set_tx(tx_version, tx, from_hash, to_hash, has_nonce)
                 .map_err(VmExecutionError::from)?;
 
             ctx.set_tx(full_tx.clone());
@@ -576,34 +1034,55 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
             ctx.journal_mut().clear();
         }
 
-        match NoBeneficiaryHandler::<C, _>::default().run(&mut self.evm) {
+        // Contract calls likely to conflict with other transactions are
+        // inspected for the storage slots that they only add constants to,
+        // whose writes commute with those of other transactions.
+        let mut is_inspected = false;
+        if self.evm.ctx().db().to_code_hash.is_some()
+            && self.mv_memory.is_inspected(tx_version.tx_idx)
+            && let Some(inspector) = self.evm.inspector().commutative()
+        {
+            inspector.reset();
+            is_inspected = true;
+        }
+        let exec_result = if is_inspected {
+            NoBeneficiaryHandler::<C, _>::default().inspect_run(&mut self.evm)
+        } else {
+            NoBeneficiaryHandler::<C, _>::default().run(&mut self.evm)
+        };
+        match exec_result {
             Ok(exec_result) => {
                 // There are at least six locations most of the time: the sender,
                 // the recipient, and up to four fee recipients (beneficiary, base fee,
                 // L1 fee, operator fee on OP Stack chains).
                 let mut write_set = WriteSet::with_capacity(6);
 
-                let ctx = self.evm.ctx();
+                let (ctx, inspector) = self.evm.ctx_inspector();
 
                 let result_and_state =
                     ResultAndState::new(exec_result, ctx.journal_mut().finalize());
 
                 for (address, account) in &result_and_state.state {
                     if account.is_selfdestructed() {
-                        // TODO: Also write [SelfDestructed] to the basic location?
-                        // For now we are betting on [code_hash] triggering the sequential
-                        // fallback when we read a self-destructed contract.
-                        write_set.push((
-                            hash_deterministic(MemoryLocation::CodeHash(*address)),
-                            MemoryValue::SelfDestructed,
-                        ));
+                        // Later transactions read the account as cleared, and its
+                        // storage as empty, until they write to it again.
+                        for location in [
+                            MemoryLocation::Balance(*address),
+                            MemoryLocation::Nonce(*address),
+                            MemoryLocation::CodeHash(*address),
+                            MemoryLocation::SelfDestruct(*address),
+                        ] {
+                            write_set.push((
+                                hash_deterministic(location),
+                                location,
+                                MemoryValue::SelfDestructed,
+                            ));
+                        }
                         continue;
                     }
 
                     if account.is_touched() {
-                        let account_location_hash =
-                            hash_deterministic(MemoryLocation::Basic(*address));
-                        let read_account = ctx.db().read_accounts.get(&account_location_hash);
+                        let read_account = ctx.db().read_accounts.get(address);
 
                         let has_code = !account.info.is_empty_code_hash();
                         let is_new_code = has_code
@@ -618,14 +1097,23 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                             })
                         {
                             if ctx.db().is_lazy {
-                                if account_location_hash == from_hash {
+                                let location = MemoryLocation::Balance(*address);
+                                if address == &tx.caller {
                                     write_set.push((
-                                        account_location_hash,
+                                        from_hash,
+                                        location,
                                         MemoryValue::LazySender(U256::MAX - account.info.balance),
                                     ));
-                                } else if Some(account_location_hash) == to_hash {
+                                    let location = MemoryLocation::Nonce(*address);
                                     write_set.push((
-                                        account_location_hash,
+                                        hash_deterministic(location),
+                                        location,
+                                        MemoryValue::Nonce(account.info.nonce),
+                                    ));
+                                } else if tx.kind.to() == Some(address) {
+                                    write_set.push((
+                                        to_hash.unwrap(),
+                                        location,
                                         MemoryValue::LazyRecipient(tx.value),
                                     ));
                                 }
@@ -641,20 +1129,21 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                             else if !self.chain.is_eip_161_enabled(self.spec_id)
                                 || !account.is_empty()
                             {
-                                write_set.push((
-                                    account_location_hash,
-                                    MemoryValue::Basic(AccountBasic {
-                                        balance: account.info.balance,
-                                        nonce: account.info.nonce,
-                                    }),
-                                ));
+                                write_account_fields(
+                                    &mut write_set,
+                                    *address,
+                                    read_account.map(|(basic, _)| basic),
+                                    &account.info,
+                                );
                             }
                         }
 
                         // Write new contract
                         if is_new_code {
+                            let location = MemoryLocation::CodeHash(*address);
                             write_set.push((
-                                hash_deterministic(MemoryLocation::CodeHash(*address)),
+                                hash_deterministic(location),
+                                location,
                                 MemoryValue::CodeHash(account.info.code_hash),
                             ));
                             self.mv_memory
@@ -666,13 +1155,71 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
 
                     // TODO: We should move this changed check to our read set like for account info?
                     for (slot, value) in account.changed_storage_slots() {
+                        let location = MemoryLocation::Storage(*address, *slot);
                         write_set.push((
-                            hash_deterministic(MemoryLocation::Storage(*address, *slot)),
+                            hash_deterministic(location),
+                            location,
                             MemoryValue::Storage(value.present_value),
                         ));
                     }
                 }
 
+                // Record the slots that the transaction only added constants to as
+                // deltas, and validate their reads by the outcomes of the comparisons
+                // made on them instead of by their origins.
+                if let Some(inspector) = inspector.commutative()
+                    && is_inspected
+                    && result_and_state.result.is_success()
+                {
+                    let db = ctx.db_mut();
+                    for slot in inspector.commutative_slots() {
+                        // The storage of new and self-destructed accounts is not
+                        // read from [VmDb].
+                        if result_and_state
+                            .state
+                            .get(&slot.address)
+                            .is_none_or(|account| {
+                                account.is_created() || account.is_selfdestructed()
+                            })
+                        {
+                            continue;
+                        }
+                        let location = MemoryLocation::Storage(slot.address, slot.index);
+                        let Some(read_origins) = db.read_set.get_mut(&hash_deterministic(location))
+                        else {
+                            continue;
+                        };
+                        // Slots cleared by a self-destruct are not validated against
+                        // their storage values. Slots read through many deltas get a
+                        // full write instead, for later reads and validations to stop
+                        // at it rather than walk all the deltas of the block.
+                        if matches!(read_origins.last(), Some(ReadOrigin::SelfDestruct(_)))
+                            || read_origins.len() > MAX_LAZY_STORAGE_DELTAS
+                        {
+                            continue;
+                        }
+                        if !db.storage.is_ready(&slot.address, Some(&slot.index)) {
+                            continue;
+                        }
+                        let Ok(storage_value) = db.storage.storage(&slot.address, &slot.index)
+                        else {
+                            continue;
+                        };
+                        read_origins.clear();
+                        read_origins.push(ReadOrigin::Commutative(Box::new(CommutativeRead {
+                            storage_value,
+                            constraints: slot.constraints.clone(),
+                        })));
+                        if let Some((.., value)) = write_set
+                            .iter_mut()
+                            .find(|(_, written_location, _)| written_location == &location)
+                        {
+                            *value = MemoryValue::LazyStorage(slot.delta);
+                            self.mv_memory.add_lazy_storage(slot.address, slot.index);
+                        }
+                    }
+                }
+
                 // Rewards
                 let mut gas_price = if let Some(priority_fee) = tx.gas_priority_fee {
                     std::cmp::min(
@@ -686,20 +1233,21 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                     gas_price = gas_price.saturating_sub(self.block_env.basefee as u128);
                 }
                 let rewards = self.chain.get_rewards(
-                    self.beneficiary_location_hash,
+                    self.block_env.beneficiary,
                     U256::from(result_and_state.result.tx_gas_used()),
                     U256::from(gas_price),
                     self.block_env.basefee,
                     full_tx,
                 );
                 for (recipient, amount) in rewards {
-                    if let Some((_, value)) = write_set
+                    let recipient_location = MemoryLocation::Balance(recipient);
+                    if let Some((.., value)) = write_set
                         .iter_mut()
-                        .find(|(location, _)| location == &recipient)
+                        .find(|(_, location, _)| location == &recipient_location)
                     {
                         match value {
-                            MemoryValue::Basic(basic) => {
-                                basic.balance = basic.balance.saturating_add(amount)
+                            MemoryValue::Balance(balance) => {
+                                *balance = balance.saturating_add(amount)
                             }
                             MemoryValue::LazySender(subtraction) => {
                                 *subtraction = subtraction.saturating_sub(amount)
@@ -707,17 +1255,27 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                             MemoryValue::LazyRecipient(addition) => {
                                 *addition = addition.saturating_add(amount)
                             }
+                            // Rewards to an account that the transaction self-destructed
+                            // are cleared with it.
+                            MemoryValue::SelfDestructed => {}
                             _ => return Err(ReadError::InvalidMemoryValueType.into()),
                         }
                     } else {
-                        write_set.push((recipient, MemoryValue::LazyRecipient(amount)));
+                        let recipient_location_hash = if recipient == self.block_env.beneficiary {
+                            self.beneficiary_location_hash
+                        } else {
+                            hash_deterministic(recipient_location)
+                        };
+                        write_set.push((
+                            recipient_location_hash,
+                            recipient_location,
+                            MemoryValue::LazyRecipient(amount),
+                        ));
                     }
                 }
 
-                let (is_lazy, read_set) = {
-                    let db = ctx.db_mut();
-                    (db.is_lazy, std::mem::take(&mut db.read_set))
-                };
+                let db = ctx.db_mut();
+                let is_lazy = db.is_lazy;
 
                 if is_lazy {
                     self.mv_memory
@@ -730,37 +1288,59 @@ impl<'a, S: Storage, C: PevmChain> Vm<'a, S, C> {
                     FinishExecFlags::empty()
                 };
 
-                if self.mv_memory.record(tx_version, read_set, write_set) {
+                if self
+                    .mv_memory
+                    .record(tx_version, &mut db.read_set, write_set)
+                    .map_err(VmExecutionError::from)?
+                {
                     flags |= FinishExecFlags::WroteNewLocation;
                 }
 
                 Ok(VmExecutionResult {
-                    execution_result: PevmTxExecutionResult::from_revm(
+                    execution_result: Ok(PevmTxExecutionResult::from_revm(
                         self.chain,
                         self.spec_id,
                         result_and_state,
-                    ),
+                    )),
                     flags,
                 })
             }
             Err(EVMError::Database(read_error)) => Err(VmExecutionError::from(read_error)),
-            Err(err) => {
-                // Optimistically retry in case some previous internal transactions send
-                // more fund to the sender but hasn't been executed yet.
-                // TODO: Let users define this behaviour through a mode enum or something.
-                // Since this retry is safe for syncing canonical blocks but can deadlock
-                // on new or faulty blocks. We can skip the transaction for new blocks and
-                // error out after a number of tries for the latter.
-                if tx_version.tx_idx > 0
-                    && matches!(
-                        err,
-                        EVMError::Transaction(
-                            InvalidTransaction::LackOfFundForMaxFee { .. }
-                                | InvalidTransaction::NonceTooHigh { .. }
-                        )
+            // The transaction is excluded from the block as if it had not been
+            // executed. Its read set is still recorded so it is re-executed when a
+            // lower transaction changes what it read, like funding its sender.
+            Err(err) if self.mode.excludes(&err) => {
+                let flags = if tx_version.tx_idx > 0 {
+                    FinishExecFlags::NeedValidation
+                } else {
+                    FinishExecFlags::empty()
+                };
+                self.mv_memory
+                    .record(
+                        tx_version,
+                        &mut self.evm.ctx().db_mut().read_set,
+                        WriteSet::new(),
                     )
-                {
-                    Err(VmExecutionError::Blocking(tx_version.tx_idx - 1))
+                    .map_err(VmExecutionError::from)?;
+                Ok(VmExecutionResult {
+                    execution_result: Err(err),
+                    flags,
+                })
+            }
+            Err(err) => {
+                // Optimistically retry in case some previous transactions fund the
+                // sender or fill its nonce gap but haven't been executed yet. Faulty
+                // blocks whose checks never pass fall back to sequential execution
+                // after a bounded number of retries (see [Pevm::try_execute]).
+                let lacks_funds = match err {
+                    EVMError::Transaction(InvalidTransaction::LackOfFundForMaxFee { .. }) => true,
+                    EVMError::Transaction(InvalidTransaction::NonceTooHigh { .. }) => false,
+                    _ => return Err(VmExecutionError::ExecutionError(err)),
+                };
+                if tx_version.tx_idx > 0 {
+                    Err(VmExecutionError::Blocking(
+                        self.mv_memory.sender_dependency(tx_version, lacks_funds),
+                    ))
                 } else {
                     Err(VmExecutionError::ExecutionError(err))
                 }
@@ -794,3 +1374,33 @@ impl<C: PevmChain, DB: Database> Handler for NoBeneficiaryHandler<C, DB> {
         Ok(())
     }
 }
+
+impl<C: PevmChain, DB: Database> InspectorHandler for NoBeneficiaryHandler<C, DB> {
+    type IT = EthInterpreter;
+}
+
+// Write the balance and nonce of an account to their locations, skipping
+// those that are the same as [read_account] when the transaction read it.
+fn write_account_fields(
+    write_set: &mut WriteSet,
+    address: Address,
+    read_account: Option<&AccountBasic>,
+    info: &AccountInfo,
+) {
+    if read_account.is_none_or(|basic| basic.balance != info.balance) {
+        let location = MemoryLocation::Balance(address);
+        write_set.push((
+            hash_deterministic(location),
+            location,
+            MemoryValue::Balance(info.balance),
+        ));
+    }
+    if read_account.is_none_or(|basic| basic.nonce != info.nonce) {
+        let location = MemoryLocation::Nonce(address);
+        write_set.push((
+            hash_deterministic(location),
+            location,
+            MemoryValue::Nonce(info.nonce),
+        ));
+    }
+}
@@ -0,0 +1,114 @@
+use std::num::NonZeroUsize;
+
+use rayon::{ThreadPool, ThreadPoolBuilder};
+
+// A pool of long-lived worker threads that execute blocks in parallel, so
+// blocks do not pay for spawning threads. Each thread also keeps its VM
+// buffers across blocks (see [crate::vm::Vm::new]).
+#[derive(Debug)]
+pub(crate) struct WorkerPool {
+    pool: ThreadPool,
+}
+
+impl WorkerPool {
+    // Spawn [num_threads] worker threads, each pinned to one of the CPU cores
+    // available to the process if [pinned]. Return [None] if the operating
+    // system fails to spawn them.
+    pub(crate) fn new(num_threads: NonZeroUsize, pinned: bool) -> Option<Self> {
+        let mut builder = ThreadPoolBuilder::new()
+            .num_threads(num_threads.get())
+            .thread_name(|index| format!("pevm-worker-{index}"));
+        if pinned {
+            let cpus = affinity::available_cpus();
+            if !cpus.is_empty() {
+                builder = builder.start_handler(move |index| {
+                    affinity::pin_current_thread(cpus[index % cpus.len()]);
+                });
+            }
+        }
+        builder.build().ok().map(|pool| Self { pool })
+    }
+
+    pub(crate) fn num_threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    // Run [worker] on [num_workers] threads of the pool concurrently and
+    // [helper] on the others, returning when all of them have returned.
+    pub(crate) fn run(
+        &self,
+        num_workers: NonZeroUsize,
+        worker: impl Fn() + Sync,
+        helper: impl Fn() + Sync,
+    ) {
+        self.pool.broadcast(|ctx| {
+            if ctx.index() < num_workers.get() {
+                worker();
+            } else {
+                helper();
+            }
+        });
+    }
+
+    // Run [helper] on every thread of the pool while the calling thread runs
+    // [op], returning the output of [op] once the helpers have returned too.
+    pub(crate) fn run_beside<R>(&self, helper: &dyn Helper, op: impl FnOnce() -> R) -> R {
+        self.pool.in_place_scope(|scope| {
+            scope.spawn_broadcast(|_, _| helper.help());
+            let output = op();
+            helper.stop();
+            output
+        })
+    }
+}
+
+// A task for the threads of the pool that no worker needs, like prefetching
+// the state that the workers will read.
+pub(crate) trait Helper: Sync {
+    // Help until there is nothing left to help with or [Self::stop] is called.
+    fn help(&self);
+
+    // Make the helping threads return soon, as the workers are done.
+    fn stop(&self);
+}
+
+#[cfg(target_os = "linux")]
+mod affinity {
+    use std::mem::{size_of, zeroed};
+
+    // The CPU cores that the process is allowed to run on.
+    pub(super) fn available_cpus() -> Vec<usize> {
+        // SAFETY: [cpu_set_t] is a plain bit set, valid when zeroed, and
+        // [sched_getaffinity] writes at most its size.
+        unsafe {
+            let mut set: libc::cpu_set_t = zeroed();
+            if libc::sched_getaffinity(0, size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+                return Vec::new();
+            }
+            (0..libc::CPU_SETSIZE as usize)
+                .filter(|cpu| libc::CPU_ISSET(*cpu, &set))
+                .collect()
+        }
+    }
+
+    // Pin the calling thread to [cpu]. Failing to is only a missed
+    // optimization, so the error is ignored.
+    pub(super) fn pin_current_thread(cpu: usize) {
+        // SAFETY: As above, and [sched_setaffinity] only reads the set.
+        unsafe {
+            let mut set: libc::cpu_set_t = zeroed();
+            libc::CPU_SET(cpu, &mut set);
+            libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set);
+        }
+    }
+}
+
+// Pinning is only supported on Linux for now.
+#[cfg(not(target_os = "linux"))]
+mod affinity {
+    pub(super) const fn available_cpus() -> Vec<usize> {
+        Vec::new()
+    }
+
+    pub(super) fn pin_current_thread(_cpu: usize) {}
+}
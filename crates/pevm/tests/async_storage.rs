@@ -0,0 +1,221 @@
+//! Test executing blocks on storage that is fetched asynchronously.
+
+#![cfg(feature = "async-storage")]
+
+#[path = "./common/mod.rs"]
+pub mod common;
+
+#[path = "./erc20/mod.rs"]
+pub mod erc20;
+
+use std::{fmt, num::NonZeroUsize, sync::Arc, thread};
+
+use alloy_primitives::{Address, B256, TxKind, U256};
+use pevm::{
+    AccountBasic, AsyncStorage, EvmAccount, EvmCode, InMemoryStorage, Pevm, PevmError,
+    PrefetchStorage, Storage, chain::PevmEthereum,
+};
+use revm::{
+    context::{BlockEnv, TxEnv},
+    database::DBErrorMarker,
+};
+use tokio::{runtime::Runtime, task};
+
+// An in-memory storage that yields to the runtime before every read, like
+// waiting for disk or network I/O.
+#[derive(Debug)]
+struct SlowStorage(InMemoryStorage);
+
+impl AsyncStorage for SlowStorage {
+    type Error = <InMemoryStorage as Storage>::Error;
+
+    async fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        task::yield_now().await;
+        self.0.basic(address)
+    }
+
+    async fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        task::yield_now().await;
+        self.0.code_hash(address)
+    }
+
+    async fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        task::yield_now().await;
+        self.0.code_by_hash(code_hash)
+    }
+
+    async fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        task::yield_now().await;
+        self.0.has_storage(address)
+    }
+
+    async fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        task::yield_now().await;
+        self.0.storage(address, index)
+    }
+
+    async fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        task::yield_now().await;
+        self.0.block_hash(number)
+    }
+}
+
+// A storage that is unreachable, like a dead RPC.
+#[derive(Debug)]
+struct FailingStorage;
+
+#[derive(Debug)]
+struct Unreachable;
+
+impl fmt::Display for Unreachable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unreachable storage")
+    }
+}
+
+impl std::error::Error for Unreachable {}
+
+impl DBErrorMarker for Unreachable {}
+
+impl AsyncStorage for FailingStorage {
+    type Error = Unreachable;
+
+    async fn basic(&self, _: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        task::yield_now().await;
+        Err(Unreachable)
+    }
+
+    async fn code_hash(&self, _: &Address) -> Result<Option<B256>, Self::Error> {
+        task::yield_now().await;
+        Err(Unreachable)
+    }
+
+    async fn code_by_hash(&self, _: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        task::yield_now().await;
+        Err(Unreachable)
+    }
+
+    async fn has_storage(&self, _: &Address) -> Result<bool, Self::Error> {
+        task::yield_now().await;
+        Err(Unreachable)
+    }
+
+    async fn storage(&self, _: &Address, _: &U256) -> Result<U256, Self::Error> {
+        task::yield_now().await;
+        Err(Unreachable)
+    }
+
+    async fn block_hash(&self, _: &u64) -> Result<B256, Self::Error> {
+        task::yield_now().await;
+        Err(Unreachable)
+    }
+}
+
+// Execute [txs] in parallel on cold prefetch storage, then sequentially on
+// in-memory storage to compare.
+fn test_prefetch_storage(storage: InMemoryStorage, txs: Vec<TxEnv>) {
+    let chain = PevmEthereum::mainnet();
+    let runtime = Runtime::new().unwrap();
+    let prefetch_storage = PrefetchStorage::new(SlowStorage(storage), runtime.handle().clone());
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let parallel_result = Pevm::default().execute_revm_parallel(
+        &chain,
+        &prefetch_storage,
+        Default::default(),
+        BlockEnv::default(),
+        txs.clone(),
+        concurrency_level,
+    );
+    assert!(parallel_result.is_ok());
+    assert_eq!(
+        pevm::execute_revm_sequential(
+            &chain,
+            &prefetch_storage.inner().0,
+            Default::default(),
+            BlockEnv::default(),
+            txs,
+        ),
+        parallel_result,
+    );
+}
+
+#[test]
+fn prefetch_raw_transfers() {
+    const BLOCK_SIZE: usize = 5_000;
+    // Mock senders after the precompile addresses, which raw transfers cannot pay.
+    const FIRST_SENDER: usize = 0x100;
+    test_prefetch_storage(
+        InMemoryStorage::new(
+            (FIRST_SENDER..FIRST_SENDER + BLOCK_SIZE)
+                .map(common::mock_account)
+                .chain([(Address::ZERO, EvmAccount::default())]) // Beneficiary
+                .collect(),
+            Default::default(),
+            Default::default(),
+        ),
+        // Transfer to the next sender to read accounts that have not been
+        // read by other transactions.
+        (0..BLOCK_SIZE)
+            .map(|i| {
+                let (caller, account) = common::mock_account(FIRST_SENDER + i);
+                let (to, _) = common::mock_account(FIRST_SENDER + (i + 1) % BLOCK_SIZE);
+                TxEnv {
+                    caller,
+                    nonce: account.nonce,
+                    kind: TxKind::Call(to),
+                    value: U256::from(1),
+                    gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                    gas_price: 1,
+                    ..TxEnv::default()
+                }
+            })
+            .collect(),
+    );
+}
+
+#[test]
+fn prefetch_erc20_transfers() {
+    let (mut state, bytecodes, txs) = erc20::generate_cluster(100, 10, 2);
+    state.insert(Address::ZERO, EvmAccount::default()); // Beneficiary
+    test_prefetch_storage(
+        InMemoryStorage::new(state, Arc::new(bytecodes), Default::default()),
+        txs,
+    );
+}
+
+#[test]
+fn prefetch_failing_storage() {
+    const BLOCK_SIZE: usize = 100;
+    // Mock senders after the precompile addresses, which raw transfers cannot pay.
+    const FIRST_SENDER: usize = 0x100;
+    let runtime = Runtime::new().unwrap();
+    let storage = PrefetchStorage::new(FailingStorage, runtime.handle().clone());
+    let txs = (0..BLOCK_SIZE)
+        .map(|i| {
+            let (caller, account) = common::mock_account(FIRST_SENDER + i);
+            TxEnv {
+                caller,
+                nonce: account.nonce,
+                kind: TxKind::Call(caller),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: 1,
+                ..TxEnv::default()
+            }
+        })
+        .collect::<Vec<_>>();
+    // The failed fetches surface as storage errors instead of parking the
+    // transactions forever.
+    let result = Pevm::default().execute_revm_parallel(
+        &PevmEthereum::mainnet(),
+        &storage,
+        Default::default(),
+        BlockEnv::default(),
+        txs,
+        NonZeroUsize::new(4).unwrap(),
+    );
+    assert!(
+        matches!(result, Err(PevmError::ExecutionError(ref err)) if err.to_string().contains("unreachable storage")),
+        "{result:?}"
+    );
+}
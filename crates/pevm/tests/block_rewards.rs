@@ -0,0 +1,362 @@
+//! Test the balance increments at the end of a block: pre-merge block & ommer
+//! rewards, and post-Shanghai withdrawals.
+
+use std::{num::NonZeroUsize, thread};
+
+use alloy_consensus::{Signed, TxLegacy};
+use alloy_eips::eip4895::{Withdrawal, Withdrawals};
+use alloy_primitives::{Address, B256, Bytes, Signature, TxKind, U256};
+use alloy_rpc_types_eth::{Block, BlockTransactions, Header};
+use pevm::{
+    BlockFeatures, ChainState, ExecutionPolicy, ExecutionStrategy, InMemoryStorage, Pevm,
+    PevmError,
+    chain::{PevmChain, PevmEthereum},
+};
+
+pub mod common;
+
+const ETH_TO_WEI: u128 = 1_000_000_000_000_000_000;
+const SHANGHAI_TIMESTAMP: u64 = 1681338455;
+
+fn mock_header(header: alloy_consensus::Header) -> Header {
+    Header {
+        inner: header,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn frontier_block_and_ommer_rewards() {
+    let chain = PevmEthereum::mainnet();
+    let beneficiary = Address::repeat_byte(0x11);
+    let ommer_beneficiary = Address::repeat_byte(0x22);
+    let ommers = vec![mock_header(alloy_consensus::Header {
+        number: 998,
+        beneficiary: ommer_beneficiary,
+        ..Default::default()
+    })];
+    let block = Block {
+        header: mock_header(alloy_consensus::Header {
+            number: 1000,
+            beneficiary,
+            gas_limit: 5_000,
+            ..Default::default()
+        }),
+        uncles: vec![B256::repeat_byte(0x33)],
+        transactions: BlockTransactions::Full(Vec::new()),
+        ..Block::default()
+    };
+    let storage = InMemoryStorage::default();
+    common::test_execute_alloy(&chain, &storage, block.clone(), &ommers, false);
+
+    let result = Pevm::default()
+        .execute_with_ommers(&chain, &storage, &block, &ommers, NonZeroUsize::MIN, true)
+        .unwrap();
+    let balance_of = |address| {
+        result
+            .balance_increment_state
+            .get(&address)
+            .cloned()
+            .flatten()
+            .unwrap()
+            .balance
+    };
+    // 5 ETH plus 1/32 of it for including the ommer.
+    assert_eq!(
+        balance_of(beneficiary),
+        U256::from(5 * ETH_TO_WEI + 5 * ETH_TO_WEI / 32)
+    );
+    // (8 + 998 - 1000) / 8 of 5 ETH.
+    assert_eq!(
+        balance_of(ommer_beneficiary),
+        U256::from(6 * 5 * ETH_TO_WEI / 8)
+    );
+    assert_eq!(result.balance_increment_state.len(), 2);
+}
+
+#[test]
+fn missing_ommer_headers() {
+    let chain = PevmEthereum::mainnet();
+    let beneficiary = Address::repeat_byte(0x11);
+    let block = Block::<alloy_rpc_types_eth::Transaction> {
+        header: mock_header(alloy_consensus::Header {
+            number: 1000,
+            beneficiary,
+            gas_limit: 5_000,
+            ..Default::default()
+        }),
+        uncles: vec![B256::repeat_byte(0x33)],
+        transactions: BlockTransactions::Full(Vec::new()),
+        ..Block::default()
+    };
+    let storage = InMemoryStorage::default();
+    assert!(matches!(
+        Pevm::default()
+            .execute_with_ommers(&chain, &storage, &block, &[], NonZeroUsize::MIN, true,),
+        Err(PevmError::MissingOmmerHeaders)
+    ));
+
+    // Without the ommer headers, only the block's beneficiary is rewarded.
+    let result = Pevm::default()
+        .execute(&chain, &storage, &block, NonZeroUsize::MIN, true)
+        .unwrap();
+    assert_eq!(result.balance_increment_state.len(), 1);
+    assert_eq!(
+        result
+            .balance_increment_state
+            .get(&beneficiary)
+            .cloned()
+            .flatten()
+            .unwrap()
+            .balance,
+        U256::from(5 * ETH_TO_WEI + 5 * ETH_TO_WEI / 32)
+    );
+}
+
+#[test]
+fn pipelined_ommer_rewards() {
+    let chain = PevmEthereum::mainnet();
+    // The beneficiaries are rewarded across blocks, and the second ommer's
+    // beneficiary is the first block's.
+    let beneficiaries = [Address::repeat_byte(0x11), Address::repeat_byte(0x12)];
+    let blocks: Vec<Block> = (0..2)
+        .map(|i| Block {
+            header: mock_header(alloy_consensus::Header {
+                number: 1000 + i,
+                beneficiary: beneficiaries[i as usize],
+                gas_limit: 5_000,
+                ..Default::default()
+            }),
+            uncles: vec![B256::repeat_byte(0x33 + i as u8)],
+            transactions: BlockTransactions::Full(Vec::new()),
+            ..Block::default()
+        })
+        .collect();
+    let ommers = vec![
+        vec![mock_header(alloy_consensus::Header {
+            number: 998,
+            beneficiary: Address::repeat_byte(0x22),
+            ..Default::default()
+        })],
+        vec![mock_header(alloy_consensus::Header {
+            number: 999,
+            beneficiary: beneficiaries[0],
+            ..Default::default()
+        })],
+    ];
+    let storage = InMemoryStorage::default();
+    assert!(matches!(
+        Pevm::default().execute_blocks_with_ommers(
+            &chain,
+            &storage,
+            &blocks,
+            &ommers[..1],
+            NonZeroUsize::MIN
+        ),
+        Err(PevmError::MissingOmmerHeaders)
+    ));
+
+    // The pipelined blocks execute the same as the blocks one by one.
+    for with_ommers in [true, false] {
+        let mut pevm = Pevm::default();
+        let pipelined_results = if with_ommers {
+            pevm.execute_blocks_with_ommers(
+                &chain,
+                &storage,
+                &blocks,
+                &ommers,
+                NonZeroUsize::new(2).unwrap(),
+            )
+        } else {
+            pevm.execute_blocks(&chain, &storage, &blocks, NonZeroUsize::new(2).unwrap())
+        }
+        .unwrap();
+        assert_eq!(pipelined_results.len(), blocks.len());
+        let mut storage = storage.clone();
+        for ((block, ommers), pipelined_result) in blocks.iter().zip(&ommers).zip(pipelined_results)
+        {
+            let result = if with_ommers {
+                pevm.execute_with_ommers(&chain, &storage, block, ommers, NonZeroUsize::MIN, true)
+            } else {
+                pevm.execute(&chain, &storage, block, NonZeroUsize::MIN, true)
+            }
+            .unwrap();
+            assert_eq!(
+                result.balance_increment_state.len(),
+                if with_ommers { 2 } else { 1 }
+            );
+            assert_eq!(result, pipelined_result);
+            storage.apply_state_diff(&result.state_diff);
+        }
+    }
+}
+
+// Always execute blocks in parallel, to test the lazy balance increments.
+#[derive(Debug)]
+struct ParallelPolicy;
+
+impl ExecutionPolicy for ParallelPolicy {
+    fn decide(&self, _: &BlockFeatures, concurrency_level: NonZeroUsize) -> ExecutionStrategy {
+        ExecutionStrategy::Parallel(concurrency_level)
+    }
+}
+
+#[test]
+fn shanghai_withdrawals() {
+    let chain = PevmEthereum::mainnet();
+    let (sender, sender_account) = common::mock_account(1);
+    let recipient = Address::repeat_byte(0x44);
+    let untouched = Address::repeat_byte(0x55);
+    let block = Block {
+        header: mock_header(alloy_consensus::Header {
+            number: 17034870,
+            timestamp: SHANGHAI_TIMESTAMP,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        }),
+        transactions: BlockTransactions::Full(vec![
+            chain.mock_tx(
+                Signed::new_unchecked(
+                    TxLegacy {
+                        chain_id: Some(chain.id()),
+                        nonce: sender_account.nonce,
+                        gas_price: 0,
+                        gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                        to: TxKind::Call(recipient),
+                        value: U256::from(1),
+                        input: Bytes::default(),
+                    },
+                    Signature::new(U256::ZERO, U256::ZERO, false),
+                    B256::default(),
+                )
+                .into(),
+                sender,
+            ),
+        ]),
+        // Withdrawal amounts are in Gwei.
+        withdrawals: Some(Withdrawals::new(vec![
+            Withdrawal {
+                index: 0,
+                validator_index: 0,
+                address: recipient,
+                amount: 5,
+            },
+            Withdrawal {
+                index: 1,
+                validator_index: 1,
+                address: recipient,
+                amount: 3,
+            },
+            Withdrawal {
+                index: 2,
+                validator_index: 2,
+                address: untouched,
+                amount: 0,
+            },
+        ])),
+        ..Block::default()
+    };
+    let storage = InMemoryStorage::new(
+        ChainState::from_iter([(sender, sender_account)]),
+        Default::default(),
+        Default::default(),
+    );
+    common::test_execute_alloy(&chain, &storage, block.clone(), &[], false);
+
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let result = Pevm::default()
+        .execute(&chain, &storage, &block, concurrency_level, false)
+        .unwrap();
+    let recipient_account = result
+        .balance_increment_state
+        .get(&recipient)
+        .cloned()
+        .flatten()
+        .unwrap();
+    // The withdrawals are credited on top of the transferred wei.
+    assert_eq!(
+        recipient_account.balance,
+        U256::from(1 + 8 * 1_000_000_000u64)
+    );
+    // Zero withdrawals don't touch the account, and there are no block rewards.
+    assert_eq!(result.balance_increment_state.len(), 1);
+}
+
+#[test]
+fn parallel_withdrawals_to_transfer_recipients() {
+    let chain = PevmEthereum::mainnet();
+    let recipient = Address::repeat_byte(0x44);
+    let accounts: Vec<_> = (1..=8).map(common::mock_account).collect();
+    let block = Block {
+        header: mock_header(alloy_consensus::Header {
+            number: 17034870,
+            timestamp: SHANGHAI_TIMESTAMP,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        }),
+        transactions: BlockTransactions::Full(
+            accounts
+                .iter()
+                .map(|(sender, account)| {
+                    chain.mock_tx(
+                        Signed::new_unchecked(
+                            TxLegacy {
+                                chain_id: Some(chain.id()),
+                                nonce: account.nonce,
+                                gas_price: 0,
+                                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                                to: TxKind::Call(recipient),
+                                value: U256::from(1),
+                                input: Bytes::default(),
+                            },
+                            Signature::new(U256::ZERO, U256::ZERO, false),
+                            B256::default(),
+                        )
+                        .into(),
+                        *sender,
+                    )
+                })
+                .collect(),
+        ),
+        // Credit the recipient and a sender of the block's transactions.
+        withdrawals: Some(Withdrawals::new(vec![
+            Withdrawal {
+                index: 0,
+                validator_index: 0,
+                address: recipient,
+                amount: 5,
+            },
+            Withdrawal {
+                index: 1,
+                validator_index: 1,
+                address: accounts[0].0,
+                amount: 3,
+            },
+        ])),
+        ..Block::default()
+    };
+    let storage = InMemoryStorage::new(
+        ChainState::from_iter(accounts.iter().cloned()),
+        Default::default(),
+        Default::default(),
+    );
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    let sequential_result = Pevm::default()
+        .execute(&chain, &storage, &block, concurrency_level, true)
+        .unwrap();
+    let parallel_result = Pevm::default()
+        .with_policy(ParallelPolicy)
+        .execute(&chain, &storage, &block, concurrency_level, false)
+        .unwrap();
+    assert_eq!(sequential_result, parallel_result);
+    assert_eq!(
+        parallel_result
+            .balance_increment_state
+            .get(&recipient)
+            .cloned()
+            .flatten()
+            .unwrap()
+            .balance,
+        U256::from(8 + 5 * 1_000_000_000u64)
+    );
+}
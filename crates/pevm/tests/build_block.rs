@@ -0,0 +1,206 @@
+//! Test building blocks from an ordered stream of candidate transactions.
+
+use std::num::NonZeroUsize;
+
+use pevm::{InMemoryStorage, Pevm, chain::PevmEthereum, execute_revm_sequential};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{Address, B256, U256, alloy_primitives::U160, eip4844::GAS_PER_BLOB},
+};
+
+pub mod common;
+
+const CANDIDATES: usize = 1_000;
+
+fn mock_storage() -> InMemoryStorage {
+    InMemoryStorage::new(
+        (0..=CANDIDATES).map(common::mock_account).collect(),
+        Default::default(),
+        Default::default(),
+    )
+}
+
+fn mock_transfer(idx: usize, gas_limit: u64) -> TxEnv {
+    TxEnv {
+        caller: Address::from(U160::from(idx)),
+        nonce: 1,
+        // Skip the precompile addresses that charge more gas.
+        kind: TransactTo::Call(Address::from(U160::from(CANDIDATES + idx))),
+        value: U256::from(1),
+        gas_limit,
+        gas_price: 1,
+        ..TxEnv::default()
+    }
+}
+
+#[test]
+fn gas_limit() {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    let block_env = BlockEnv {
+        // The last transfer must leave room for its reserved gas.
+        gas_limit: 301 * common::RAW_TRANSFER_GAS_LIMIT,
+        ..BlockEnv::default()
+    };
+    // Candidates reserve twice the gas they use, to build in several windows.
+    // Every 10th candidate is unfunded and must be skipped.
+    let candidates = (1..=CANDIDATES)
+        .map(|i| {
+            let mut tx = mock_transfer(i, 2 * common::RAW_TRANSFER_GAS_LIMIT);
+            if i % 10 == 0 {
+                tx.caller = Address::repeat_byte(0x77);
+                tx.nonce = 0;
+            }
+            tx
+        })
+        .collect::<Vec<_>>();
+
+    let built_block = Pevm::default()
+        .build_block(
+            &chain,
+            &storage,
+            Default::default(),
+            block_env.clone(),
+            candidates.clone(),
+            None,
+            NonZeroUsize::new(8).unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(built_block.txs.len(), 300);
+    assert_eq!(built_block.gas_used, 300 * common::RAW_TRANSFER_GAS_LIMIT);
+    assert_eq!(built_block.blob_gas_used, 0);
+    // Candidates up to the last included one, minus the skipped ones.
+    let last_included = 300 * 10 / 9;
+    assert_eq!(
+        built_block
+            .excluded_txs
+            .iter()
+            .map(|(tx_idx, _)| *tx_idx)
+            .collect::<Vec<_>>(),
+        (1..=last_included)
+            .filter(|i| i % 10 == 0)
+            .map(|i| i - 1)
+            .collect::<Vec<_>>()
+    );
+    // The remaining candidates reserve more gas than is left.
+    assert_eq!(
+        built_block.skipped_txs,
+        (last_included..CANDIDATES).collect::<Vec<_>>()
+    );
+    assert_eq!(
+        built_block.txs,
+        candidates[..last_included]
+            .iter()
+            .filter(|tx| tx.nonce == 1)
+            .cloned()
+            .collect::<Vec<_>>()
+    );
+    // The built block executes the same as a canonical block.
+    assert_eq!(
+        execute_revm_sequential(
+            &chain,
+            &storage,
+            Default::default(),
+            block_env,
+            built_block.txs.clone()
+        )
+        .unwrap(),
+        built_block.tx_results
+    );
+}
+
+#[test]
+fn blob_gas_limit() {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    let candidates = (1..=10).map(|i| TxEnv {
+        tx_type: 3,
+        // A versioned hash starts with the KZG version byte.
+        blob_hashes: vec![B256::repeat_byte(1)],
+        max_fee_per_blob_gas: 1,
+        ..mock_transfer(i, common::RAW_TRANSFER_GAS_LIMIT)
+    });
+
+    let built_block = Pevm::default()
+        .build_block(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            candidates,
+            Some(3 * GAS_PER_BLOB),
+            NonZeroUsize::new(8).unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(built_block.txs.len(), 3);
+    assert_eq!(built_block.blob_gas_used, 3 * GAS_PER_BLOB);
+    assert_eq!(built_block.gas_used, 3 * common::RAW_TRANSFER_GAS_LIMIT);
+    assert!(built_block.excluded_txs.is_empty());
+    assert_eq!(built_block.skipped_txs, (3..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn skip_large_candidates() {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    let block_env = BlockEnv {
+        gas_limit: 4 * common::RAW_TRANSFER_GAS_LIMIT,
+        ..BlockEnv::default()
+    };
+    // Candidates too large for the remaining gas are skipped, while smaller
+    // ones after them are still included in the next windows.
+    let candidates = [1, 5, 1, 2, 3, 1, 1, 1]
+        .into_iter()
+        .enumerate()
+        .map(|(i, gas)| mock_transfer(i + 1, gas * common::RAW_TRANSFER_GAS_LIMIT))
+        .collect::<Vec<_>>();
+
+    let built_block = Pevm::default()
+        .build_block(
+            &chain,
+            &storage,
+            Default::default(),
+            block_env.clone(),
+            candidates.clone(),
+            None,
+            NonZeroUsize::new(2).unwrap(),
+        )
+        .unwrap();
+
+    assert_eq!(built_block.skipped_txs, vec![1, 4]);
+    assert!(built_block.excluded_txs.is_empty());
+    assert_eq!(built_block.gas_used, 4 * common::RAW_TRANSFER_GAS_LIMIT);
+    assert_eq!(built_block.txs, [0, 2, 3, 5].map(|i| candidates[i].clone()));
+    assert_eq!(
+        execute_revm_sequential(
+            &chain,
+            &storage,
+            Default::default(),
+            block_env,
+            built_block.txs.clone()
+        )
+        .unwrap(),
+        built_block.tx_results
+    );
+}
+
+#[test]
+fn no_candidates() {
+    let built_block = Pevm::default()
+        .build_block(
+            &PevmEthereum::mainnet(),
+            &mock_storage(),
+            Default::default(),
+            BlockEnv::default(),
+            Vec::new(),
+            None,
+            NonZeroUsize::MIN,
+        )
+        .unwrap();
+    assert!(built_block.txs.is_empty());
+    assert!(built_block.skipped_txs.is_empty());
+    assert_eq!(built_block.gas_used, 0);
+    assert!(built_block.state_diff.accounts.is_empty());
+}
@@ -0,0 +1,162 @@
+//! Test excluding invalid transactions when building and testing blocks.
+
+use std::num::NonZeroUsize;
+
+use alloy_consensus::{Signed, TxLegacy};
+use alloy_primitives::{Address, B256, Bytes, Signature, TxKind, U256};
+use alloy_rpc_types_eth::{Block, BlockTransactions, Header};
+use pevm::{
+    InMemoryStorage, Pevm, PevmMode,
+    chain::{PevmChain, PevmEthereum},
+};
+use revm::primitives::alloy_primitives::U160;
+
+pub mod common;
+
+const BLOCK_SIZE: usize = 200;
+
+// A block of transfers with a few invalid transactions, and the indices of
+// those that must be excluded.
+fn mock_block(chain: &PevmEthereum) -> (Block<alloy_rpc_types_eth::Transaction>, Vec<usize>) {
+    let unfunded = Address::repeat_byte(0x77);
+    let mut excluded = Vec::new();
+    let txs = (1..=BLOCK_SIZE)
+        .map(|i| {
+            let (address, account) = common::mock_account(i);
+            let (from, nonce, to) = match i {
+                // Sending before being funded.
+                10 | 20 => {
+                    excluded.push(i - 1);
+                    (unfunded, 0, address)
+                }
+                // Funding then sending.
+                30 => (address, account.nonce, unfunded),
+                40 => (unfunded, 0, address),
+                // Nonce gap.
+                50 => {
+                    excluded.push(i - 1);
+                    (address, account.nonce + 1, address)
+                }
+                // Nonce already used by the first transaction.
+                60 => {
+                    excluded.push(i - 1);
+                    let (first, first_account) = common::mock_account(1);
+                    (first, first_account.nonce, first)
+                }
+                _ => (address, account.nonce, address),
+            };
+            chain.mock_tx(
+                Signed::new_unchecked(
+                    TxLegacy {
+                        chain_id: Some(chain.id()),
+                        nonce,
+                        gas_price: 0,
+                        gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                        to: TxKind::Call(to),
+                        value: U256::from(1000),
+                        input: Bytes::default(),
+                    },
+                    Signature::new(U256::ZERO, U256::ZERO, false),
+                    B256::default(),
+                )
+                .into(),
+                from,
+            )
+        })
+        .collect();
+    let block = Block {
+        header: Header {
+            inner: alloy_consensus::Header {
+                number: 1,
+                timestamp: 1681338455,
+                gas_limit: 30_000_000,
+                // Large enough to execute in parallel.
+                gas_used: 30_000_000,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        transactions: BlockTransactions::Full(txs),
+        ..Block::default()
+    };
+    (block, excluded)
+}
+
+fn test_mode(mode: PevmMode) {
+    let chain = PevmEthereum::mainnet();
+    let storage = InMemoryStorage::new(
+        (0..=BLOCK_SIZE).map(common::mock_account).collect(),
+        Default::default(),
+        Default::default(),
+    );
+    let (block, excluded) = mock_block(&chain);
+    let concurrency_level = NonZeroUsize::new(8).unwrap();
+
+    let sequential_result = Pevm::with_mode(mode)
+        .execute(&chain, &storage, &block, concurrency_level, true)
+        .unwrap();
+    let parallel_result = Pevm::with_mode(mode)
+        .execute(&chain, &storage, &block, concurrency_level, false)
+        .unwrap();
+    for result in [&sequential_result, &parallel_result] {
+        assert_eq!(
+            result
+                .excluded_txs
+                .iter()
+                .map(|(tx_idx, _)| *tx_idx)
+                .collect::<Vec<_>>(),
+            excluded
+        );
+        assert_eq!(result.tx_results.len(), BLOCK_SIZE - excluded.len());
+        assert_eq!(
+            result
+                .tx_results
+                .last()
+                .unwrap()
+                .receipt
+                .cumulative_gas_used,
+            (BLOCK_SIZE - excluded.len()) as u64 * common::RAW_TRANSFER_GAS_LIMIT
+        );
+    }
+    assert_eq!(sequential_result.tx_results, parallel_result.tx_results);
+    assert_eq!(sequential_result.state_diff, parallel_result.state_diff);
+
+    // The funded sender is included once.
+    let unfunded = Address::repeat_byte(0x77);
+    let unfunded_diff = parallel_result.state_diff.accounts.get(&unfunded).unwrap();
+    assert_eq!(unfunded_diff.present.as_ref().unwrap().nonce, 1);
+    assert!(
+        !parallel_result
+            .state_diff
+            .accounts
+            .contains_key(&Address::from(U160::from(50)))
+    );
+}
+
+#[test]
+fn build_mode() {
+    test_mode(PevmMode::Build);
+}
+
+#[test]
+fn test_mode_collects_errors() {
+    test_mode(PevmMode::Test);
+}
+
+// Syncing aborts on the first invalid transaction that no lower
+// transaction can fix.
+#[test]
+fn sync_mode() {
+    let chain = PevmEthereum::mainnet();
+    let storage = InMemoryStorage::new(
+        (0..=BLOCK_SIZE).map(common::mock_account).collect(),
+        Default::default(),
+        Default::default(),
+    );
+    let (block, _) = mock_block(&chain);
+    assert!(
+        Pevm::default()
+            .execute(&chain, &storage, &block, NonZeroUsize::MIN, true)
+            .is_err()
+    );
+}
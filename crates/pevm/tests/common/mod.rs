@@ -19,7 +19,7 @@ use pevm::{
 pub mod runner;
 
 /// runner module imports
-pub use runner::{mock_account, test_execute_alloy, test_execute_revm};
+pub use runner::{mock_account, test_execute_alloy, test_execute_revm, test_execute_revm_with};
 
 /// storage module
 pub mod storage;
@@ -31,9 +31,13 @@ pub const RAW_TRANSFER_GAS_LIMIT: u64 = 21_000;
 ///
 /// Expects `../../data/{chain}/blocks/` for block snapshots and `../../data/{chain}/` for
 /// shared artifacts (`bytecodes.bincode.gz`, `block_hashes.bincode`).
+///
+/// Most snapshots only store the accounts their block touches in `pre_state.json`. Those
+/// that store the full state before the block in `full_state.json` instead are passed to
+/// the handler with `full_state` set, to also check their state root.
 pub fn for_each_block_from_disk<T: serde::de::DeserializeOwned>(
     chain: &str,
-    mut handler: impl FnMut(Block<T>, InMemoryStorage),
+    mut handler: impl FnMut(Block<T>, Vec<Header>, InMemoryStorage, bool),
 ) {
     let data_dir = std::path::PathBuf::from(format!("../../data/{chain}"));
 
@@ -64,14 +68,26 @@ pub fn for_each_block_from_disk<T: serde::de::DeserializeOwned>(
         ))
         .unwrap();
 
-        let accounts: HashMap<Address, EvmAccount, BuildSuffixHasher> = serde_json::from_reader(
-            BufReader::new(File::open(block_dir.join("pre_state.json")).unwrap()),
-        )
-        .unwrap();
+        // Only blocks with ommers can have their headers snapshotted, which
+        // `bins/fetch` does. Blocks without them are executed without their
+        // ommer rewards.
+        let ommers = match File::open(block_dir.join("ommers.json")) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file)).unwrap(),
+            Err(_) => Vec::new(),
+        };
+
+        let (state_file, full_state) = match File::open(block_dir.join("full_state.json")) {
+            Ok(file) => (file, true),
+            Err(_) => (File::open(block_dir.join("pre_state.json")).unwrap(), false),
+        };
+        let accounts: HashMap<Address, EvmAccount, BuildSuffixHasher> =
+            serde_json::from_reader(BufReader::new(state_file)).unwrap();
 
         handler(
             block,
+            ommers,
             InMemoryStorage::new(accounts, Arc::clone(&bytecodes), Arc::clone(&block_hashes)),
+            full_state,
         );
     }
 }
@@ -119,5 +135,5 @@ where
         ..Block::<C::Transaction>::default()
     };
     let storage = InMemoryStorage::new(accounts, Default::default(), Default::default());
-    test_execute_alloy(chain, &storage, block, false);
+    test_execute_alloy(chain, &storage, block, &[], false);
 }
@@ -1,7 +1,7 @@
 use alloy_primitives::Bloom;
-use alloy_rpc_types_eth::Block;
+use alloy_rpc_types_eth::{Block, Header};
 use pevm::{
-    EvmAccount, Pevm, Storage,
+    BlockStateDiff, EvmAccount, Pevm, Storage,
     chain::{CalculateReceiptRootError, PevmChain},
 };
 use revm::{
@@ -27,6 +27,15 @@ pub fn mock_account(idx: usize) -> (Address, EvmAccount) {
 /// Execute an REVM block sequentially and parallelly with PEVM and assert that
 /// the execution results match.
 pub fn test_execute_revm<C, S>(chain: &C, storage: S, txs: Vec<C::EvmTx>)
+where
+    C: PevmChain + PartialEq + Send + Sync,
+    S: Storage + Send + Sync + Debug,
+{
+    test_execute_revm_with(Pevm::default(), chain, storage, txs);
+}
+
+/// Like [`test_execute_revm`], executing the block in parallel with `pevm`.
+pub fn test_execute_revm_with<C, S>(mut pevm: Pevm, chain: &C, storage: S, txs: Vec<C::EvmTx>)
 where
     C: PevmChain + PartialEq + Send + Sync,
     S: Storage + Send + Sync + Debug,
@@ -40,7 +49,7 @@ where
             BlockEnv::default(),
             txs.clone(),
         ),
-        Pevm::default().execute_revm_parallel(
+        pevm.execute_revm_parallel(
             chain,
             &storage,
             C::EvmSpecId::default(),
@@ -52,24 +61,49 @@ where
 }
 
 /// Execute an Alloy block sequentially & with pevm and assert that
-/// the execution results match.
+/// the execution results match. Returns the block's state diff, for callers
+/// with the full pre-block state to also match the header's state root.
 pub fn test_execute_alloy<C, S>(
     chain: &C,
     storage: &S,
     block: Block<C::Transaction>,
+    ommers: &[Header],
     must_match_block_header: bool,
-) where
+) -> BlockStateDiff
+where
     C: PevmChain + PartialEq + Send + Sync,
     S: Storage + Send + Sync + Debug,
 {
     let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    // Blocks whose ommer headers are not snapshotted are executed without the
+    // ommer rewards.
+    let execute = |pevm: &mut Pevm, force_sequential| {
+        if ommers.len() == block.uncles.len() {
+            pevm.execute_with_ommers(
+                chain,
+                storage,
+                &block,
+                ommers,
+                concurrency_level,
+                force_sequential,
+            )
+        } else {
+            pevm.execute(chain, storage, &block, concurrency_level, force_sequential)
+        }
+    };
     let mut pevm = Pevm::default();
-    let sequential_result = pevm.execute(chain, storage, &block, concurrency_level, true);
-    let parallel_result = pevm.execute(chain, storage, &block, concurrency_level, false);
+    let sequential_result = execute(&mut pevm, true);
+    let parallel_result = execute(&mut pevm, false);
     assert!(sequential_result.is_ok());
     assert_eq!(&sequential_result, &parallel_result);
+    // Prefetching on the worker threads leaves the results unchanged.
+    assert_eq!(
+        &execute(&mut Pevm::default().with_prefetching(), true),
+        &sequential_result
+    );
 
-    let tx_results = sequential_result.unwrap();
+    let result = sequential_result.unwrap();
+    let tx_results = result.tx_results;
     if must_match_block_header {
         let spec_id = chain.get_block_spec(&block.header).unwrap();
 
@@ -96,4 +130,5 @@ pub fn test_execute_alloy<C, S>(
                 .unwrap_or_default()
         );
     }
+    result.state_diff
 }
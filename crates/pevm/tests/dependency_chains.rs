@@ -0,0 +1,96 @@
+//! Test blocks with long chains of dependent transactions, which are executed
+//! as sequential segments within parallel execution.
+
+use std::{num::NonZeroUsize, sync::Arc};
+
+use pevm::{Bytecodes, EvmAccount, EvmCode, InMemoryStorage, Pevm, chain::PevmEthereum};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{Address, Bytes, U256},
+    state::Bytecode,
+};
+
+pub mod common;
+
+// Use more workers than the test machine may have to force conflicts.
+const CONCURRENCY_LEVEL: NonZeroUsize = NonZeroUsize::new(8).unwrap();
+// Mock senders after the precompile addresses, which raw transfers cannot pay.
+const FIRST_SENDER: usize = 0x100;
+const COUNTER: Address = Address::repeat_byte(0xcc);
+
+// A contract that increments the value at slot 0 on every call, so every call
+// depends on the previous one.
+// PUSH1 0 SLOAD PUSH1 1 ADD PUSH1 0 SSTORE STOP
+const COUNTER_CODE: [u8; 10] = [0x60, 0x00, 0x54, 0x60, 0x01, 0x01, 0x60, 0x00, 0x55, 0x00];
+
+fn mock_storage(num_senders: usize) -> InMemoryStorage {
+    let mut accounts = (FIRST_SENDER..FIRST_SENDER + num_senders)
+        .map(common::mock_account)
+        .chain([common::mock_account(0)]) // Beneficiary
+        .collect::<pevm::ChainState>();
+    let code = Bytecode::new_raw(Bytes::from_static(&COUNTER_CODE));
+    let code_hash = code.hash_slow();
+    accounts.insert(
+        COUNTER,
+        EvmAccount {
+            nonce: 1,
+            code_hash: Some(code_hash),
+            ..EvmAccount::default()
+        },
+    );
+    let bytecodes = Bytecodes::from_iter([(code_hash, EvmCode::from(code))]);
+    InMemoryStorage::new(accounts, Arc::new(bytecodes), Default::default())
+}
+
+// Mock a transaction from a distinct sender, calling the counter or
+// transferring to itself.
+fn mock_tx(sender_idx: usize, calls_counter: bool) -> TxEnv {
+    let (caller, account) = common::mock_account(FIRST_SENDER + sender_idx);
+    TxEnv {
+        caller,
+        nonce: account.nonce,
+        kind: TransactTo::Call(if calls_counter { COUNTER } else { caller }),
+        value: U256::from(1),
+        gas_limit: 100_000,
+        gas_price: 1,
+        ..TxEnv::default()
+    }
+}
+
+fn test_chains(txs: Vec<TxEnv>) {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage(txs.len());
+    assert_eq!(
+        pevm::execute_revm_sequential(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            txs.clone(),
+        ),
+        Pevm::default().execute_revm_parallel(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            txs,
+            CONCURRENCY_LEVEL,
+        ),
+    );
+}
+
+#[test]
+fn hot_counter() {
+    test_chains((0..2_000).map(|i| mock_tx(i, true)).collect());
+}
+
+// Chains of counter calls between independent transfers, which other workers
+// execute while a segment runs.
+#[test]
+fn hot_counter_between_transfers() {
+    test_chains(
+        (0..5_000)
+            .map(|i| mock_tx(i, (i / 250) % 2 == 1 || i % 7 == 0))
+            .collect(),
+    );
+}
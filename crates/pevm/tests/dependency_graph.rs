@@ -0,0 +1,114 @@
+//! Test the dependency graphs discovered of blocks executed in parallel.
+
+use std::num::NonZeroUsize;
+
+use pevm::{
+    DependencyGraph, DependencyLocation, InMemoryStorage, Pevm, PevmMode, chain::PevmEthereum,
+    execute_revm_sequential,
+};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{U256, hardfork::SpecId},
+};
+
+pub mod common;
+
+const BLOCK_SIZE: usize = 100;
+
+// Mock senders after the precompile addresses, which raw transfers cannot pay.
+const FIRST_SENDER: usize = 0x100;
+
+// Execute raw transfers of the senders at [sender_of] each index to distinct
+// recipients, returning the dependency graph of the block. We build blocks
+// for raw transfers to read the nonces of their senders instead of being
+// lazily updated.
+fn execute(sender_of: impl Fn(usize) -> usize) -> DependencyGraph {
+    let chain = PevmEthereum::mainnet();
+    let storage = InMemoryStorage::new(
+        (FIRST_SENDER..FIRST_SENDER + BLOCK_SIZE)
+            .map(common::mock_account)
+            .chain([common::mock_account(0)])
+            .collect(),
+        Default::default(),
+        Default::default(),
+    );
+    let mut nonces = [0; BLOCK_SIZE];
+    let txs: Vec<TxEnv> = (0..BLOCK_SIZE)
+        .map(|i| {
+            let sender = sender_of(i);
+            nonces[sender] += 1;
+            TxEnv {
+                caller: common::mock_account(FIRST_SENDER + sender).0,
+                nonce: nonces[sender],
+                kind: TransactTo::Call(common::mock_account(FIRST_SENDER + BLOCK_SIZE + i).0),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: 1,
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+
+    let mut pevm = Pevm::with_mode(PevmMode::Build).with_dependency_graph();
+    assert_eq!(
+        pevm.execute_revm_parallel(
+            &chain,
+            &storage,
+            SpecId::default(),
+            BlockEnv::default(),
+            txs.clone(),
+            NonZeroUsize::new(4).unwrap(),
+        ),
+        execute_revm_sequential(
+            &chain,
+            &storage,
+            SpecId::default(),
+            BlockEnv::default(),
+            txs
+        )
+    );
+    let graph = pevm.take_dependency_graph().unwrap();
+    assert_eq!(pevm.take_dependency_graph(), None);
+    graph
+}
+
+#[test]
+fn independent_transfers_graph() {
+    let graph = execute(|i| i);
+    assert!(graph.edges.is_empty());
+    assert_eq!(
+        graph.tx_gas,
+        vec![common::RAW_TRANSFER_GAS_LIMIT; BLOCK_SIZE]
+    );
+    assert_eq!(graph.critical_path_gas(), common::RAW_TRANSFER_GAS_LIMIT);
+    assert_eq!(graph.max_speedup(), BLOCK_SIZE as f64);
+}
+
+#[test]
+fn same_sender_transfers_graph() {
+    let graph = execute(|_| 0);
+    // Each transaction reads the nonce of the previous one.
+    let sender = common::mock_account(FIRST_SENDER).0;
+    for tx_idx in 1..BLOCK_SIZE {
+        assert!(graph.edges.iter().any(|edge| edge.from == tx_idx - 1
+            && edge.to == tx_idx
+            && edge.location == DependencyLocation::Nonce(sender)));
+    }
+    assert_eq!(graph.critical_path_gas(), graph.total_gas());
+    assert_eq!(graph.max_speedup(), 1.0);
+
+    assert!(graph.to_dot().contains("98 -> 99"));
+    assert_eq!(
+        serde_json::from_str::<DependencyGraph>(&serde_json::to_string(&graph).unwrap()).unwrap(),
+        graph
+    );
+}
+
+#[test]
+fn interleaved_senders_graph() {
+    // Ten chains of transactions from the same senders.
+    let graph = execute(|i| i % 10);
+    assert!(graph.edges.iter().all(|edge| edge.to - edge.from == 10));
+    assert_eq!(graph.critical_path_gas(), graph.total_gas() / 10);
+    assert_eq!(graph.max_speedup(), 10.0);
+}
@@ -8,13 +8,13 @@ pub mod common;
 #[path = "./mod.rs"]
 pub mod erc20;
 
-use common::test_execute_revm;
-use erc20::generate_cluster;
+use common::{test_execute_revm, test_execute_revm_with};
+use erc20::{generate_cluster, generate_hot_recipients};
 use pevm::chain::PevmEthereum;
-use pevm::{Bytecodes, ChainState, EvmAccount, InMemoryStorage};
-use revm::context::TxEnv;
+use pevm::{Bytecodes, ChainState, EvmAccount, InMemoryStorage, Pevm};
+use revm::context::{BlockEnv, TxEnv};
 use revm::primitives::Address;
-use std::sync::Arc;
+use std::{num::NonZeroUsize, sync::Arc, thread};
 
 #[test]
 fn erc20_independent() {
@@ -55,3 +55,63 @@ fn erc20_clusters() {
         final_txs,
     )
 }
+
+// Many holders transferring to the same recipient, whose balance every
+// transfer adds to.
+#[test]
+fn erc20_hot_recipient() {
+    let (mut state, bytecodes, txs) = generate_hot_recipients(2_000, 1, 1);
+    state.insert(Address::ZERO, EvmAccount::default()); // Beneficiary
+    let bytecodes = Arc::new(bytecodes);
+    for pevm in [Pevm::default(), Pevm::default().with_commutative_writes()] {
+        test_execute_revm_with(
+            pevm,
+            &PevmEthereum::mainnet(),
+            InMemoryStorage::new(state.clone(), bytecodes.clone(), Default::default()),
+            txs.clone(),
+        );
+    }
+}
+
+// A few distributors sending several transfers each to a small community,
+// whose members receive from different distributors in the same block.
+#[test]
+fn erc20_airdrop() {
+    let (mut state, bytecodes, txs) = generate_hot_recipients(20, 50, 100);
+    state.insert(Address::ZERO, EvmAccount::default()); // Beneficiary
+    let bytecodes = Arc::new(bytecodes);
+    for pevm in [Pevm::default(), Pevm::default().with_commutative_writes()] {
+        test_execute_revm_with(
+            pevm,
+            &PevmEthereum::mainnet(),
+            InMemoryStorage::new(state.clone(), bytecodes.clone(), Default::default()),
+            txs.clone(),
+        );
+    }
+}
+
+#[test]
+fn erc20_prefetching() {
+    let (mut state, bytecodes, txs) = generate_cluster(1000, 5, 2);
+    state.insert(Address::ZERO, EvmAccount::default()); // Beneficiary
+    let chain = PevmEthereum::mainnet();
+    let storage = InMemoryStorage::new(state, Arc::new(bytecodes), Default::default());
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    assert_eq!(
+        pevm::execute_revm_sequential(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            txs.clone(),
+        ),
+        Pevm::default().with_prefetching().execute_revm_parallel(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            txs,
+            concurrency_level,
+        ),
+    );
+}
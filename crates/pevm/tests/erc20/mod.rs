@@ -74,6 +74,62 @@ pub fn generate_cluster(
         }
     }
 
+    let bytecodes = take_bytecodes(&mut state);
+    (state, bytecodes, txs)
+}
+
+/// Generates transfers from each sender to a few recipients in turn, which
+/// credit the same hot balances across senders, like an airdrop or deposits
+/// to an exchange.
+pub fn generate_hot_recipients(
+    num_senders: usize,
+    num_recipients: usize,
+    num_transfers_per_sender: usize,
+) -> (ChainState, Bytecodes, Vec<TxEnv>) {
+    let senders = generate_addresses(num_senders);
+    let recipients = generate_addresses(num_recipients);
+
+    let gld_address = Address::new(rand::random());
+
+    let gld_account = ERC20Token::new("Gold Token", "GLD", 18, 222_222_000_000_000_000_000_000u128)
+        .add_balances(&senders, uint!(1_000_000_000_000_000_000_U256))
+        // Crediting an empty balance costs more than [GAS_LIMIT].
+        .add_balances(&recipients, uint!(1_000_000_000_000_000_000_U256))
+        .build();
+
+    let mut state = ChainState::from_iter([(gld_address, gld_account)]);
+    for sender in &senders {
+        state.insert(
+            *sender,
+            EvmAccount {
+                balance: uint!(4_567_000_000_000_000_000_000_U256),
+                ..EvmAccount::default()
+            },
+        );
+    }
+
+    let mut txs = Vec::new();
+    for nonce in 0..num_transfers_per_sender {
+        for (i, sender) in senders.iter().enumerate() {
+            let recipient = recipients[(nonce * num_senders + i) % num_recipients];
+            txs.push(TxEnv {
+                caller: *sender,
+                gas_limit: GAS_LIMIT,
+                gas_price: 0xb2d05e07,
+                kind: TransactTo::Call(gld_address),
+                data: ERC20Token::transfer(recipient, U256::from(rand::random::<u8>())),
+                nonce: nonce as u64,
+                ..TxEnv::default()
+            })
+        }
+    }
+
+    let bytecodes = take_bytecodes(&mut state);
+    (state, bytecodes, txs)
+}
+
+// Move the code of the accounts in [state] to their own map.
+fn take_bytecodes(state: &mut ChainState) -> Bytecodes {
     let mut bytecodes = Bytecodes::default();
     for account in state.values_mut() {
         let code = account.code.take();
@@ -81,6 +137,5 @@ pub fn generate_cluster(
             bytecodes.insert(account.code_hash.unwrap(), code);
         }
     }
-
-    (state, bytecodes, txs)
+    bytecodes
 }
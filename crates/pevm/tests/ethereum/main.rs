@@ -11,26 +11,21 @@
 
 use pevm::chain::PevmEthereum;
 use pevm::{
-    Bytecodes, ChainState, EvmAccount, EvmCode, InMemoryStorage, Pevm, PevmError,
-    PevmTxExecutionResult,
+    BlockStateDiff, Bytecodes, ChainState, EvmAccount, EvmCode, InMemoryStorage, Pevm, PevmError,
+    PevmTxExecutionResult, calculate_state_root,
 };
 use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
 use revm::context::result::InvalidTransaction;
 use revm::context::{BlockEnv, TransactTo, TxEnv};
 use revm::context_interface::block::BlobExcessGasAndPrice;
 use revm::context_interface::either::Either;
-use revm::database::PlainAccount;
-use revm::primitives::KECCAK_EMPTY;
 use revm::primitives::eip4844::{
     BLOB_BASE_FEE_UPDATE_FRACTION_CANCUN, BLOB_BASE_FEE_UPDATE_FRACTION_PRAGUE,
 };
 use revm::primitives::hardfork::SpecId;
-use revm::state::{AccountInfo, Bytecode};
+use revm::state::Bytecode;
 use revm_statetest_types::{Env, SpecName, Test, TestSuite, TestUnit, TransactionParts};
-use revme::cmd::statetest::{
-    merkle_trie::{log_rlp_hash, state_merkle_trie_root},
-    utils::recover_address,
-};
+use revme::cmd::statetest::{merkle_trie::log_rlp_hash, utils::recover_address};
 use std::path::Path;
 use std::sync::Arc;
 use std::{fs, num::NonZeroUsize};
@@ -145,12 +140,13 @@ fn run_test_unit(path: &Path, unit: TestUnit) {
             }
 
             let spec_id = spec_name.to_spec_id();
+            let storage = InMemoryStorage::new(chain_state, Arc::new(bytecodes), Default::default());
 
             match (
                 test.expect_exception.as_deref(),
                 Pevm::default().execute_revm_parallel(
                     &PevmEthereum::mainnet(),
-                    &InMemoryStorage::new(chain_state.clone(), Arc::new(bytecodes), Default::default()),
+                    &storage,
                     spec_id,
                     build_block_env(&unit.env, spec_id),
                     vec![tx_env.unwrap()],
@@ -197,35 +193,8 @@ fn run_test_unit(path: &Path, unit: TestUnit) {
                     let logs_root = log_rlp_hash(&receipt.logs);
                     assert_eq!(logs_root, test.logs, "Mismatched logs root for {path:?}");
 
-                    // This is a good reference for a minimal state/DB commitment logic for
-                    // pevm/revm to meet the Ethereum specs throughout the eras.
-                    for (address, account) in state {
-                        if let Some(account) = account {
-                            let chain_state_account = chain_state.entry(address).or_default();
-                            chain_state_account.balance = account.balance;
-                            chain_state_account.nonce = account.nonce;
-                            chain_state_account.code_hash = account.code_hash;
-                            chain_state_account.code = account.code;
-                            chain_state_account.storage.extend(account.storage);
-                        } else {
-                            chain_state.remove(&address);
-                        }
-                    }
-                    // TODO: Implement our own state root calculation function to remove
-                    // this conversion to [PlainAccount]
-                    let plain_chain_state = chain_state.into_iter().map(|(address, account)| {
-                        (address, PlainAccount {
-                            info: AccountInfo {
-                                balance: account.balance,
-                                nonce: account.nonce,
-                                code_hash: account.code_hash.unwrap_or(KECCAK_EMPTY),
-                                code: account.code.map(Bytecode::from),
-                                account_id: None,
-                            },
-                            storage: account.storage.into_iter().collect(),
-                        })}).collect::<Vec<_>>();
-                    let state_root =
-                        state_merkle_trie_root(plain_chain_state.iter().map(|(address, account)| (*address, account)));
+                    let state_diff = BlockStateDiff::from_transitions(&storage, [&state]).unwrap();
+                    let state_root = calculate_state_root(&storage, &state_diff).unwrap();
                     assert_eq!(state_root, test.hash, "Mismatched state root for {path:?}");
                 }
                 unexpected_res => {
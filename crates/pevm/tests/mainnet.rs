@@ -1,6 +1,6 @@
 //! Test with mainnet blocks
 
-use pevm::chain::PevmEthereum;
+use pevm::{calculate_state_root, chain::PevmEthereum};
 
 pub mod common;
 
@@ -24,20 +24,30 @@ where
     let provider = ProviderBuilder::<_, _, C::Network>::default().connect_http(url);
 
     for &block_number in block_numbers {
-        let block = provider
+        let block: alloy_rpc_types_eth::Block<C::Transaction> = provider
             .get_block(BlockId::number(block_number))
             .full()
             .await
             .unwrap()
             .unwrap()
             .into();
+        let mut ommers = Vec::with_capacity(block.uncles.len());
+        for index in 0..block.uncles.len() {
+            let ommer: alloy_rpc_types_eth::Block<C::Transaction> = provider
+                .get_uncle(BlockId::number(block_number), index as u64)
+                .await
+                .unwrap()
+                .unwrap()
+                .into();
+            ommers.push(ommer.header);
+        }
         let spec_id = chain.get_block_spec(&block.header).unwrap();
         let rpc_storage = pevm::RpcStorage::new(
             provider.clone(),
             spec_id.into(),
             BlockId::number(block_number - 1),
         );
-        common::test_execute_alloy(&chain, &rpc_storage, block, true);
+        common::test_execute_alloy(&chain, &rpc_storage, block, &ommers, true);
     }
 }
 
@@ -79,11 +89,23 @@ async fn rise_mainnet_blocks_from_rpc() {
 
 #[test]
 fn mainnet_blocks_from_disk() {
-    common::for_each_block_from_disk("ethereum", |block, storage| {
+    common::for_each_block_from_disk("ethereum", |block, ommers, storage, full_state| {
         // Run several times to try catching a race condition if there is any.
         // 1000~2000 is a better choice for local testing after major changes.
         for _ in 0..3 {
-            common::test_execute_alloy(&PevmEthereum::mainnet(), &storage, block.clone(), true)
+            let state_diff = common::test_execute_alloy(
+                &PevmEthereum::mainnet(),
+                &storage,
+                block.clone(),
+                &ommers,
+                true,
+            );
+            if full_state {
+                assert_eq!(
+                    calculate_state_root(&storage, &state_diff).unwrap(),
+                    block.header.state_root
+                );
+            }
         }
     });
 }
@@ -91,9 +113,9 @@ fn mainnet_blocks_from_disk() {
 #[test]
 fn rise_blocks_from_disk() {
     use pevm::chain::PevmRise;
-    common::for_each_block_from_disk("rise", |block, storage| {
+    common::for_each_block_from_disk("rise", |block, ommers, storage, _| {
         for _ in 0..3 {
-            common::test_execute_alloy(&PevmRise, &storage, block.clone(), true)
+            common::test_execute_alloy(&PevmRise, &storage, block.clone(), &ommers, true);
         }
     });
 }
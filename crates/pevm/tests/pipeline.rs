@@ -0,0 +1,199 @@
+//! Test pipelining the execution of consecutive blocks.
+
+use std::{num::NonZeroUsize, sync::Arc};
+
+use alloy_consensus::{Signed, TxLegacy};
+use alloy_eips::{
+    eip2935::{HISTORY_STORAGE_ADDRESS, HISTORY_STORAGE_CODE},
+    eip4788::{BEACON_ROOTS_ADDRESS, BEACON_ROOTS_CODE},
+    eip4895::{Withdrawal, Withdrawals},
+    eip7002::{WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS, WITHDRAWAL_REQUEST_PREDEPLOY_CODE},
+    eip7251::{CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS, CONSOLIDATION_REQUEST_PREDEPLOY_CODE},
+};
+use alloy_primitives::{Address, B256, Bytes, Signature, TxKind, U256};
+use alloy_rpc_types_eth::{Block, BlockTransactions, Header};
+use pevm::{
+    Bytecodes, EvmAccount, EvmCode, InMemoryStorage, Pevm,
+    chain::{PevmChain, PevmEthereum},
+};
+use revm::{primitives::alloy_primitives::U160, state::Bytecode};
+
+pub mod common;
+
+const PRAGUE_TIMESTAMP: u64 = 1746612311;
+const NUM_BLOCKS: u64 = 4;
+const NUM_SENDERS: usize = 50;
+// Mock senders after the precompile addresses, which raw transfers cannot pay.
+const FIRST_SENDER: usize = 0x100;
+
+// An account that is only funded by a withdrawal, then spends in later blocks.
+const WITHDRAWAL_RECIPIENT: Address = Address::repeat_byte(0x77);
+// A fresh account funded by a transfer, then spends in later blocks.
+const TRANSFER_RECIPIENT: Address = Address::repeat_byte(0x88);
+
+fn mock_storage() -> InMemoryStorage {
+    let mut accounts = (FIRST_SENDER..=FIRST_SENDER + NUM_SENDERS)
+        .map(common::mock_account)
+        .collect::<pevm::ChainState>();
+    let mut bytecodes = Bytecodes::default();
+    for (address, code) in [
+        (BEACON_ROOTS_ADDRESS, &BEACON_ROOTS_CODE),
+        (HISTORY_STORAGE_ADDRESS, &HISTORY_STORAGE_CODE),
+        (
+            WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS,
+            &WITHDRAWAL_REQUEST_PREDEPLOY_CODE,
+        ),
+        (
+            CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS,
+            &CONSOLIDATION_REQUEST_PREDEPLOY_CODE,
+        ),
+    ] {
+        let code = Bytecode::new_raw(code.clone());
+        let code_hash = code.hash_slow();
+        accounts.insert(
+            address,
+            EvmAccount {
+                nonce: 1,
+                code_hash: Some(code_hash),
+                ..EvmAccount::default()
+            },
+        );
+        bytecodes.insert(code_hash, EvmCode::from(code));
+    }
+    InMemoryStorage::new(accounts, Arc::new(bytecodes), Default::default())
+}
+
+fn mock_tx(
+    chain: &PevmEthereum,
+    from: Address,
+    nonce: u64,
+    to: Address,
+    value: U256,
+) -> alloy_rpc_types_eth::Transaction {
+    chain.mock_tx(
+        Signed::new_unchecked(
+            TxLegacy {
+                chain_id: Some(chain.id()),
+                nonce,
+                gas_price: 1,
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                to: TxKind::Call(to),
+                value,
+                input: Bytes::default(),
+            },
+            Signature::new(U256::ZERO, U256::ZERO, false),
+            B256::default(),
+        )
+        .into(),
+        from,
+    )
+}
+
+// Consecutive blocks where every sender sends again in the next block, and
+// accounts funded in a block spend in the next ones.
+fn mock_blocks(chain: &PevmEthereum) -> Vec<Block<alloy_rpc_types_eth::Transaction>> {
+    (1..=NUM_BLOCKS)
+        .map(|number| {
+            let mut txs = (0..NUM_SENDERS)
+                .map(|i| {
+                    let (from, account) = common::mock_account(FIRST_SENDER + i);
+                    // Transfer to the next sender to depend on other blocks' writes.
+                    let to = Address::from(U160::from(FIRST_SENDER + (i + 1) % NUM_SENDERS));
+                    mock_tx(
+                        chain,
+                        from,
+                        account.nonce + number - 1,
+                        to,
+                        U256::from(i + 1),
+                    )
+                })
+                .collect::<Vec<_>>();
+            if number == 1 {
+                let (funder, account) = common::mock_account(FIRST_SENDER + NUM_SENDERS);
+                txs.push(mock_tx(
+                    chain,
+                    funder,
+                    account.nonce,
+                    TRANSFER_RECIPIENT,
+                    U256::from(1_000_000),
+                ));
+            } else {
+                txs.push(mock_tx(
+                    chain,
+                    WITHDRAWAL_RECIPIENT,
+                    number - 2,
+                    Address::from(U160::from(FIRST_SENDER)),
+                    U256::from(1),
+                ));
+                txs.push(mock_tx(
+                    chain,
+                    TRANSFER_RECIPIENT,
+                    number - 2,
+                    WITHDRAWAL_RECIPIENT,
+                    U256::from(1),
+                ));
+            }
+            Block {
+                header: Header {
+                    hash: B256::from(U256::from(number)),
+                    inner: alloy_consensus::Header {
+                        number,
+                        parent_hash: B256::from(U256::from(number - 1)),
+                        beneficiary: Address::from(U160::from(FIRST_SENDER as u64 + number % 2)),
+                        timestamp: PRAGUE_TIMESTAMP + number * 12,
+                        gas_limit: 30_000_000,
+                        excess_blob_gas: Some(0),
+                        parent_beacon_block_root: Some(B256::from(U256::from(number))),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                transactions: BlockTransactions::Full(txs),
+                withdrawals: Some(Withdrawals::new(vec![Withdrawal {
+                    index: number,
+                    validator_index: 0,
+                    address: WITHDRAWAL_RECIPIENT,
+                    amount: 1_000_000,
+                }])),
+                ..Block::default()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn pipelined_blocks() {
+    let chain = PevmEthereum::mainnet();
+    let blocks = mock_blocks(&chain);
+
+    let mut storage = mock_storage();
+    let pipelined_results = Pevm::default()
+        .execute_blocks(&chain, &storage, &blocks, NonZeroUsize::new(8).unwrap())
+        .unwrap();
+    assert_eq!(pipelined_results.len(), blocks.len());
+
+    // The pipelined blocks execute the same as sequential blocks one by one.
+    for (block, pipelined_result) in blocks.iter().zip(pipelined_results) {
+        let result = Pevm::default()
+            .execute(&chain, &storage, block, NonZeroUsize::MIN, true)
+            .unwrap();
+        assert!(result.excluded_txs.is_empty());
+        assert_eq!(result, pipelined_result);
+        storage.apply_state_diff(&result.state_diff);
+    }
+}
+
+#[test]
+fn no_blocks() {
+    assert!(
+        Pevm::default()
+            .execute_blocks(
+                &PevmEthereum::mainnet(),
+                &mock_storage(),
+                &[],
+                NonZeroUsize::MIN,
+            )
+            .unwrap()
+            .is_empty()
+    );
+}
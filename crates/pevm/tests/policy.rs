@@ -0,0 +1,235 @@
+//! Test deciding how to execute blocks from their features.
+
+use std::{
+    num::NonZeroUsize,
+    sync::{Arc, Mutex},
+};
+
+use pevm::{
+    AdaptiveExecutionPolicy, BlockFeatures, DefaultExecutionPolicy, ExecutionPolicy,
+    ExecutionStrategy, InMemoryStorage, Pevm, chain::PevmEthereum, execute_revm_sequential,
+};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{Address, Bytes, U256, alloy_primitives::U160},
+};
+
+pub mod common;
+
+const CONCURRENCY_LEVEL: NonZeroUsize = NonZeroUsize::new(8).unwrap();
+
+// Mock [block_size] transactions from [num_senders] senders to [num_recipients]
+// recipients, calling them with some input data if [contract_calls].
+fn mock_txs(
+    block_size: usize,
+    num_senders: usize,
+    num_recipients: usize,
+    contract_calls: bool,
+) -> Vec<TxEnv> {
+    (0..block_size)
+        .map(|i| TxEnv {
+            caller: Address::from(U160::from(1 + i % num_senders)),
+            kind: TransactTo::Call(Address::from(U160::from(1_000_000 + i % num_recipients))),
+            data: if contract_calls {
+                Bytes::from_static(&[0xa9, 0x05, 0x9c, 0xbb])
+            } else {
+                Bytes::new()
+            },
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            ..TxEnv::default()
+        })
+        .collect()
+}
+
+fn decide(txs: &[TxEnv], gas_used: Option<u64>) -> ExecutionStrategy {
+    let mut features = BlockFeatures::new(txs);
+    features.gas_used = gas_used;
+    DefaultExecutionPolicy.decide(&features, CONCURRENCY_LEVEL)
+}
+
+fn decide_adaptive(txs: &[TxEnv]) -> ExecutionStrategy {
+    AdaptiveExecutionPolicy.decide(&BlockFeatures::new(txs), CONCURRENCY_LEVEL)
+}
+
+#[test]
+fn block_features() {
+    let mut txs = mock_txs(100, 10, 4, false);
+    txs.extend(mock_txs(100, 50, 100, true));
+    txs.push(TxEnv {
+        kind: TransactTo::Create,
+        gas_limit: 1_000_000,
+        ..TxEnv::default()
+    });
+    let features = BlockFeatures::new(&txs);
+    assert_eq!(
+        features,
+        BlockFeatures {
+            num_txs: 201,
+            total_gas_limit: 200 * common::RAW_TRANSFER_GAS_LIMIT + 1_000_000,
+            num_senders: 51,
+            num_recipients: 101,
+            num_contract_calls: 101,
+            gas_used: None,
+        }
+    );
+    assert!((features.sender_collision_ratio() - (1.0 - 51.0 / 201.0)).abs() < f64::EPSILON);
+    assert!((features.recipient_collision_ratio() - (1.0 - 101.0 / 201.0)).abs() < f64::EPSILON);
+    assert!((features.contract_call_share() - 101.0 / 201.0).abs() < f64::EPSILON);
+    assert_eq!(BlockFeatures::new(&[]).sender_collision_ratio(), 0.0);
+}
+
+#[test]
+fn default_policy() {
+    // Blocks with fewer transactions than workers or under 4M gas used are
+    // not worth the thread overheads.
+    assert_eq!(decide(&[], None), ExecutionStrategy::Sequential);
+    assert_eq!(
+        decide(&mock_txs(7, 7, 7, false), Some(30_000_000)),
+        ExecutionStrategy::Sequential
+    );
+    assert_eq!(
+        decide(&mock_txs(1_000, 1_000, 1_000, false), Some(3_999_999)),
+        ExecutionStrategy::Sequential
+    );
+    assert_eq!(
+        decide(&mock_txs(8, 1, 1, true), Some(4_000_000)),
+        ExecutionStrategy::Parallel(CONCURRENCY_LEVEL)
+    );
+    // Blocks being built are judged by their gas limits.
+    assert_eq!(
+        decide(&mock_txs(100, 100, 100, false), None),
+        ExecutionStrategy::Sequential
+    );
+    assert_eq!(
+        decide(&mock_txs(200, 200, 200, false), None),
+        ExecutionStrategy::Parallel(CONCURRENCY_LEVEL)
+    );
+}
+
+#[test]
+fn adaptive_policy() {
+    // Empty and small blocks are not worth the thread overheads.
+    assert_eq!(decide_adaptive(&[]), ExecutionStrategy::Sequential);
+    assert_eq!(
+        decide_adaptive(&mock_txs(7, 7, 7, false)),
+        ExecutionStrategy::Sequential
+    );
+    assert_eq!(
+        decide_adaptive(&mock_txs(100, 100, 100, false)),
+        ExecutionStrategy::Sequential
+    );
+    // Gigagas blocks use all workers.
+    assert_eq!(
+        decide_adaptive(&mock_txs(47_620, 47_620, 47_620, false)),
+        ExecutionStrategy::Parallel(CONCURRENCY_LEVEL)
+    );
+    assert_eq!(
+        decide_adaptive(&mock_txs(47_620, 47_620, 1, true)),
+        ExecutionStrategy::Parallel(CONCURRENCY_LEVEL)
+    );
+    // Blocks with some gas use fewer workers.
+    assert_eq!(
+        decide_adaptive(&mock_txs(300, 300, 300, false)),
+        ExecutionStrategy::Parallel(NonZeroUsize::new(3).unwrap())
+    );
+    // Contract calls of the same sender are chained, unlike raw transfers.
+    assert_eq!(
+        decide_adaptive(&mock_txs(10_000, 1, 1, true)),
+        ExecutionStrategy::Sequential
+    );
+    assert_eq!(
+        decide_adaptive(&mock_txs(10_000, 4, 1, true)),
+        ExecutionStrategy::Parallel(NonZeroUsize::new(4).unwrap())
+    );
+    assert_eq!(
+        decide_adaptive(&mock_txs(10_000, 1, 1, false)),
+        ExecutionStrategy::Parallel(CONCURRENCY_LEVEL)
+    );
+}
+
+// A policy that always picks [strategy], recording the features it decides on.
+#[derive(Debug)]
+struct RecordingPolicy {
+    strategy: ExecutionStrategy,
+    decided: Arc<Mutex<Vec<BlockFeatures>>>,
+}
+
+impl ExecutionPolicy for RecordingPolicy {
+    fn decide(
+        &self,
+        features: &BlockFeatures,
+        _concurrency_level: NonZeroUsize,
+    ) -> ExecutionStrategy {
+        self.decided.lock().unwrap().push(features.clone());
+        self.strategy
+    }
+}
+
+#[test]
+fn custom_policy() {
+    const BLOCK_SIZE: usize = 1_000;
+    // Mock senders and recipients after the precompile addresses, which raw
+    // transfers cannot pay.
+    const FIRST_ACCOUNT: usize = 0x100;
+    let chain = PevmEthereum::mainnet();
+    let storage = InMemoryStorage::new(
+        (FIRST_ACCOUNT..FIRST_ACCOUNT + BLOCK_SIZE)
+            .map(common::mock_account)
+            .chain([common::mock_account(0)])
+            .collect(),
+        Default::default(),
+        Default::default(),
+    );
+    let txs = (0..BLOCK_SIZE)
+        .map(|i| {
+            let (caller, account) = common::mock_account(FIRST_ACCOUNT + i);
+            TxEnv {
+                caller,
+                nonce: account.nonce,
+                kind: TransactTo::Call(caller),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: 1,
+                ..TxEnv::default()
+            }
+        })
+        .collect::<Vec<_>>();
+    let block_env = BlockEnv {
+        gas_limit: BLOCK_SIZE as u64 * common::RAW_TRANSFER_GAS_LIMIT,
+        ..BlockEnv::default()
+    };
+    let sequential_results = execute_revm_sequential(
+        &chain,
+        &storage,
+        Default::default(),
+        block_env.clone(),
+        txs.clone(),
+    )
+    .unwrap();
+
+    // Both strategies, including parallel execution with more workers than
+    // the concurrency level, build the same block.
+    for strategy in [
+        ExecutionStrategy::Sequential,
+        ExecutionStrategy::Parallel(NonZeroUsize::new(64).unwrap()),
+    ] {
+        let decided = Arc::new(Mutex::new(Vec::new()));
+        let built_block = Pevm::default()
+            .with_policy(RecordingPolicy {
+                strategy,
+                decided: decided.clone(),
+            })
+            .build_block(
+                &chain,
+                &storage,
+                Default::default(),
+                block_env.clone(),
+                txs.clone(),
+                None,
+                CONCURRENCY_LEVEL,
+            )
+            .unwrap();
+        assert_eq!(built_block.tx_results, sequential_results);
+        assert_eq!(*decided.lock().unwrap(), [BlockFeatures::new(&txs)]);
+    }
+}
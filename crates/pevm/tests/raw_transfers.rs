@@ -1,12 +1,12 @@
 //! Test raw transfers -- only send some ETH from one account to another without extra data.
 
-use std::collections::HashMap;
+use std::{collections::HashMap, num::NonZeroUsize};
 
-use pevm::{InMemoryStorage, chain::PevmEthereum};
+use pevm::{InMemoryStorage, Pevm, chain::PevmEthereum};
 use rand::random;
 use revm::{
-    context::{TransactTo, TxEnv},
-    primitives::{Address, U256, alloy_primitives::U160},
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{Address, U256, alloy_primitives::U160, hardfork::SpecId},
 };
 
 pub mod common;
@@ -86,6 +86,240 @@ fn raw_transfers_same_sender_multiple_txs() {
     );
 }
 
+// A few senders sending interleaved transfers to each other, forming nonce
+// chains whose transactions also read the balances written by other chains.
+#[test]
+fn raw_transfers_interleaved_senders() {
+    let block_size = 5_000; // number of transactions
+    let senders = 10;
+    // Mock senders after the precompile addresses, which raw transfers cannot pay.
+    let first_sender = 0x100;
+    let mut nonces = HashMap::new();
+
+    common::test_execute_revm(
+        &PevmEthereum::mainnet(),
+        // Mock the beneficiary account (`Address:ZERO`) and the senders.
+        InMemoryStorage::new(
+            (first_sender..first_sender + senders)
+                .map(common::mock_account)
+                .chain([common::mock_account(0)])
+                .collect(),
+            Default::default(),
+            Default::default(),
+        ),
+        (0..block_size)
+            .map(|i| {
+                let (address, _) = common::mock_account(first_sender + i % senders);
+                let (to, _) = common::mock_account(first_sender + (i + 1) % senders);
+                let nonce = nonces.entry(address).or_insert(0);
+                *nonce += 1;
+                TxEnv {
+                    caller: address,
+                    nonce: *nonce,
+                    kind: TransactTo::Call(to),
+                    value: U256::from(1),
+                    gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                    gas_price: 1,
+                    ..TxEnv::default()
+                }
+            })
+            .collect(),
+    );
+}
+
+// A hot wallet interleaving withdrawals to users with deposits from them,
+// which write its balance while its own transactions also chain its nonce.
+#[test]
+fn raw_transfers_hot_wallet() {
+    let block_size = 5_000; // number of transactions
+    let users = 1_000;
+    // Mock accounts after the precompile addresses, which raw transfers cannot pay.
+    let hot_wallet = 0x100;
+    let first_user = hot_wallet + 1;
+    let mut nonces = HashMap::new();
+
+    common::test_execute_revm(
+        &PevmEthereum::mainnet(),
+        // Mock the beneficiary account (`Address:ZERO`), the hot wallet and the users.
+        InMemoryStorage::new(
+            (hot_wallet..first_user + users)
+                .map(common::mock_account)
+                .chain([common::mock_account(0)])
+                .collect(),
+            Default::default(),
+            Default::default(),
+        ),
+        (0..block_size)
+            .map(|i| {
+                let (wallet, _) = common::mock_account(hot_wallet);
+                let (user, _) = common::mock_account(first_user + i % users);
+                let (caller, to) = if i % 3 == 0 {
+                    (wallet, user)
+                } else {
+                    (user, wallet)
+                };
+                let nonce = nonces.entry(caller).or_insert(0);
+                *nonce += 1;
+                TxEnv {
+                    caller,
+                    nonce: *nonce,
+                    kind: TransactTo::Call(to),
+                    value: U256::from(i),
+                    gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                    gas_price: 1,
+                    ..TxEnv::default()
+                }
+            })
+            .collect(),
+    );
+}
+
+// New accounts funded by earlier transactions in the block before sending
+// their own, which must wait for their funding instead of the previous
+// transaction.
+#[test]
+fn raw_transfers_funded_senders() {
+    let users = 1_000;
+    // Mock accounts after the precompile addresses, which raw transfers cannot pay.
+    let funder = 0x100;
+    let first_user = 0x1000;
+
+    common::test_execute_revm(
+        &PevmEthereum::mainnet(),
+        // Mock the beneficiary account (`Address:ZERO`) and the funder, but not the users.
+        InMemoryStorage::new(
+            [common::mock_account(0), common::mock_account(funder)]
+                .into_iter()
+                .collect(),
+            Default::default(),
+            Default::default(),
+        ),
+        // The funder funds all users, who then send to themselves in reverse order.
+        (0..users)
+            .map(|i| TxEnv {
+                caller: common::mock_account(funder).0,
+                nonce: i as u64 + 1,
+                kind: TransactTo::Call(common::mock_account(first_user + i).0),
+                value: U256::from(1_000_000),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: 1,
+                ..TxEnv::default()
+            })
+            .chain((0..users).rev().map(|i| {
+                let (user, _) = common::mock_account(first_user + i);
+                TxEnv {
+                    caller: user,
+                    nonce: 0,
+                    kind: TransactTo::Call(user),
+                    value: U256::from(i),
+                    gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                    gas_price: 1,
+                    ..TxEnv::default()
+                }
+            }))
+            .collect(),
+    );
+}
+
+// A faulty block with a nonce gap that no earlier transaction fills, which
+// must fail like sequential execution instead of retrying forever.
+#[test]
+fn raw_transfers_nonce_gap() {
+    let chain = PevmEthereum::mainnet();
+    let first_sender = 0x100;
+    let storage = InMemoryStorage::new(
+        (first_sender..first_sender + 10)
+            .map(common::mock_account)
+            .chain([common::mock_account(0)])
+            .collect(),
+        Default::default(),
+        Default::default(),
+    );
+    let txs: Vec<TxEnv> = (0..10)
+        .map(|i| {
+            let (address, _) = common::mock_account(first_sender + i);
+            TxEnv {
+                caller: address,
+                nonce: if i == 5 { 3 } else { 1 },
+                kind: TransactTo::Call(address),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: 1,
+                ..TxEnv::default()
+            }
+        })
+        .collect();
+
+    let sequential_result = pevm::execute_revm_sequential(
+        &chain,
+        &storage,
+        SpecId::default(),
+        BlockEnv::default(),
+        txs.clone(),
+    );
+    assert!(sequential_result.is_err());
+    assert_eq!(
+        sequential_result,
+        Pevm::default().execute_revm_parallel(
+            &chain,
+            &storage,
+            SpecId::default(),
+            BlockEnv::default(),
+            txs,
+            NonZeroUsize::new(4).unwrap(),
+        )
+    );
+}
+
+// A faulty block with a nonce gap in a chain of transfers from the same
+// sender, which may be lazily updated instead of checked during execution.
+#[test]
+fn raw_transfers_same_sender_nonce_gap() {
+    let chain = PevmEthereum::mainnet();
+    let (sender, _) = common::mock_account(0x100);
+    let storage = InMemoryStorage::new(
+        [common::mock_account(0x100), common::mock_account(0)]
+            .into_iter()
+            .collect(),
+        Default::default(),
+        Default::default(),
+    );
+    let txs: Vec<TxEnv> = (1..=100)
+        .map(|i| TxEnv {
+            caller: sender,
+            nonce: if i < 50 { i } else { i + 1 },
+            kind: TransactTo::Call(common::mock_account(0x1000 + i as usize).0),
+            value: U256::from(1),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: 1,
+            ..TxEnv::default()
+        })
+        .collect();
+
+    assert!(
+        pevm::execute_revm_sequential(
+            &chain,
+            &storage,
+            SpecId::default(),
+            BlockEnv::default(),
+            txs.clone(),
+        )
+        .is_err()
+    );
+    assert!(
+        Pevm::default()
+            .execute_revm_parallel(
+                &chain,
+                &storage,
+                SpecId::default(),
+                BlockEnv::default(),
+                txs,
+                NonZeroUsize::new(4).unwrap(),
+            )
+            .is_err()
+    );
+}
+
 #[test]
 fn ethereum_empty_alloy_block() {
     common::test_independent_raw_transfers(&PevmEthereum::mainnet(), 0);
@@ -0,0 +1,169 @@
+//! Test replaying the recorded schedules of blocks executed in parallel.
+
+use std::num::NonZeroUsize;
+
+use pevm::{
+    InMemoryStorage, Pevm, PevmError, PevmMode, PevmResult, ScheduleEvent, ScheduleLog,
+    chain::PevmEthereum, execute_revm_sequential,
+};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{U256, hardfork::SpecId},
+};
+
+pub mod common;
+
+const BLOCK_SIZE: usize = 100;
+
+// Mock senders after the precompile addresses, which raw transfers cannot pay.
+const FIRST_SENDER: usize = 0x100;
+
+fn mock_storage() -> InMemoryStorage {
+    InMemoryStorage::new(
+        (FIRST_SENDER..FIRST_SENDER + 10)
+            .map(common::mock_account)
+            .chain([common::mock_account(0)])
+            .collect(),
+        Default::default(),
+        Default::default(),
+    )
+}
+
+// Raw transfers between a few senders, which depend on each other's nonces
+// and balances.
+fn mock_txs() -> Vec<TxEnv> {
+    (0..BLOCK_SIZE)
+        .map(|i| TxEnv {
+            caller: common::mock_account(FIRST_SENDER + i % 10).0,
+            nonce: (i / 10) as u64 + 1,
+            kind: TransactTo::Call(common::mock_account(FIRST_SENDER + (i + 1) % 10).0),
+            value: U256::from(i),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: 1,
+            ..TxEnv::default()
+        })
+        .collect()
+}
+
+// Record the schedule of executing the mock block in parallel, checking
+// that the recording does not change the result.
+fn record(concurrency_level: usize) -> (PevmResult<PevmEthereum>, ScheduleLog) {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    let sequential_result = execute_revm_sequential(
+        &chain,
+        &storage,
+        SpecId::default(),
+        BlockEnv::default(),
+        mock_txs(),
+    );
+    assert!(sequential_result.is_ok());
+
+    // Build blocks to read the nonces of the senders instead of lazily
+    // updating them, for more dependencies.
+    let mut pevm = Pevm::with_mode(PevmMode::Build).with_schedule_recording();
+    assert_eq!(
+        pevm.execute_revm_parallel(
+            &chain,
+            &storage,
+            SpecId::default(),
+            BlockEnv::default(),
+            mock_txs(),
+            NonZeroUsize::new(concurrency_level).unwrap(),
+        ),
+        sequential_result
+    );
+    let log = pevm.take_schedule_log().unwrap();
+    assert_eq!(pevm.take_schedule_log(), None);
+    for tx_idx in 0..BLOCK_SIZE {
+        assert!(log.events.contains(&ScheduleEvent::Execution {
+            tx_idx,
+            incarnation: 0
+        }));
+    }
+    (sequential_result, log)
+}
+
+// Replays must build blocks like the recording, to read the same locations.
+fn replay(pevm: &mut Pevm, log: &ScheduleLog) -> PevmResult<PevmEthereum> {
+    pevm.replay_revm_parallel(
+        &PevmEthereum::mainnet(),
+        &mock_storage(),
+        SpecId::default(),
+        BlockEnv::default(),
+        mock_txs(),
+        log,
+    )
+}
+
+#[test]
+fn replay_sequential_schedule() {
+    let (sequential_result, log) = record(1);
+    let mut pevm = Pevm::with_mode(PevmMode::Build).with_schedule_recording();
+    assert_eq!(replay(&mut pevm, &log), sequential_result);
+    // Replays are not recorded.
+    assert_eq!(pevm.take_schedule_log(), None);
+}
+
+#[test]
+fn replay_parallel_schedule() {
+    let (sequential_result, log) = record(4);
+    assert_eq!(
+        serde_json::from_str::<ScheduleLog>(&serde_json::to_string(&log).unwrap()).unwrap(),
+        log
+    );
+
+    // Replays are deterministic, and only diverge from the recording on
+    // executions that raced with the writes they read.
+    let mut pevm = Pevm::with_mode(PevmMode::Build);
+    let replay_result = replay(&mut pevm, &log);
+    assert_eq!(replay(&mut pevm, &log), replay_result);
+    if !matches!(replay_result, Err(PevmError::ReplayDiverged { .. })) {
+        assert_eq!(replay_result, sequential_result);
+    }
+}
+
+#[test]
+fn diverged_replay() {
+    // The first transaction never aborts to execute a second incarnation.
+    let log = ScheduleLog {
+        events: vec![
+            ScheduleEvent::Execution {
+                tx_idx: 0,
+                incarnation: 0,
+            },
+            ScheduleEvent::Execution {
+                tx_idx: 0,
+                incarnation: 1,
+            },
+        ],
+    };
+    assert_eq!(
+        replay(&mut Pevm::with_mode(PevmMode::Build), &log),
+        Err(PevmError::ReplayDiverged { position: 1 })
+    );
+}
+
+#[test]
+fn diverged_reads() {
+    let (_, mut log) = record(1);
+    // An execution that read from other transaction versions than recorded.
+    let position = log
+        .events
+        .iter()
+        .rposition(|event| matches!(event, ScheduleEvent::FinishedExecution { .. }))
+        .unwrap();
+    if let ScheduleEvent::FinishedExecution { reads, .. } = &mut log.events[position] {
+        *reads = reads.wrapping_add(1);
+    }
+    assert_eq!(
+        replay(&mut Pevm::with_mode(PevmMode::Build), &log),
+        Err(PevmError::ReplayDiverged { position })
+    );
+    // Replays in another mode read other locations.
+    let (_, log) = record(1);
+    assert!(matches!(
+        replay(&mut Pevm::default(), &log),
+        Err(PevmError::ReplayDiverged { .. })
+    ));
+}
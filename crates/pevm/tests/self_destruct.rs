@@ -0,0 +1,247 @@
+//! Test self-destructing and re-creating accounts mid-block, which must be
+//! executed in parallel instead of falling back to sequential execution.
+
+use std::num::NonZeroUsize;
+
+use pevm::{
+    Bytecodes, ChainState, EvmAccount, EvmCode, InMemoryStorage, Pevm, PevmTxExecutionResult,
+    chain::PevmEthereum, execute_revm_sequential,
+};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{Address, B256, Bytes, U256, bytes, hardfork::SpecId},
+    state::Bytecode,
+};
+
+pub mod common;
+
+// PUSH1 1 SLOAD PUSH1 1 ADD PUSH1 1 SSTORE STOP
+const COUNTER_INIT_CODE: Bytes = bytes!("600a600c600039600a6000f360015460010160015500");
+
+// CODECOPY the counter init code and CREATE2 it with a zero salt.
+const FACTORY_CODE: Bytes =
+    bytes!("601660116000396000601660006000f500600a600c600039600a6000f360015460010160015500");
+
+// CALLER SELFDESTRUCT
+const SELF_DESTRUCT_CODE: Bytes = bytes!("33ff");
+
+// MSTORE the self-destructing code as init code and CREATE2 it with the call
+// value and a zero salt.
+const SELF_DESTRUCT_FACTORY_CODE: Bytes = bytes!("6133ff60005260006002601e34f500");
+
+// PUSH1 1 SLOAD PUSH1 1 ADD PUSH1 1 SSTORE CALLER SELFDESTRUCT
+const COUNTER_SELF_DESTRUCT_CODE: Bytes = bytes!("60015460010160015533ff");
+
+fn mock_contract(bytecodes: &mut Bytecodes, code: Bytes, storage: &[(U256, U256)]) -> EvmAccount {
+    let code = Bytecode::new_raw(code);
+    let code_hash = code.hash_slow();
+    bytecodes.insert(code_hash, EvmCode::from(code));
+    EvmAccount {
+        balance: U256::from(10),
+        nonce: 1,
+        code_hash: Some(code_hash),
+        storage: storage.iter().copied().collect(),
+        ..EvmAccount::default()
+    }
+}
+
+// Calls from the mock accounts, each sending its first transaction.
+fn mock_calls(calls: &[(usize, Address, u64)]) -> Vec<TxEnv> {
+    calls
+        .iter()
+        .map(|(i, to, value)| TxEnv {
+            caller: common::mock_account(*i).0,
+            nonce: 1,
+            kind: TransactTo::Call(*to),
+            value: U256::from(*value),
+            gas_limit: 200_000,
+            ..TxEnv::default()
+        })
+        .collect()
+}
+
+// Execute [txs] in parallel without falling back to sequential execution, and
+// match the results of the sequential execution.
+fn execute_parallel(
+    storage: &InMemoryStorage,
+    spec_id: SpecId,
+    txs: Vec<TxEnv>,
+) -> Vec<PevmTxExecutionResult> {
+    let chain = PevmEthereum::mainnet();
+    let sequential_results =
+        execute_revm_sequential(&chain, storage, spec_id, BlockEnv::default(), txs.clone())
+            .unwrap();
+    for concurrency_level in [NonZeroUsize::MIN, NonZeroUsize::new(4).unwrap()] {
+        let mut pevm = Pevm::default().with_stats();
+        assert_eq!(
+            pevm.execute_revm_parallel(
+                &chain,
+                storage,
+                spec_id,
+                BlockEnv::default(),
+                txs.clone(),
+                concurrency_level,
+            )
+            .unwrap(),
+            sequential_results
+        );
+        assert_eq!(pevm.take_stats().unwrap().fallback, None);
+    }
+    sequential_results
+}
+
+#[test]
+fn self_destruct_and_recreate() {
+    let chain = PevmEthereum::mainnet();
+    let factory = Address::repeat_byte(0x66);
+    let counter = factory.create2_from_code(B256::ZERO, COUNTER_INIT_CODE);
+
+    let mut bytecodes = Bytecodes::default();
+    let mut accounts: ChainState = (1..=8).map(common::mock_account).collect();
+    accounts.insert(factory, mock_contract(&mut bytecodes, FACTORY_CODE, &[]));
+    // CALLER SELFDESTRUCT, at the address that the factory re-creates the
+    // counter at, with a stale counter to be cleared.
+    accounts.insert(
+        counter,
+        mock_contract(
+            &mut bytecodes,
+            bytes!("33ff"),
+            &[(U256::from(1), U256::from(100))],
+        ),
+    );
+    let storage = InMemoryStorage::new(accounts, bytecodes.into(), Default::default());
+
+    let txs: Vec<TxEnv> = [
+        // Fails to re-create the counter before it self-destructs.
+        (1, factory, 0),
+        (2, counter, 0),
+        // Transfers to and calls the self-destructed account.
+        (3, counter, 5),
+        (4, counter, 0),
+        (5, factory, 0),
+        // Increments the re-created counter from a cleared slot.
+        (6, counter, 0),
+        (7, counter, 7),
+        (8, counter, 0),
+    ]
+    .into_iter()
+    .map(|(i, to, value)| TxEnv {
+        caller: common::mock_account(i).0,
+        nonce: 1,
+        kind: TransactTo::Call(to),
+        value: U256::from(value),
+        gas_limit: 200_000,
+        ..TxEnv::default()
+    })
+    .collect();
+
+    for concurrency_level in [NonZeroUsize::MIN, NonZeroUsize::new(4).unwrap()] {
+        let mut pevm = Pevm::default();
+        let ((), result) = pevm.session(
+            &chain,
+            &storage,
+            SpecId::SHANGHAI,
+            BlockEnv::default(),
+            concurrency_level,
+            |session| {
+                for tx in txs.clone() {
+                    session.push_tx(tx);
+                }
+            },
+        );
+        let (tx_results, _) = result.unwrap();
+
+        let sequential_results = execute_revm_sequential(
+            &chain,
+            &storage,
+            SpecId::SHANGHAI,
+            BlockEnv::default(),
+            txs.clone(),
+        )
+        .unwrap();
+        assert_eq!(tx_results, sequential_results);
+
+        // The counter was destroyed before being re-created and incremented.
+        assert_eq!(tx_results[1].state.get(&counter), Some(&None));
+        let counter_account = tx_results[7].state.get(&counter).unwrap().as_ref().unwrap();
+        assert_eq!(counter_account.balance, U256::from(12));
+        assert_eq!(
+            counter_account.storage.get(&U256::from(1)),
+            Some(&U256::from(3))
+        );
+    }
+}
+
+// Since Cancun (EIP-6780), accounts created in the same transaction are still
+// deleted when they self-destruct, and can be created again at the same
+// address by later transactions.
+#[test]
+fn cancun_create_and_self_destruct() {
+    let factory = Address::repeat_byte(0x66);
+    let child = factory.create2_from_code(B256::ZERO, SELF_DESTRUCT_CODE);
+
+    let mut bytecodes = Bytecodes::default();
+    let mut accounts: ChainState = (1..=4).map(common::mock_account).collect();
+    accounts.insert(
+        factory,
+        mock_contract(&mut bytecodes, SELF_DESTRUCT_FACTORY_CODE, &[]),
+    );
+    let storage = InMemoryStorage::new(accounts, bytecodes.into(), Default::default());
+
+    // The child sends its endowment back to the factory as it self-destructs.
+    let txs = mock_calls(&[
+        (1, factory, 1),
+        (2, factory, 2),
+        (3, factory, 3),
+        (4, factory, 4),
+    ]);
+    let tx_results = execute_parallel(&storage, SpecId::CANCUN, txs);
+
+    for (tx_idx, tx_result) in tx_results.iter().enumerate() {
+        assert_eq!(tx_result.state.get(&child), Some(&None));
+        let factory_account = tx_result.state[&factory].as_ref().unwrap();
+        assert_eq!(factory_account.nonce, 2 + tx_idx as u64);
+    }
+    let factory_account = tx_results[3].state[&factory].as_ref().unwrap();
+    assert_eq!(factory_account.balance, U256::from(10 + 1 + 2 + 3 + 4));
+}
+
+// Since Cancun (EIP-6780), self-destructing an account created before only
+// sends its balance away, keeping its code and storage.
+#[test]
+fn cancun_self_destruct_keeps_storage() {
+    let counter = Address::repeat_byte(0x77);
+
+    let mut bytecodes = Bytecodes::default();
+    let mut accounts: ChainState = (1..=4).map(common::mock_account).collect();
+    accounts.insert(
+        counter,
+        mock_contract(
+            &mut bytecodes,
+            COUNTER_SELF_DESTRUCT_CODE,
+            &[(U256::from(1), U256::from(100))],
+        ),
+    );
+    let code_hash = accounts[&counter].code_hash;
+    let storage = InMemoryStorage::new(accounts, bytecodes.into(), Default::default());
+
+    // Each call increments the counter from the previous value, then sends
+    // the counter's balance to the caller.
+    let txs = mock_calls(&[
+        (1, counter, 0),
+        (2, counter, 5),
+        (3, counter, 0),
+        (4, counter, 7),
+    ]);
+    let tx_results = execute_parallel(&storage, SpecId::CANCUN, txs);
+
+    for (tx_idx, tx_result) in tx_results.iter().enumerate() {
+        let counter_account = tx_result.state[&counter].as_ref().unwrap();
+        assert_eq!(counter_account.balance, U256::ZERO);
+        assert_eq!(counter_account.code_hash, code_hash);
+        assert_eq!(
+            counter_account.storage.get(&U256::from(1)),
+            Some(&U256::from(101 + tx_idx))
+        );
+    }
+}
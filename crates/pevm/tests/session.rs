@@ -0,0 +1,228 @@
+//! Test streaming transactions to an in-flight block.
+
+use std::{num::NonZeroUsize, sync::mpsc, thread};
+
+use pevm::{InMemoryStorage, Pevm, PevmMode, chain::PevmEthereum, execute_revm_sequential};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{Address, U256, alloy_primitives::U160},
+};
+
+pub mod common;
+
+const BLOCK_SIZE: usize = 100;
+
+// A hot recipient that many transactions transfer to.
+const HOT_RECIPIENT: usize = 2 * BLOCK_SIZE;
+
+fn mock_storage() -> InMemoryStorage {
+    InMemoryStorage::new(
+        (0..=BLOCK_SIZE).map(common::mock_account).collect(),
+        Default::default(),
+        Default::default(),
+    )
+}
+
+// Transfers with dependencies across batches, and a transaction that is
+// excluded as its sender is only funded later.
+fn mock_txs() -> Vec<TxEnv> {
+    let unfunded = Address::repeat_byte(0x77);
+    (1..=BLOCK_SIZE)
+        .map(|i| {
+            let (caller, nonce, to) = match i {
+                // Sending before and after being funded.
+                30 | 50 => (unfunded, 0, Address::from(U160::from(i))),
+                40 => (Address::from(U160::from(i)), 1, unfunded),
+                // Sending again from the previous sender.
+                _ if i % 7 == 0 => (
+                    Address::from(U160::from(i - 1)),
+                    2,
+                    Address::from(U160::from(HOT_RECIPIENT)),
+                ),
+                _ if i % 3 == 0 => (
+                    Address::from(U160::from(i)),
+                    1,
+                    Address::from(U160::from(HOT_RECIPIENT)),
+                ),
+                // Skip the precompile addresses that charge more gas.
+                _ => (
+                    Address::from(U160::from(i)),
+                    1,
+                    Address::from(U160::from(BLOCK_SIZE + i)),
+                ),
+            };
+            TxEnv {
+                caller,
+                nonce,
+                kind: TransactTo::Call(to),
+                // Fund the previously unfunded sender enough for gas.
+                value: U256::from(if i == 40 { 1_000_000 } else { 1_000 }),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: 1,
+                ..TxEnv::default()
+            }
+        })
+        .collect()
+}
+
+fn test_session(mode: PevmMode, concurrency_level: NonZeroUsize) {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    let txs = mock_txs();
+
+    let mut pevm = Pevm::with_mode(mode);
+    let (receipts, result) = pevm.session(
+        &chain,
+        &storage,
+        Default::default(),
+        BlockEnv::default(),
+        concurrency_level,
+        |session| {
+            let mut receipts = Vec::new();
+            for (i, tx) in txs.iter().enumerate() {
+                receipts.extend(session.push_tx(tx.clone()));
+                // Wait for unevenly sized batches too.
+                if i % 11 == 0 {
+                    receipts.extend(session.flush());
+                }
+            }
+            receipts.extend(session.flush());
+            receipts
+        },
+    );
+    let (tx_results, excluded_txs) = result.unwrap();
+
+    assert_eq!(
+        excluded_txs
+            .iter()
+            .map(|(tx_idx, _)| *tx_idx)
+            .collect::<Vec<_>>(),
+        vec![29]
+    );
+    assert_eq!(
+        receipts
+            .iter()
+            .map(|(tx_idx, _)| *tx_idx)
+            .collect::<Vec<_>>(),
+        (0..BLOCK_SIZE).collect::<Vec<_>>()
+    );
+    assert!(receipts[29].1.is_err());
+
+    // The streamed block executes the same as a canonical block.
+    let included_txs = txs
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 29)
+        .map(|(_, tx)| tx)
+        .collect::<Vec<_>>();
+    let sequential_results = execute_revm_sequential(
+        &chain,
+        &storage,
+        Default::default(),
+        BlockEnv::default(),
+        included_txs,
+    )
+    .unwrap();
+    assert_eq!(tx_results, sequential_results);
+    assert_eq!(
+        receipts
+            .into_iter()
+            .filter_map(|(_, receipt)| receipt.ok())
+            .collect::<Vec<_>>(),
+        sequential_results
+            .into_iter()
+            .map(|tx_result| tx_result.receipt)
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn session_batches() {
+    test_session(PevmMode::Build, NonZeroUsize::new(4).unwrap());
+}
+
+#[test]
+fn session_single_worker() {
+    test_session(PevmMode::Build, NonZeroUsize::MIN);
+}
+
+#[test]
+fn session_test_mode() {
+    test_session(PevmMode::Test, NonZeroUsize::new(8).unwrap());
+}
+
+#[test]
+fn empty_session() {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    let mut pevm = Pevm::default();
+    let (receipts, result) = pevm.session(
+        &chain,
+        &storage,
+        Default::default(),
+        BlockEnv::default(),
+        NonZeroUsize::new(8).unwrap(),
+        |session| session.flush(),
+    );
+    assert!(receipts.is_empty());
+    let (tx_results, excluded_txs) = result.unwrap();
+    assert!(tx_results.is_empty());
+    assert!(excluded_txs.is_empty());
+}
+
+#[test]
+fn streamed_session() {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    let txs = mock_txs();
+
+    // Transactions arrive from another thread while the pushed ones execute.
+    let (sender, receiver) = mpsc::channel();
+    let mut pevm = Pevm::default();
+    let ((), result) = thread::scope(|scope| {
+        scope.spawn(|| {
+            for tx in mock_txs() {
+                sender.send(tx).unwrap();
+            }
+            drop(sender);
+        });
+        pevm.session(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            NonZeroUsize::new(4).unwrap(),
+            |session| {
+                for tx in receiver {
+                    session.push_tx(tx);
+                }
+            },
+        )
+    });
+    let (tx_results, excluded_txs) = result.unwrap();
+
+    assert_eq!(
+        excluded_txs
+            .iter()
+            .map(|(tx_idx, _)| *tx_idx)
+            .collect::<Vec<_>>(),
+        vec![29]
+    );
+    let included_txs = txs
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 29)
+        .map(|(_, tx)| tx)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        tx_results,
+        execute_revm_sequential(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            included_txs,
+        )
+        .unwrap()
+    );
+}
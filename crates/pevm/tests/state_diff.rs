@@ -0,0 +1,217 @@
+//! Test the merged state changes of a block and applying them to storage.
+
+use std::{num::NonZeroUsize, sync::Arc};
+
+use alloy_consensus::{Signed, TxLegacy};
+use alloy_eips::{
+    eip4788::{BEACON_ROOTS_ADDRESS, BEACON_ROOTS_CODE},
+    eip4895::{Withdrawal, Withdrawals},
+};
+use alloy_primitives::{Address, B256, Bytes, Signature, TxKind, U256, bytes};
+use alloy_rpc_types_eth::{Block, BlockTransactions, Header};
+use pevm::{
+    AccountBasic, Bytecodes, ChainState, EvmAccount, EvmCode, InMemoryStorage, Pevm, Storage,
+    StorageSlotDiff,
+    chain::{PevmChain, PevmEthereum},
+};
+use revm::state::Bytecode;
+
+pub mod common;
+
+const SHANGHAI_TIMESTAMP: u64 = 1681338455;
+const CANCUN_TIMESTAMP: u64 = 1710338135;
+
+fn mock_block(
+    chain: &PevmEthereum,
+    header: alloy_consensus::Header,
+    txs: Vec<(Address, u64, Address, u64)>,
+) -> Block<alloy_rpc_types_eth::Transaction> {
+    Block {
+        header: Header {
+            inner: header,
+            ..Default::default()
+        },
+        transactions: BlockTransactions::Full(
+            txs.into_iter()
+                .map(|(from, nonce, to, value)| {
+                    chain.mock_tx(
+                        Signed::new_unchecked(
+                            TxLegacy {
+                                chain_id: Some(chain.id()),
+                                nonce,
+                                gas_price: 0,
+                                gas_limit: 100_000,
+                                to: TxKind::Call(to),
+                                value: U256::from(value),
+                                input: Bytes::default(),
+                            },
+                            Signature::new(U256::ZERO, U256::ZERO, false),
+                            B256::default(),
+                        )
+                        .into(),
+                        from,
+                    )
+                })
+                .collect(),
+        ),
+        ..Block::default()
+    }
+}
+
+fn mock_contract(code: Bytes, balance: U256, storage: &[(U256, U256)]) -> (EvmAccount, EvmCode) {
+    let code = Bytecode::new_raw(code);
+    (
+        EvmAccount {
+            balance,
+            nonce: 1,
+            code_hash: Some(code.hash_slow()),
+            storage: storage.iter().copied().collect(),
+            ..EvmAccount::default()
+        },
+        EvmCode::from(code),
+    )
+}
+
+#[test]
+fn transfers_system_calls_and_withdrawals() {
+    let chain = PevmEthereum::mainnet();
+    let (sender, sender_account) = common::mock_account(1);
+    let recipient = Address::repeat_byte(0x44);
+    let (beacon_roots, beacon_roots_code) =
+        mock_contract(BEACON_ROOTS_CODE.clone(), U256::ZERO, &[]);
+    let mut accounts = ChainState::default();
+    accounts.insert(sender, sender_account.clone());
+    accounts.insert(BEACON_ROOTS_ADDRESS, beacon_roots.clone());
+    let mut bytecodes = Bytecodes::default();
+    bytecodes.insert(beacon_roots.code_hash.unwrap(), beacon_roots_code);
+    let mut storage = InMemoryStorage::new(accounts, Arc::new(bytecodes), Default::default());
+
+    let parent_beacon_block_root = B256::repeat_byte(0x42);
+    let mut block = mock_block(
+        &chain,
+        alloy_consensus::Header {
+            number: 1,
+            timestamp: CANCUN_TIMESTAMP,
+            gas_limit: 30_000_000,
+            excess_blob_gas: Some(0),
+            parent_beacon_block_root: Some(parent_beacon_block_root),
+            ..Default::default()
+        },
+        vec![
+            (sender, sender_account.nonce, recipient, 1),
+            (sender, sender_account.nonce + 1, recipient, 2),
+        ],
+    );
+    block.withdrawals = Some(Withdrawals::new(vec![Withdrawal {
+        index: 0,
+        validator_index: 0,
+        address: recipient,
+        amount: 1,
+    }]));
+    common::test_execute_alloy(&chain, &storage, block.clone(), &[], false);
+
+    let diff = Pevm::default()
+        .execute(&chain, &storage, &block, NonZeroUsize::MIN, true)
+        .unwrap()
+        .state_diff;
+
+    // The sender and recipient merge the changes of both transactions and the withdrawal.
+    let sender_diff = diff.accounts.get(&sender).unwrap();
+    assert_eq!(
+        sender_diff.present,
+        Some(AccountBasic {
+            balance: sender_account.balance - U256::from(3),
+            nonce: sender_account.nonce + 2,
+        })
+    );
+    let recipient_diff = diff.accounts.get(&recipient).unwrap();
+    assert_eq!(recipient_diff.original, None);
+    assert_eq!(
+        recipient_diff.present,
+        Some(AccountBasic {
+            balance: U256::from(3 + 1_000_000_000u64),
+            nonce: 0,
+        })
+    );
+    // The beacon roots contract has two new slots.
+    let beacon_roots_diff = diff.accounts.get(&BEACON_ROOTS_ADDRESS).unwrap();
+    assert_eq!(beacon_roots_diff.storage.len(), 2);
+    assert!(beacon_roots_diff.storage.values().any(|slot| slot
+        == &StorageSlotDiff {
+            original: U256::ZERO,
+            present: parent_beacon_block_root.into(),
+        }));
+    assert_eq!(diff.accounts.len(), 3);
+    assert!(diff.new_bytecodes.is_empty());
+    assert_eq!(diff.destroyed_accounts().count(), 0);
+
+    storage.apply_state_diff(&diff);
+    assert_eq!(storage.basic(&recipient).unwrap(), recipient_diff.present);
+    assert_eq!(storage.basic(&sender).unwrap(), sender_diff.present);
+    for (slot, slot_diff) in &beacon_roots_diff.storage {
+        assert_eq!(
+            storage.storage(&BEACON_ROOTS_ADDRESS, slot).unwrap(),
+            slot_diff.present
+        );
+    }
+}
+
+#[test]
+fn self_destructed_contract() {
+    let chain = PevmEthereum::mainnet();
+    let (sender, sender_account) = common::mock_account(1);
+    let contract_address = Address::repeat_byte(0x55);
+    // CALLER SELFDESTRUCT
+    let (contract, contract_code) = mock_contract(
+        bytes!("33ff"),
+        U256::from(10),
+        &[(U256::from(1), U256::from(1))],
+    );
+    let mut accounts = ChainState::default();
+    accounts.insert(sender, sender_account.clone());
+    accounts.insert(contract_address, contract.clone());
+    let mut bytecodes = Bytecodes::default();
+    bytecodes.insert(contract.code_hash.unwrap(), contract_code);
+    let mut storage = InMemoryStorage::new(accounts, Arc::new(bytecodes), Default::default());
+
+    // Self-destructs only fully destroy pre-Cancun contracts.
+    let block = mock_block(
+        &chain,
+        alloy_consensus::Header {
+            number: 17034870,
+            timestamp: SHANGHAI_TIMESTAMP,
+            gas_limit: 30_000_000,
+            ..Default::default()
+        },
+        vec![(sender, sender_account.nonce, contract_address, 0)],
+    );
+    common::test_execute_alloy(&chain, &storage, block.clone(), &[], false);
+
+    let diff = Pevm::default()
+        .execute(&chain, &storage, &block, NonZeroUsize::MIN, true)
+        .unwrap()
+        .state_diff;
+
+    let contract_diff = diff.accounts.get(&contract_address).unwrap();
+    assert!(contract_diff.destroyed);
+    assert_eq!(contract_diff.present, None);
+    assert_eq!(contract_diff.code_hash, None);
+    assert!(contract_diff.storage.is_empty());
+    assert_eq!(
+        diff.destroyed_accounts().collect::<Vec<_>>(),
+        vec![&contract_address]
+    );
+    // The contract balance goes to the caller.
+    assert_eq!(
+        diff.accounts.get(&sender).unwrap().present,
+        Some(AccountBasic {
+            balance: sender_account.balance + U256::from(10),
+            nonce: sender_account.nonce + 1,
+        })
+    );
+
+    storage.apply_state_diff(&diff);
+    assert_eq!(storage.basic(&contract_address).unwrap(), None);
+    assert_eq!(storage.code_hash(&contract_address).unwrap(), None);
+    assert!(!storage.has_storage(&contract_address).unwrap());
+}
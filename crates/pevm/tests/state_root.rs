@@ -0,0 +1,150 @@
+//! Test the state root calculation over block state diffs.
+
+use std::{num::NonZeroUsize, sync::Arc};
+
+use alloy_consensus::{Signed, TxLegacy};
+use alloy_primitives::{Address, B256, Bytes, Signature, TxKind, U256, bytes};
+use alloy_rpc_types_eth::{Block, BlockTransactions, Header};
+use alloy_trie::EMPTY_ROOT_HASH;
+use pevm::{
+    BlockStateDiff, Bytecodes, ChainState, EvmAccount, EvmCode, InMemoryStorage, Pevm,
+    StateRootProvider, Storage, calculate_state_root,
+    chain::{PevmChain, PevmEthereum},
+};
+use revm::{
+    database::PlainAccount,
+    primitives::KECCAK_EMPTY,
+    state::{AccountInfo, Bytecode},
+};
+use revme::cmd::statetest::merkle_trie::state_merkle_trie_root;
+
+pub mod common;
+
+// Calculate the state root with revm's reference implementation.
+fn reference_state_root(storage: &InMemoryStorage) -> B256 {
+    let accounts = storage
+        .addresses()
+        .unwrap()
+        .into_iter()
+        .map(|address| {
+            let basic = storage.basic(&address).unwrap().unwrap();
+            let account = PlainAccount {
+                info: AccountInfo {
+                    balance: basic.balance,
+                    nonce: basic.nonce,
+                    code_hash: storage.code_hash(&address).unwrap().unwrap_or(KECCAK_EMPTY),
+                    code: None,
+                    account_id: None,
+                },
+                storage: storage
+                    .storage_slots(&address)
+                    .unwrap()
+                    .into_iter()
+                    .collect(),
+            };
+            (address, account)
+        })
+        .collect::<Vec<_>>();
+    state_merkle_trie_root(
+        accounts
+            .iter()
+            .map(|(address, account)| (*address, account)),
+    )
+}
+
+#[test]
+fn empty_state() {
+    assert_eq!(
+        calculate_state_root(&InMemoryStorage::default(), &BlockStateDiff::default()).unwrap(),
+        EMPTY_ROOT_HASH
+    );
+}
+
+#[test]
+fn block_state_root() {
+    let chain = PevmEthereum::mainnet();
+    let mut accounts = (1..=10).map(common::mock_account).collect::<ChainState>();
+    let mut bytecodes = Bytecodes::default();
+    // CALLER SELFDESTRUCT
+    let destructible = Bytecode::new_raw(bytes!("33ff"));
+    let destructible_address = Address::repeat_byte(0x55);
+    accounts.insert(
+        destructible_address,
+        EvmAccount {
+            balance: U256::from(10),
+            nonce: 1,
+            code_hash: Some(destructible.hash_slow()),
+            storage: [(U256::from(1), U256::from(1))].into_iter().collect(),
+            ..EvmAccount::default()
+        },
+    );
+    bytecodes.insert(destructible.hash_slow(), EvmCode::from(destructible));
+    // An untouched account with storage.
+    accounts.insert(
+        Address::repeat_byte(0x66),
+        EvmAccount {
+            balance: U256::from(1),
+            storage: (0..100u64)
+                .map(|slot| (U256::from(slot), U256::from(slot + 1)))
+                .collect(),
+            ..EvmAccount::default()
+        },
+    );
+    let storage = InMemoryStorage::new(accounts, Arc::new(bytecodes), Default::default());
+
+    let txs = (1..=10)
+        .map(|i| {
+            let (sender, sender_account) = common::mock_account(i);
+            let to = if i == 1 {
+                destructible_address
+            } else {
+                Address::repeat_byte(i as u8)
+            };
+            chain.mock_tx(
+                Signed::new_unchecked(
+                    TxLegacy {
+                        chain_id: Some(chain.id()),
+                        nonce: sender_account.nonce,
+                        gas_price: 0,
+                        gas_limit: 100_000,
+                        to: TxKind::Call(to),
+                        value: U256::from(i),
+                        input: Bytes::default(),
+                    },
+                    Signature::new(U256::ZERO, U256::ZERO, false),
+                    B256::default(),
+                )
+                .into(),
+                sender,
+            )
+        })
+        .collect();
+    let block = Block {
+        header: Header {
+            inner: alloy_consensus::Header {
+                number: 17034870,
+                // Shanghai, to fully destroy the self-destructed contract.
+                timestamp: 1681338455,
+                gas_limit: 30_000_000,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        transactions: BlockTransactions::Full(txs),
+        ..Block::default()
+    };
+    let diff = Pevm::default()
+        .execute(&chain, &storage, &block, NonZeroUsize::MIN, true)
+        .unwrap()
+        .state_diff;
+    let state_root = calculate_state_root(&storage, &diff).unwrap();
+
+    let mut post_storage = storage.clone();
+    post_storage.apply_state_diff(&diff);
+    assert_eq!(state_root, reference_state_root(&post_storage));
+    assert_eq!(
+        state_root,
+        calculate_state_root(&post_storage, &BlockStateDiff::default()).unwrap()
+    );
+    assert_ne!(state_root, reference_state_root(&storage));
+}
@@ -0,0 +1,131 @@
+//! Test the statistics collected of blocks executed in parallel.
+
+use std::num::NonZeroUsize;
+
+use pevm::{FallbackReason, InMemoryStorage, Pevm, chain::PevmEthereum, execute_revm_sequential};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{Bytes, U256, hardfork::SpecId},
+};
+
+pub mod common;
+
+const BLOCK_SIZE: usize = 100;
+
+// Mock senders after the precompile addresses, which raw transfers cannot pay.
+const FIRST_SENDER: usize = 0x100;
+
+fn mock_storage() -> InMemoryStorage {
+    InMemoryStorage::new(
+        (FIRST_SENDER..FIRST_SENDER + BLOCK_SIZE)
+            .map(common::mock_account)
+            .chain([common::mock_account(0)])
+            .collect(),
+        Default::default(),
+        Default::default(),
+    )
+}
+
+// Raw transfers of a few senders to the other accounts, with a nonce gap at
+// [gap_idx] if set. Senders never receive, so the first transaction of each
+// sender is never lazily updated.
+fn mock_txs(gap_idx: Option<usize>) -> Vec<TxEnv> {
+    (0..BLOCK_SIZE)
+        .map(|i| {
+            let (caller, _) = common::mock_account(FIRST_SENDER + i % 10);
+            let (to, _) = common::mock_account(FIRST_SENDER + 10 + i % (BLOCK_SIZE - 10));
+            TxEnv {
+                caller,
+                nonce: (i / 10) as u64 + if gap_idx == Some(i) { 2 } else { 1 },
+                kind: TransactTo::Call(to),
+                value: U256::from(i),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: 1,
+                ..TxEnv::default()
+            }
+        })
+        .collect()
+}
+
+fn execute(pevm: &mut Pevm, txs: Vec<TxEnv>) {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    assert_eq!(
+        pevm.execute_revm_parallel(
+            &chain,
+            &storage,
+            SpecId::default(),
+            BlockEnv::default(),
+            txs.clone(),
+            NonZeroUsize::new(4).unwrap(),
+        ),
+        execute_revm_sequential(
+            &chain,
+            &storage,
+            SpecId::default(),
+            BlockEnv::default(),
+            txs
+        )
+    );
+}
+
+#[test]
+fn parallel_block_stats() {
+    let mut pevm = Pevm::default().with_stats();
+    execute(&mut pevm, mock_txs(None));
+
+    let stats = pevm.take_stats().unwrap();
+    assert_eq!(stats.block_size, BLOCK_SIZE);
+    assert_eq!(stats.concurrency_level, 4);
+    assert_eq!(stats.incarnations.len(), BLOCK_SIZE);
+    assert!(
+        stats
+            .incarnations
+            .iter()
+            .all(|incarnations| *incarnations > 0)
+    );
+    assert!(
+        stats
+            .dependencies
+            .iter()
+            .all(|(tx_idx, blocking_tx_idx)| blocking_tx_idx < tx_idx)
+    );
+    assert!(stats.memory_locations > 0);
+    assert_eq!(stats.fallback, None);
+    // The statistics are only taken once.
+    assert_eq!(pevm.take_stats(), None);
+}
+
+// A nonce gap in the first transaction of a sender keeps blocking until the
+// block falls back to sequential.
+#[test]
+fn fallback_stats() {
+    let mut pevm = Pevm::default().with_stats();
+    execute(&mut pevm, mock_txs(Some(5)));
+
+    let stats = pevm.take_stats().unwrap();
+    assert_eq!(stats.fallback, Some(FallbackReason::BlockingRetries));
+}
+
+// The other workers idle while the first transaction loops until it runs out
+// of gas, instead of spinning.
+#[test]
+fn idle_worker_stats() {
+    let mut txs = mock_txs(None);
+    txs[0].kind = TransactTo::Create;
+    // JUMPDEST PUSH1 0 JUMP
+    txs[0].data = Bytes::from_static(&[0x5b, 0x60, 0x00, 0x56]);
+    txs[0].gas_limit = 10_000_000;
+    let mut pevm = Pevm::default().with_stats();
+    execute(&mut pevm, txs);
+
+    let stats = pevm.take_stats().unwrap();
+    assert!(stats.idle_tasks > 0);
+}
+
+#[test]
+fn no_stats_by_default() {
+    let mut pevm = Pevm::default();
+    execute(&mut pevm, mock_txs(None));
+    assert_eq!(pevm.take_stats(), None);
+}
@@ -0,0 +1,351 @@
+//! Test the system contract calls before and after a block's transactions.
+
+use std::{num::NonZeroUsize, sync::Arc, thread};
+
+use alloy_consensus::{Signed, TxLegacy};
+use alloy_eips::{
+    eip2935::{HISTORY_SERVE_WINDOW, HISTORY_STORAGE_ADDRESS, HISTORY_STORAGE_CODE},
+    eip4788::{BEACON_ROOTS_ADDRESS, BEACON_ROOTS_CODE},
+    eip6110::{DEPOSIT_REQUEST_TYPE, MAINNET_DEPOSIT_CONTRACT_ADDRESS},
+    eip7002::{WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS, WITHDRAWAL_REQUEST_PREDEPLOY_CODE},
+    eip7251::{CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS, CONSOLIDATION_REQUEST_PREDEPLOY_CODE},
+    eip7685::Requests,
+};
+use alloy_primitives::{Address, B256, Bytes, Signature, TxKind, U256, keccak256};
+use alloy_rpc_types_eth::{Block, BlockTransactions, Header};
+use pevm::{
+    Bytecodes, ChainState, EvmAccount, EvmCode, InMemoryStorage, Pevm, PevmError,
+    chain::{PevmChain, PevmEthereum},
+};
+use revm::state::Bytecode;
+
+pub mod common;
+
+const CANCUN_TIMESTAMP: u64 = 1710338135;
+const PRAGUE_TIMESTAMP: u64 = 1746612311;
+
+// The beacon roots contract stores roots in a ring buffer of this length.
+const BEACON_ROOTS_BUFFER_LENGTH: u64 = 8191;
+
+// The sender of the mock transactions, after the precompile addresses.
+const SENDER: usize = 0x100;
+
+fn mock_storage(contracts: &[(Address, &Bytes)]) -> InMemoryStorage {
+    let mut accounts: ChainState = [common::mock_account(SENDER)].into_iter().collect();
+    let mut bytecodes = Bytecodes::default();
+    for (address, code) in contracts {
+        let code = Bytecode::new_raw((*code).clone());
+        let code_hash = code.hash_slow();
+        accounts.insert(
+            *address,
+            EvmAccount {
+                nonce: 1,
+                code_hash: Some(code_hash),
+                ..EvmAccount::default()
+            },
+        );
+        bytecodes.insert(code_hash, EvmCode::from(code));
+    }
+    InMemoryStorage::new(accounts, Arc::new(bytecodes), Default::default())
+}
+
+fn mock_block(header: alloy_consensus::Header) -> Block<alloy_rpc_types_eth::Transaction> {
+    Block {
+        header: Header {
+            inner: header,
+            ..Default::default()
+        },
+        transactions: BlockTransactions::Full(Vec::new()),
+        ..Block::default()
+    }
+}
+
+fn mock_prague_block(
+    txs: Vec<alloy_rpc_types_eth::Transaction>,
+) -> Block<alloy_rpc_types_eth::Transaction> {
+    Block {
+        transactions: BlockTransactions::Full(txs),
+        ..mock_block(alloy_consensus::Header {
+            number: 1,
+            timestamp: PRAGUE_TIMESTAMP,
+            gas_limit: 30_000_000,
+            excess_blob_gas: Some(0),
+            parent_beacon_block_root: Some(B256::repeat_byte(0x42)),
+            ..Default::default()
+        })
+    }
+}
+
+// The system contracts that Prague blocks call.
+fn prague_contracts() -> Vec<(Address, &'static Bytes)> {
+    vec![
+        (BEACON_ROOTS_ADDRESS, &BEACON_ROOTS_CODE),
+        (HISTORY_STORAGE_ADDRESS, &HISTORY_STORAGE_CODE),
+        (
+            WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS,
+            &WITHDRAWAL_REQUEST_PREDEPLOY_CODE,
+        ),
+        (
+            CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS,
+            &CONSOLIDATION_REQUEST_PREDEPLOY_CODE,
+        ),
+    ]
+}
+
+// Execute [block] with and without pipelining, which must agree.
+fn execute(
+    storage: &InMemoryStorage,
+    block: &Block<alloy_rpc_types_eth::Transaction>,
+) -> Result<Requests, PevmError<PevmEthereum>> {
+    let chain = PevmEthereum::mainnet();
+    let result = Pevm::default().execute(&chain, storage, block, NonZeroUsize::MIN, true);
+    assert_eq!(
+        Pevm::default()
+            .execute_blocks(
+                &chain,
+                storage,
+                std::slice::from_ref(block),
+                NonZeroUsize::new(2).unwrap(),
+            )
+            .map(|mut results| results.pop().unwrap()),
+        result
+    );
+    result.map(|result| result.requests)
+}
+
+#[test]
+fn beacon_roots_contract() {
+    let storage = mock_storage(&[(BEACON_ROOTS_ADDRESS, &BEACON_ROOTS_CODE)]);
+    let parent_beacon_block_root = B256::repeat_byte(0x42);
+    let block = mock_block(alloy_consensus::Header {
+        number: 1,
+        timestamp: CANCUN_TIMESTAMP,
+        gas_limit: 30_000_000,
+        excess_blob_gas: Some(0),
+        parent_beacon_block_root: Some(parent_beacon_block_root),
+        ..Default::default()
+    });
+
+    let concurrency_level = thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let result = Pevm::default()
+        .execute(
+            &PevmEthereum::mainnet(),
+            &storage,
+            &block,
+            concurrency_level,
+            false,
+        )
+        .unwrap();
+
+    let contract = result
+        .pre_block_state
+        .get(&BEACON_ROOTS_ADDRESS)
+        .cloned()
+        .flatten()
+        .unwrap();
+    let timestamp_index = U256::from(CANCUN_TIMESTAMP % BEACON_ROOTS_BUFFER_LENGTH);
+    assert_eq!(
+        contract.storage.get(&timestamp_index),
+        Some(&U256::from(CANCUN_TIMESTAMP))
+    );
+    assert_eq!(
+        contract
+            .storage
+            .get(&(timestamp_index + U256::from(BEACON_ROOTS_BUFFER_LENGTH))),
+        Some(&parent_beacon_block_root.into())
+    );
+    // Neither the system caller nor the beneficiary is touched.
+    assert_eq!(result.pre_block_state.len(), 1);
+    assert!(result.post_block_state.is_empty());
+    assert!(result.requests.is_empty());
+}
+
+#[test]
+fn prague_system_contracts() {
+    let storage = mock_storage(&prague_contracts());
+    let number = 10_000;
+    let parent_hash = B256::repeat_byte(0x69);
+    let block = mock_block(alloy_consensus::Header {
+        number,
+        parent_hash,
+        timestamp: PRAGUE_TIMESTAMP,
+        gas_limit: 30_000_000,
+        excess_blob_gas: Some(0),
+        parent_beacon_block_root: Some(B256::repeat_byte(0x42)),
+        ..Default::default()
+    });
+    common::test_execute_alloy(
+        &PevmEthereum::mainnet(),
+        &storage,
+        block.clone(),
+        &[],
+        false,
+    );
+
+    let result = Pevm::default()
+        .execute(
+            &PevmEthereum::mainnet(),
+            &storage,
+            &block,
+            NonZeroUsize::MIN,
+            true,
+        )
+        .unwrap();
+
+    let history = result
+        .pre_block_state
+        .get(&HISTORY_STORAGE_ADDRESS)
+        .cloned()
+        .flatten()
+        .unwrap();
+    assert_eq!(
+        history
+            .storage
+            .get(&U256::from((number - 1) % HISTORY_SERVE_WINDOW as u64)),
+        Some(&parent_hash.into())
+    );
+    assert!(result.pre_block_state.contains_key(&BEACON_ROOTS_ADDRESS));
+    // The request queues are empty.
+    assert!(result.requests.is_empty());
+}
+
+#[test]
+fn missing_system_contracts() {
+    // Calls to the beacon roots and history contracts are skipped without
+    // their code.
+    let block = mock_block(alloy_consensus::Header {
+        number: 1,
+        timestamp: CANCUN_TIMESTAMP,
+        gas_limit: 30_000_000,
+        excess_blob_gas: Some(0),
+        parent_beacon_block_root: Some(B256::repeat_byte(0x42)),
+        ..Default::default()
+    });
+    let result = Pevm::default()
+        .execute(
+            &PevmEthereum::mainnet(),
+            &mock_storage(&[]),
+            &block,
+            NonZeroUsize::MIN,
+            false,
+        )
+        .unwrap();
+    assert!(result.pre_block_state.is_empty());
+
+    // Blocks are invalid without the code of the request contracts.
+    let storage = mock_storage(&[
+        (BEACON_ROOTS_ADDRESS, &BEACON_ROOTS_CODE),
+        (HISTORY_STORAGE_ADDRESS, &HISTORY_STORAGE_CODE),
+        (
+            WITHDRAWAL_REQUEST_PREDEPLOY_ADDRESS,
+            &WITHDRAWAL_REQUEST_PREDEPLOY_CODE,
+        ),
+    ]);
+    assert_eq!(
+        execute(&storage, &mock_prague_block(Vec::new())),
+        Err(PevmError::MissingSystemContract(
+            CONSOLIDATION_REQUEST_PREDEPLOY_ADDRESS
+        ))
+    );
+}
+
+// ABI-encode the bytes fields of a deposit log.
+fn deposit_log_data(fields: &[&[u8]; 5]) -> Vec<u8> {
+    let mut head = Vec::new();
+    let mut tail = Vec::new();
+    for field in fields {
+        head.extend(U256::from(32 * fields.len() + tail.len()).to_be_bytes::<32>());
+        tail.extend(U256::from(field.len()).to_be_bytes::<32>());
+        tail.extend(*field);
+        tail.resize(tail.len().next_multiple_of(32), 0);
+    }
+    head.extend(tail);
+    head
+}
+
+// A call to the deposit contract, which logs its call data as a deposit log.
+fn mock_deposit(
+    chain: &PevmEthereum,
+    nonce: u64,
+    log_data: Vec<u8>,
+) -> alloy_rpc_types_eth::Transaction {
+    chain.mock_tx(
+        Signed::new_unchecked(
+            TxLegacy {
+                chain_id: Some(chain.id()),
+                nonce,
+                gas_price: 1,
+                gas_limit: 100_000,
+                to: TxKind::Call(MAINNET_DEPOSIT_CONTRACT_ADDRESS),
+                value: U256::ZERO,
+                input: log_data.into(),
+            },
+            Signature::new(U256::ZERO, U256::ZERO, false),
+            B256::default(),
+        )
+        .into(),
+        common::mock_account(SENDER).0,
+    )
+}
+
+#[test]
+fn deposit_requests() {
+    let chain = PevmEthereum::mainnet();
+    // CALLDATASIZE PUSH0 PUSH0 CALLDATACOPY PUSH32 <topic> CALLDATASIZE PUSH0
+    // LOG1 STOP
+    let deposit_code: Bytes = [&[0x36, 0x5f, 0x5f, 0x37, 0x7f][..]]
+        .into_iter()
+        .chain([
+            keccak256("DepositEvent(bytes,bytes,bytes,bytes,bytes)").as_slice(),
+            &[0x36, 0x5f, 0xa1, 0x00],
+        ])
+        .flatten()
+        .copied()
+        .collect();
+    let mut contracts = prague_contracts();
+    contracts.push((MAINNET_DEPOSIT_CONTRACT_ADDRESS, &deposit_code));
+    let storage = mock_storage(&contracts);
+
+    // The pubkey, withdrawal credentials, amount, signature and index.
+    let deposits = (0..2u8)
+        .map(|i| {
+            [
+                vec![i + 1; 48],
+                vec![i + 2; 32],
+                vec![i + 3; 8],
+                vec![i + 4; 96],
+                vec![i; 8],
+            ]
+        })
+        .collect::<Vec<_>>();
+    let (_, account) = common::mock_account(SENDER);
+    let block = mock_prague_block(
+        deposits
+            .iter()
+            .enumerate()
+            .map(|(i, fields)| {
+                mock_deposit(
+                    &chain,
+                    account.nonce + i as u64,
+                    deposit_log_data(&fields.each_ref().map(Vec::as_slice)),
+                )
+            })
+            .collect(),
+    );
+    let mut expected_requests = Requests::default();
+    expected_requests.push_request_with_type(
+        DEPOSIT_REQUEST_TYPE,
+        deposits.iter().flatten().flatten().copied(),
+    );
+    assert_eq!(execute(&storage, &block), Ok(expected_requests));
+
+    // A deposit log with a short pubkey invalidates the block.
+    let mut fields = deposits[0].clone();
+    fields[0].pop();
+    let block = mock_prague_block(vec![mock_deposit(
+        &chain,
+        account.nonce,
+        deposit_log_data(&fields.each_ref().map(Vec::as_slice)),
+    )]);
+    assert_eq!(execute(&storage, &block), Err(PevmError::InvalidDepositLog));
+}
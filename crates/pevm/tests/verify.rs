@@ -0,0 +1,249 @@
+//! Test verifying parallel executions against sequential executions.
+
+use std::{
+    convert::Infallible,
+    num::NonZeroUsize,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use pevm::{
+    AccountBasic, Divergence, DivergenceLocation, EvmCode, InMemoryStorage, Pevm, PevmMode,
+    PevmTxExecutionResult, Storage, VerifyMode, chain::PevmEthereum, execute_revm_sequential,
+};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::{Address, B256, U256, hardfork::SpecId},
+};
+
+pub mod common;
+
+const BLOCK_SIZE: usize = 100;
+
+// Mock senders after the precompile addresses, which raw transfers cannot pay.
+const FIRST_SENDER: usize = 0x100;
+
+fn mock_storage() -> InMemoryStorage {
+    InMemoryStorage::new(
+        (FIRST_SENDER..FIRST_SENDER + 10)
+            .map(common::mock_account)
+            .chain([common::mock_account(0)])
+            .collect(),
+        Default::default(),
+        Default::default(),
+    )
+}
+
+// Raw transfers between a few senders.
+fn mock_txs() -> Vec<TxEnv> {
+    (0..BLOCK_SIZE)
+        .map(|i| TxEnv {
+            caller: common::mock_account(FIRST_SENDER + i % 10).0,
+            nonce: (i / 10) as u64 + 1,
+            kind: TransactTo::Call(common::mock_account(FIRST_SENDER + (i + 1) % 10).0),
+            value: U256::from(i),
+            gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+            gas_price: 1,
+            ..TxEnv::default()
+        })
+        .collect()
+}
+
+fn sequential_results() -> Vec<PevmTxExecutionResult> {
+    execute_revm_sequential(
+        &PevmEthereum::mainnet(),
+        &mock_storage(),
+        SpecId::default(),
+        BlockEnv::default(),
+        mock_txs(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn verified_blocks() {
+    let sequential_results = sequential_results();
+    for verify_mode in [
+        VerifyMode::Always,
+        VerifyMode::Sampled(NonZeroUsize::new(2).unwrap()),
+    ] {
+        let mut pevm = Pevm::default().with_verify_mode(verify_mode);
+        for _ in 0..3 {
+            assert_eq!(
+                pevm.execute_revm_parallel(
+                    &PevmEthereum::mainnet(),
+                    &mock_storage(),
+                    SpecId::default(),
+                    BlockEnv::default(),
+                    mock_txs(),
+                    NonZeroUsize::new(4).unwrap(),
+                ),
+                Ok(sequential_results.clone())
+            );
+        }
+        assert!(pevm.take_divergences().is_empty());
+    }
+}
+
+// Excluded transactions must fail the same way in both executions.
+#[test]
+fn verified_excluded_txs() {
+    let mut txs = mock_txs();
+    // A nonce gap after the last transaction of the sender.
+    txs.push(TxEnv {
+        nonce: (BLOCK_SIZE / 10) as u64 + 2,
+        ..txs[BLOCK_SIZE - 1].clone()
+    });
+    let mut pevm = Pevm::with_mode(PevmMode::Build).with_verify_mode(VerifyMode::Always);
+    assert_eq!(
+        pevm.execute_revm_parallel(
+            &PevmEthereum::mainnet(),
+            &mock_storage(),
+            SpecId::default(),
+            BlockEnv::default(),
+            txs,
+            NonZeroUsize::new(4).unwrap(),
+        ),
+        Ok(sequential_results())
+    );
+    assert!(pevm.take_divergences().is_empty());
+}
+
+// A storage whose account balance grows by one wei after it is first read,
+// for the sequential execution to diverge from the parallel one before it.
+#[derive(Debug)]
+struct DriftingStorage {
+    inner: InMemoryStorage,
+    address: Address,
+    read: AtomicBool,
+}
+
+impl Storage for DriftingStorage {
+    type Error = Infallible;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        let mut basic = self.inner.basic(address)?;
+        if *address == self.address
+            && self.read.swap(true, Ordering::Relaxed)
+            && let Some(basic) = &mut basic
+        {
+            basic.balance += U256::from(1);
+        }
+        Ok(basic)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.inner.code_hash(address)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.inner.code_by_hash(code_hash)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.inner.has_storage(address)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        self.inner.storage(address, index)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.inner.block_hash(number)
+    }
+}
+
+// Diverged blocks return the sequential results, and report the divergence.
+#[test]
+fn diverged_block() {
+    let txs = mock_txs()[..1].to_vec();
+    let recipient = txs[0].kind.to().copied().unwrap();
+    let storage = DriftingStorage {
+        inner: mock_storage(),
+        address: recipient,
+        read: AtomicBool::new(false),
+    };
+    let mut pevm = Pevm::default().with_verify_mode(VerifyMode::Always);
+    let tx_results = pevm
+        .execute_revm_parallel(
+            &PevmEthereum::mainnet(),
+            &storage,
+            SpecId::default(),
+            BlockEnv::default(),
+            txs,
+            NonZeroUsize::new(4).unwrap(),
+        )
+        .unwrap();
+
+    // The sequential execution reads the drifted balance.
+    let balance = common::mock_account(FIRST_SENDER + 1).1.balance;
+    assert_eq!(
+        tx_results[0].state[&recipient].as_ref().unwrap().balance,
+        balance + U256::from(1)
+    );
+    let divergences = pevm.take_divergences();
+    assert_eq!(divergences.len(), 1);
+    assert_eq!(divergences[0].tx_idx, Some(0));
+    assert_eq!(
+        divergences[0].location,
+        DivergenceLocation::Balance(recipient)
+    );
+    assert!(pevm.take_divergences().is_empty());
+}
+
+#[test]
+fn first_divergence() {
+    let sequential_results = sequential_results();
+    assert_eq!(
+        Divergence::find(&sequential_results, &sequential_results),
+        None
+    );
+
+    let sender = common::mock_account(FIRST_SENDER + 3).0;
+    let mut parallel_results = sequential_results.clone();
+    let account = parallel_results[13]
+        .state
+        .get_mut(&sender)
+        .unwrap()
+        .as_mut()
+        .unwrap();
+    account.storage.insert(U256::from(1), U256::from(2));
+    account.balance += U256::from(1);
+    let divergence = Divergence::find(&parallel_results, &sequential_results).unwrap();
+    assert_eq!(divergence.tx_idx, Some(13));
+    // Balances are compared before storage.
+    assert_eq!(divergence.location, DivergenceLocation::Balance(sender));
+    let balance = sequential_results[13].state[&sender]
+        .as_ref()
+        .unwrap()
+        .balance;
+    assert_eq!(
+        divergence.parallel,
+        format!("{:?}", balance + U256::from(1))
+    );
+    assert_eq!(divergence.sequential, format!("{balance:?}"));
+
+    // Earlier transactions diverge first.
+    let sender = common::mock_account(FIRST_SENDER + 5).0;
+    parallel_results[5]
+        .state
+        .get_mut(&sender)
+        .unwrap()
+        .as_mut()
+        .unwrap()
+        .storage
+        .insert(U256::from(7), U256::from(8));
+    let divergence = Divergence::find(&parallel_results, &sequential_results).unwrap();
+    assert_eq!(
+        divergence,
+        Divergence {
+            tx_idx: Some(5),
+            location: DivergenceLocation::Storage(sender, U256::from(7)),
+            parallel: String::from("Some(8)"),
+            sequential: String::from("None"),
+        }
+    );
+    assert_eq!(
+        divergence.to_string(),
+        format!("tx #5 storage 7 of {sender}: parallel Some(8), sequential None")
+    );
+}
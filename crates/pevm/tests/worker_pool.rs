@@ -0,0 +1,232 @@
+//! Test executing many blocks on the worker threads and memory that pevm keeps
+//! across them.
+
+use std::{
+    convert::Infallible,
+    num::NonZeroUsize,
+    sync::atomic::{AtomicUsize, Ordering},
+    thread,
+    time::{Duration, Instant},
+};
+
+use alloy_consensus::{Signed, TxLegacy};
+use alloy_primitives::{Address, B256, Bytes, Signature, TxKind};
+use alloy_rpc_types_eth::{Block, BlockTransactions, Header};
+use pevm::{
+    AccountBasic, EvmCode, InMemoryStorage, Pevm, Storage,
+    chain::{PevmChain, PevmEthereum},
+    execute_revm_sequential,
+};
+use revm::{
+    context::{BlockEnv, TransactTo, TxEnv},
+    primitives::U256,
+};
+
+pub mod common;
+
+// Mock senders after the precompile addresses, which raw transfers cannot pay.
+const FIRST_SENDER: usize = 0x100;
+const NUM_SENDERS: usize = 100;
+
+fn mock_storage() -> InMemoryStorage {
+    InMemoryStorage::new(
+        (FIRST_SENDER..FIRST_SENDER + NUM_SENDERS)
+            .map(common::mock_account)
+            .chain([common::mock_account(0)]) // Beneficiary
+            .collect(),
+        Default::default(),
+        Default::default(),
+    )
+}
+
+// Each sender sends several transfers to the next sender, for blocks to have
+// both dependencies and independent transactions.
+fn mock_txs(block_size: usize) -> Vec<TxEnv> {
+    (0..block_size)
+        .map(|i| {
+            let (caller, account) = common::mock_account(FIRST_SENDER + i % NUM_SENDERS);
+            let (recipient, _) = common::mock_account(FIRST_SENDER + (i + 1) % NUM_SENDERS);
+            TxEnv {
+                caller,
+                nonce: account.nonce + (i / NUM_SENDERS) as u64,
+                kind: TransactTo::Call(recipient),
+                value: U256::from(1),
+                gas_limit: common::RAW_TRANSFER_GAS_LIMIT,
+                gas_price: 1,
+                ..TxEnv::default()
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn reuse_workers_across_blocks() {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    let txs = mock_txs(1_000);
+    let sequential_results = execute_revm_sequential(
+        &chain,
+        &storage,
+        Default::default(),
+        BlockEnv::default(),
+        txs.clone(),
+    );
+
+    // The workers grow with the concurrency level, and blocks that need fewer
+    // of them run on a subset.
+    let mut pevm = Pevm::default().with_pinned_workers();
+    for concurrency_level in [2, 8, 4, 1, 16, 8] {
+        assert_eq!(
+            pevm.execute_revm_parallel(
+                &chain,
+                &storage,
+                Default::default(),
+                BlockEnv::default(),
+                txs.clone(),
+                NonZeroUsize::new(concurrency_level).unwrap(),
+            ),
+            sequential_results
+        );
+    }
+}
+
+#[test]
+fn recycle_memory_across_blocks() {
+    let chain = PevmEthereum::mainnet();
+    let storage = mock_storage();
+    // Blocks reuse the cleared memory and schedulers of larger and smaller
+    // blocks before them, which must not leak into their results.
+    let mut pevm = Pevm::default();
+    for block_size in [1_000, 10, 500, 1_000, 1, 200, 1_000] {
+        let txs = mock_txs(block_size);
+        let sequential_results = execute_revm_sequential(
+            &chain,
+            &storage,
+            Default::default(),
+            BlockEnv::default(),
+            txs.clone(),
+        );
+        assert_eq!(
+            pevm.execute_revm_parallel(
+                &chain,
+                &storage,
+                Default::default(),
+                BlockEnv::default(),
+                txs,
+                NonZeroUsize::new(4).unwrap(),
+            ),
+            sequential_results
+        );
+    }
+}
+
+// A storage that counts the reads on the worker threads, and holds the first
+// read of [Self::sender] by the calling thread until the workers read too.
+#[derive(Debug)]
+struct WorkerReadsStorage {
+    inner: InMemoryStorage,
+    sender: Address,
+    worker_reads: AtomicUsize,
+}
+
+impl Storage for WorkerReadsStorage {
+    type Error = Infallible;
+
+    fn basic(&self, address: &Address) -> Result<Option<AccountBasic>, Self::Error> {
+        if thread::current()
+            .name()
+            .is_some_and(|name| name.starts_with("pevm-worker"))
+        {
+            self.worker_reads.fetch_add(1, Ordering::Relaxed);
+        } else if *address == self.sender {
+            let started_at = Instant::now();
+            while self.worker_reads.load(Ordering::Relaxed) == 0
+                && started_at.elapsed() < Duration::from_secs(10)
+            {
+                thread::sleep(Duration::from_millis(1));
+            }
+        }
+        self.inner.basic(address)
+    }
+
+    fn code_hash(&self, address: &Address) -> Result<Option<B256>, Self::Error> {
+        self.inner.code_hash(address)
+    }
+
+    fn code_by_hash(&self, code_hash: &B256) -> Result<Option<EvmCode>, Self::Error> {
+        self.inner.code_by_hash(code_hash)
+    }
+
+    fn has_storage(&self, address: &Address) -> Result<bool, Self::Error> {
+        self.inner.has_storage(address)
+    }
+
+    fn storage(&self, address: &Address, index: &U256) -> Result<U256, Self::Error> {
+        self.inner.storage(address, index)
+    }
+
+    fn block_hash(&self, number: &u64) -> Result<B256, Self::Error> {
+        self.inner.block_hash(number)
+    }
+}
+
+#[test]
+fn prefetch_sequential_blocks_on_workers() {
+    let chain = PevmEthereum::mainnet();
+    let txs = mock_txs(100);
+    let block = Block {
+        header: Header {
+            inner: alloy_consensus::Header {
+                number: 1,
+                gas_limit: 30_000_000,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        transactions: BlockTransactions::Full(
+            txs.iter()
+                .map(|tx| {
+                    chain.mock_tx(
+                        Signed::new_unchecked(
+                            TxLegacy {
+                                chain_id: Some(chain.id()),
+                                nonce: tx.nonce,
+                                gas_price: 1,
+                                gas_limit: tx.gas_limit,
+                                to: TxKind::Call(*tx.kind.to().unwrap()),
+                                value: tx.value,
+                                input: Bytes::default(),
+                            },
+                            Signature::new(U256::ZERO, U256::ZERO, false),
+                            B256::default(),
+                        )
+                        .into(),
+                        tx.caller,
+                    )
+                })
+                .collect(),
+        ),
+        ..Block::default()
+    };
+    let storage = WorkerReadsStorage {
+        inner: mock_storage(),
+        sender: txs[0].caller,
+        worker_reads: AtomicUsize::new(0),
+    };
+
+    // The calling thread executes the block while the workers prefetch it.
+    let concurrency_level = NonZeroUsize::new(4).unwrap();
+    let prefetched_result = Pevm::default().with_prefetching().execute(
+        &chain,
+        &storage,
+        &block,
+        concurrency_level,
+        true,
+    );
+    assert!(storage.worker_reads.load(Ordering::Relaxed) > 0);
+    assert!(prefetched_result.is_ok());
+    assert_eq!(
+        prefetched_result,
+        Pevm::default().execute(&chain, &mock_storage(), &block, concurrency_level, true)
+    );
+}